@@ -1987,7 +1987,7 @@ fn resolve_home_dir() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from("/tmp"))
 }
 
-fn resolve_flowdesk_home_dir() -> PathBuf {
+pub(crate) fn resolve_flowdesk_home_dir() -> PathBuf {
     resolve_home_dir().join(".flow-desk")
 }
 
@@ -0,0 +1,372 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::config_diff::{diff_config_file, parse_env_line, should_mask, ConfigFormat, MASKED_VALUE};
+
+/// One file to apply, optionally narrowed to flattened keys such as `server.port` or
+/// `hosts[1]`. Selecting a key also selects everything under it; arrays apply as a whole.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConfigSelection {
+    pub repo_key: String,
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<String>>,
+}
+
+/// A key both the workspace and the release branch changed differently since the merge base.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyConflict {
+    pub key: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+    pub masked: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeOutcome {
+    /// The workspace already has the release changes.
+    Unchanged,
+    /// `keys` lists the flattened keys that changed; `reformatted` means the file was
+    /// re-serialized (key-level merge of JSON/YAML/TOML), which drops comments and layout.
+    Merged { content: String, keys: Vec<String>, reformatted: bool },
+    /// Nothing is written. `diff3` holds the line-level merge with conflict markers, if any.
+    Conflict { reason: String, keys: Vec<KeyConflict>, diff3: Option<String> },
+    Skipped(String),
+}
+
+/// Three-way merge of one config file: `base` from the merge base of the stable and release
+/// branches, `ours` from the workspace, `theirs` from the release branch. `None` means the
+/// file does not exist on that side.
+///
+/// Without `keys` the whole file is merged line by line with `git merge-file`, which keeps
+/// comments and layout. With `keys` only those keys are merged, on the parsed values.
+pub fn merge_config_file(
+    file: &str,
+    base: Option<&str>,
+    ours: Option<&str>,
+    theirs: Option<&str>,
+    keys: Option<&[String]>,
+) -> Result<MergeOutcome, String> {
+    let Some(theirs) = theirs else {
+        return Ok(MergeOutcome::Skipped(
+            "Deleted on the release branch; remove it from the workspace by hand if intended".to_string(),
+        ));
+    };
+    match keys.filter(|keys| !keys.is_empty()) {
+        Some(keys) => merge_selected_keys(file, base, ours, theirs, keys),
+        None => merge_whole_file(file, base, ours, theirs),
+    }
+}
+
+fn merge_whole_file(file: &str, base: Option<&str>, ours: Option<&str>, theirs: &str) -> Result<MergeOutcome, String> {
+    let Some(ours) = ours else {
+        return Ok(match base {
+            None => MergeOutcome::Merged {
+                content: theirs.to_string(),
+                keys: changed_keys(file, "", theirs),
+                reformatted: false,
+            },
+            Some(base) if base == theirs => MergeOutcome::Skipped("Deleted in the workspace".to_string()),
+            Some(_) => MergeOutcome::Conflict {
+                reason: "Deleted in the workspace but changed on the release branch".to_string(),
+                keys: Vec::new(),
+                diff3: None,
+            },
+        });
+    };
+    if ours == theirs {
+        return Ok(MergeOutcome::Unchanged);
+    }
+    let (clean, merged) = git_merge_file(ours, base.unwrap_or(""), theirs)?;
+    if !clean {
+        return Ok(MergeOutcome::Conflict {
+            reason: "The workspace and the release branch changed the same lines".to_string(),
+            keys: key_conflicts(file, base, ours, theirs),
+            diff3: Some(merged),
+        });
+    }
+    if merged == ours {
+        return Ok(MergeOutcome::Unchanged);
+    }
+    Ok(MergeOutcome::Merged {
+        keys: changed_keys(file, ours, &merged),
+        content: merged,
+        reformatted: false,
+    })
+}
+
+fn merge_selected_keys(
+    file: &str,
+    base: Option<&str>,
+    ours: Option<&str>,
+    theirs: &str,
+    keys: &[String],
+) -> Result<MergeOutcome, String> {
+    let format = ConfigFormat::detect(file)
+        .ok_or_else(|| format!("Key selection needs a JSON, YAML, TOML or .env file: {}", file))?;
+    let parse = |content: Option<&str>| format.parse(content.unwrap_or(""));
+    let (base_value, ours_value, theirs_value) = (parse(base)?, parse(ours)?, parse(Some(theirs))?);
+
+    let mut merge = ValueMerge { keys: Some(keys), applied: Vec::new(), conflicts: Vec::new() };
+    let merged = merge
+        .merge("", Some(&base_value), Some(&ours_value), Some(&theirs_value))
+        .unwrap_or_else(|| Value::Object(Map::new()));
+    if !merge.conflicts.is_empty() {
+        return Ok(MergeOutcome::Conflict {
+            reason: "Selected keys were changed differently in the workspace".to_string(),
+            keys: merge.conflicts,
+            diff3: None,
+        });
+    }
+    if merge.applied.is_empty() {
+        return Ok(MergeOutcome::Unchanged);
+    }
+    let content = match format {
+        ConfigFormat::Env => write_env(ours.unwrap_or(""), &merged),
+        ConfigFormat::Json => serde_json::to_string_pretty(&merged)
+            .map(|content| content + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", file, e))?,
+        ConfigFormat::Yaml => serde_yaml::to_string(&merged).map_err(|e| format!("Failed to write {}: {}", file, e))?,
+        ConfigFormat::Toml => toml::to_string_pretty(&merged).map_err(|e| format!("Failed to write {}: {}", file, e))?,
+    };
+    Ok(MergeOutcome::Merged {
+        content,
+        keys: merge.applied,
+        reformatted: format != ConfigFormat::Env,
+    })
+}
+
+struct ValueMerge<'a> {
+    keys: Option<&'a [String]>,
+    applied: Vec<String>,
+    conflicts: Vec<KeyConflict>,
+}
+
+impl ValueMerge<'_> {
+    fn selected(&self, path: &str) -> bool {
+        self.keys.is_none_or(|keys| {
+            keys.iter().any(|key| {
+                key == path
+                    || [".", "["].iter().any(|separator| {
+                        key.starts_with(&format!("{}{}", path, separator)) || path.starts_with(&format!("{}{}", key, separator))
+                    })
+            })
+        })
+    }
+
+    /// Objects merge per key (workspace order first); everything else is a leaf.
+    fn merge(&mut self, path: &str, base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+        let objects = [base, ours, theirs];
+        if objects.iter().any(|value| value.is_some_and(Value::is_object))
+            && objects.iter().all(|value| value.is_none_or(Value::is_object))
+        {
+            let empty = Map::new();
+            let [base_map, ours_map, theirs_map] = objects.map(|value| value.and_then(Value::as_object).unwrap_or(&empty));
+            let mut seen = BTreeSet::new();
+            let mut merged = Map::new();
+            for key in ours_map.keys().chain(theirs_map.keys()).chain(base_map.keys()) {
+                if !seen.insert(key.clone()) {
+                    continue;
+                }
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                if let Some(value) = self.merge(&child, base_map.get(key), ours_map.get(key), theirs_map.get(key)) {
+                    merged.insert(key.clone(), value);
+                }
+            }
+            return if ours.is_none() && merged.is_empty() { None } else { Some(Value::Object(merged)) };
+        }
+
+        if base == theirs || ours == theirs || !self.selected(path) {
+            return ours.cloned();
+        }
+        if base == ours {
+            self.applied.push(path.to_string());
+            return theirs.cloned();
+        }
+        let text = |value: Option<&Value>| value.map(Value::to_string);
+        let (base, ours_text, theirs) = (text(base), text(ours), text(theirs));
+        let masked = should_mask(path, &[base.as_ref(), ours_text.as_ref(), theirs.as_ref()]);
+        let shown = |value: Option<String>| value.map(|value| if masked { MASKED_VALUE.to_string() } else { value });
+        self.conflicts.push(KeyConflict {
+            key: path.to_string(),
+            base: shown(base),
+            ours: shown(ours_text),
+            theirs: shown(theirs),
+            masked,
+        });
+        ours.cloned()
+    }
+}
+
+/// Key-level view of a line-level conflict, when the file format can be parsed.
+fn key_conflicts(file: &str, base: Option<&str>, ours: &str, theirs: &str) -> Vec<KeyConflict> {
+    let Some(format) = ConfigFormat::detect(file) else { return Vec::new() };
+    let parsed = [base.unwrap_or(""), ours, theirs].map(|content| format.parse(content));
+    let [Ok(base), Ok(ours), Ok(theirs)] = parsed else { return Vec::new() };
+    let mut merge = ValueMerge { keys: None, applied: Vec::new(), conflicts: Vec::new() };
+    merge.merge("", Some(&base), Some(&ours), Some(&theirs));
+    merge.conflicts
+}
+
+fn changed_keys(file: &str, before: &str, after: &str) -> Vec<String> {
+    diff_config_file(file, Some(before), Some(after))
+        .changes
+        .into_iter()
+        .map(|change| change.key)
+        .collect()
+}
+
+/// Rewrite `.env` lines in place so comments, order and untouched lines survive.
+fn write_env(ours: &str, merged: &Value) -> String {
+    let empty = Map::new();
+    let merged = merged.as_object().unwrap_or(&empty);
+    let value_of = |key: &str| merged.get(key).map(|value| value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()));
+    let mut seen = BTreeSet::new();
+    let mut lines = Vec::new();
+    for line in ours.lines() {
+        let Some((key, current)) = parse_env_line(line) else {
+            lines.push(line.to_string());
+            continue;
+        };
+        seen.insert(key.clone());
+        match value_of(&key) {
+            Some(value) if value == current => lines.push(line.to_string()),
+            Some(value) => {
+                let export = if line.trim_start().starts_with("export ") { "export " } else { "" };
+                lines.push(format!("{}{}={}", export, key, quote_env(&value)));
+            }
+            None => {}
+        }
+    }
+    for key in merged.keys().filter(|key| !seen.contains(*key)) {
+        lines.push(format!("{}={}", key, quote_env(&value_of(key).unwrap_or_default())));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn quote_env(value: &str) -> String {
+    if value.is_empty() || value.chars().any(|ch| ch.is_whitespace() || "#\"'".contains(ch)) {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `git merge-file -p --diff3`: returns whether the merge was clean and the merged text
+/// (with conflict markers when it was not).
+fn git_merge_file(ours: &str, base: &str, theirs: &str) -> Result<(bool, String), String> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let dir = std::env::temp_dir().join(format!("flow-desk-merge-{}-{}", std::process::id(), nanos));
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let paths = [("ours", ours), ("base", base), ("theirs", theirs)].map(|(name, content)| {
+        let path = dir.join(name);
+        fs::write(&path, content).map(|_| path)
+    });
+    let result = match paths {
+        [Ok(ours), Ok(base), Ok(theirs)] => Command::new("git")
+            .args(["merge-file", "-p", "--diff3", "-L", "workspace", "-L", "merge-base", "-L", "release"])
+            .args([&ours, &base, &theirs])
+            .output()
+            .map_err(|e| format!("git merge-file failed: {}", e))
+            .and_then(|output| match output.status.code() {
+                Some(code) if (0..=127).contains(&code) => Ok((code == 0, String::from_utf8_lossy(&output.stdout).to_string())),
+                _ => Err(format!("git merge-file failed: {}", String::from_utf8_lossy(&output.stderr).trim())),
+            }),
+        _ => Err(format!("Failed to write merge inputs in {}", dir.display())),
+    };
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_release_changes_around_local_edits() {
+        let base = "# api\nAPI_URL=https://api.example.com\nTIMEOUT=30\nREGION=eu\n";
+        let ours = "# api\nAPI_URL=http://localhost:3000\nTIMEOUT=30\nREGION=eu\n";
+        let theirs = "# api\nAPI_URL=https://api.example.com\nTIMEOUT=30\nREGION=us\n";
+
+        let outcome = merge_config_file(".env", Some(base), Some(ours), Some(theirs), None).unwrap();
+        let MergeOutcome::Merged { content, keys, reformatted } = outcome else { panic!("expected merge") };
+        assert_eq!(content, "# api\nAPI_URL=http://localhost:3000\nTIMEOUT=30\nREGION=us\n");
+        assert_eq!(keys, vec!["REGION"]);
+        assert!(!reformatted);
+
+        assert_eq!(merge_config_file(".env", Some(base), Some(theirs), Some(theirs), None).unwrap(), MergeOutcome::Unchanged);
+    }
+
+    #[test]
+    fn reports_line_and_key_conflicts_without_writing() {
+        let base = "{\n  \"db\": { \"password\": \"a\" },\n  \"port\": 1\n}\n";
+        let ours = "{\n  \"db\": { \"password\": \"b\" },\n  \"port\": 1\n}\n";
+        let theirs = "{\n  \"db\": { \"password\": \"c\" },\n  \"port\": 1\n}\n";
+        let MergeOutcome::Conflict { keys, diff3, .. } = merge_config_file("config/app.json", Some(base), Some(ours), Some(theirs), None).unwrap()
+        else {
+            panic!("expected conflict")
+        };
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].key, "db.password");
+        assert_eq!(keys[0].theirs.as_deref(), Some(MASKED_VALUE));
+        assert!(diff3.unwrap().contains("<<<<<<< workspace"));
+    }
+
+    #[test]
+    fn applies_only_selected_keys() {
+        let base = "server:\n  port: 80\n  host: a\nlog: info\n";
+        let ours = "server:\n  port: 80\n  host: local\nlog: info\n";
+        let theirs = "server:\n  port: 8080\n  host: b\nlog: debug\nfeature: true\n";
+        let keys = ["server.port".to_string(), "feature".to_string()];
+        let outcome = merge_config_file("app.yaml", Some(base), Some(ours), Some(theirs), Some(&keys)).unwrap();
+        let MergeOutcome::Merged { content, keys, reformatted } = outcome else { panic!("expected merge") };
+        assert_eq!(keys, vec!["server.port", "feature"]);
+        assert!(reformatted);
+        let merged = ConfigFormat::Yaml.parse(&content).unwrap();
+        assert_eq!(merged["server"]["port"], 8080);
+        assert_eq!(merged["server"]["host"], "local");
+        assert_eq!(merged["log"], "info");
+        assert_eq!(merged["feature"], true);
+
+        let conflicting = ["server.host".to_string()];
+        assert!(matches!(
+            merge_config_file("app.yaml", Some(base), Some(ours), Some(theirs), Some(&conflicting)).unwrap(),
+            MergeOutcome::Conflict { .. }
+        ));
+    }
+
+    #[test]
+    fn edits_env_lines_in_place_for_selected_keys() {
+        let base = "A=1\n# keep\nexport B=2\n";
+        let ours = "A=1\n# keep\nexport B=2\nLOCAL=yes\n";
+        let theirs = "A=1\nexport B=3\nC=hello world\n";
+        let keys = ["B".to_string(), "C".to_string()];
+        let MergeOutcome::Merged { content, .. } = merge_config_file(".env.local", Some(base), Some(ours), Some(theirs), Some(&keys)).unwrap()
+        else {
+            panic!("expected merge")
+        };
+        assert_eq!(content, "A=1\n# keep\nexport B=3\nLOCAL=yes\nC=\"hello world\"\n");
+    }
+    #[test]
+    fn escapes_double_quotes_in_rewritten_env_values() {
+        let keys = ["JSON".to_string(), "PATTERN".to_string()];
+        let theirs = "JSON={\"a\":1}\nPATTERN='a\\\"b'\n";
+        let MergeOutcome::Merged { content, .. } = merge_config_file(".env", Some(""), Some(""), Some(theirs), Some(&keys)).unwrap()
+        else {
+            panic!("expected merge")
+        };
+        assert_eq!(content, "JSON=\"{\\\"a\\\":1}\"\nPATTERN=\"a\\\\\\\"b\"\n");
+        let parsed: Vec<_> = content.lines().filter_map(parse_env_line).collect();
+        assert_eq!(
+            parsed,
+            [("JSON".to_string(), "{\"a\":1}".to_string()), ("PATTERN".to_string(), "a\\\"b".to_string())]
+        );
+    }
+}
@@ -0,0 +1,651 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Where release documents are published, configured per workspace in the release profile.
+///
+/// `baseUrl` is the Confluence root the REST API hangs off: `https://<site>.atlassian.net/wiki`
+/// for Cloud, the server URL (plus context path) for Server/Data Center.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConfluenceTarget {
+    pub base_url: String,
+    pub space_key: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub parent_page_id: String,
+    /// Page title; `{version}` expands to the release version. Defaults to `Release {version}`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub title_template: String,
+}
+
+impl ConfluenceTarget {
+    pub fn is_configured(&self) -> bool {
+        !self.base_url.trim().is_empty() && !self.space_key.trim().is_empty()
+    }
+
+    pub fn page_title(&self, version: &str) -> String {
+        let template = if self.title_template.trim().is_empty() {
+            "Release {version}"
+        } else {
+            self.title_template.as_str()
+        };
+        template.replace("{version}", version.trim())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfluencePage {
+    pub id: String,
+    pub title: String,
+    pub version: u64,
+    pub url: String,
+}
+
+impl ConfluencePage {
+    fn from_value(value: &Value, base_url: &str) -> Option<Self> {
+        let id = value.get("id").and_then(Value::as_str)?.to_string();
+        let links = value.get("_links");
+        let link_base = links
+            .and_then(|links| links.get("base"))
+            .and_then(Value::as_str)
+            .unwrap_or(base_url)
+            .trim_end_matches('/');
+        let webui = links.and_then(|links| links.get("webui")).and_then(Value::as_str).unwrap_or("");
+        Some(Self {
+            title: value.get("title").and_then(Value::as_str).unwrap_or("").to_string(),
+            version: value
+                .get("version")
+                .and_then(|version| version.get("number"))
+                .and_then(Value::as_u64)
+                .unwrap_or(1),
+            url: if webui.is_empty() {
+                format!("{}/pages/viewpage.action?pageId={}", link_base, id)
+            } else {
+                format!("{}{}", link_base, webui)
+            },
+            id,
+        })
+    }
+}
+
+/// Minimal Confluence REST client (content API v1, which Cloud and Server both serve).
+pub struct ConfluenceClient {
+    base_url: String,
+    authorization: Option<String>,
+    http: reqwest::Client,
+}
+
+impl ConfluenceClient {
+    /// Cloud uses email + API token (Basic); Server/DC personal access tokens go without an email (Bearer).
+    pub fn new(base_url: &str, email: Option<&str>, api_token: Option<&str>) -> Self {
+        let email = email.map(str::trim).filter(|email| !email.is_empty());
+        let token = api_token.map(str::trim).filter(|token| !token.is_empty());
+        let authorization = match (email, token) {
+            (Some(email), Some(token)) => Some(format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", email, token))
+            )),
+            (None, Some(token)) => Some(format!("Bearer {}", token)),
+            _ => None,
+        };
+        Self {
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            authorization,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<(u16, Value), String> {
+        let request = match &self.authorization {
+            Some(authorization) => request.header("Authorization", authorization),
+            None => request,
+        };
+        let response = request
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| format!("Confluence request failed: {}", e))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read Confluence response: {}", e))?;
+        Ok((status, serde_json::from_str(&body).unwrap_or(Value::String(body))))
+    }
+
+    fn failure(action: &str, status: u16, body: &Value) -> String {
+        let message = body
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| body.to_string().chars().take(300).collect());
+        format!("Confluence {} failed with HTTP {}: {}", action, status, message)
+    }
+
+    pub async fn get_page(&self, page_id: &str) -> Result<Option<ConfluencePage>, String> {
+        let url = format!("{}/rest/api/content/{}?expand=version", self.base_url, urlencoding::encode(page_id));
+        match self.send(self.http.get(&url)).await? {
+            (200, body) => Ok(ConfluencePage::from_value(&body, &self.base_url)),
+            (404, _) => Ok(None),
+            (status, body) => Err(Self::failure("page lookup", status, &body)),
+        }
+    }
+
+    pub async fn find_page(&self, space_key: &str, title: &str) -> Result<Option<ConfluencePage>, String> {
+        let url = format!(
+            "{}/rest/api/content?type=page&spaceKey={}&title={}&expand=version",
+            self.base_url,
+            urlencoding::encode(space_key),
+            urlencoding::encode(title)
+        );
+        match self.send(self.http.get(&url)).await? {
+            (200, body) => Ok(body
+                .get("results")
+                .and_then(Value::as_array)
+                .and_then(|results| results.first())
+                .and_then(|page| ConfluencePage::from_value(page, &self.base_url))),
+            (status, body) => Err(Self::failure("page search", status, &body)),
+        }
+    }
+
+    pub async fn create_page(&self, target: &ConfluenceTarget, title: &str, storage: &str) -> Result<ConfluencePage, String> {
+        let mut payload = json!({
+            "type": "page",
+            "title": title,
+            "space": { "key": target.space_key },
+            "body": { "storage": { "value": storage, "representation": "storage" } }
+        });
+        if !target.parent_page_id.trim().is_empty() {
+            payload["ancestors"] = json!([{ "id": target.parent_page_id.trim() }]);
+        }
+        let url = format!("{}/rest/api/content", self.base_url);
+        match self.send(self.http.post(&url).json(&payload)).await? {
+            (200 | 201, body) => ConfluencePage::from_value(&body, &self.base_url)
+                .ok_or_else(|| "Confluence create response has no page id".to_string()),
+            (status, body) => Err(Self::failure("page create", status, &body)),
+        }
+    }
+
+    /// Replace the page body. Confluence requires the next version number; a 409 means someone
+    /// else saved in between.
+    async fn update_page(&self, page: &ConfluencePage, title: &str, storage: &str) -> Result<Result<ConfluencePage, u16>, String> {
+        let payload = json!({
+            "id": page.id,
+            "type": "page",
+            "title": title,
+            "version": { "number": page.version + 1, "message": "Updated by Flow Desk release" },
+            "body": { "storage": { "value": storage, "representation": "storage" } }
+        });
+        let url = format!("{}/rest/api/content/{}", self.base_url, urlencoding::encode(&page.id));
+        match self.send(self.http.put(&url).json(&payload)).await? {
+            (200, body) => Ok(Ok(ConfluencePage::from_value(&body, &self.base_url)
+                .ok_or_else(|| "Confluence update response has no page id".to_string())?)),
+            (409, _) => Ok(Err(409)),
+            (status, body) => Err(Self::failure("page update", status, &body)),
+        }
+    }
+
+    /// Create the page, or update it when `known_page_id` or a same-titled page in the space
+    /// exists. Returns the page and whether it was newly created.
+    pub async fn publish(
+        &self,
+        target: &ConfluenceTarget,
+        title: &str,
+        storage: &str,
+        known_page_id: Option<&str>,
+    ) -> Result<(ConfluencePage, bool), String> {
+        let existing = match known_page_id.filter(|id| !id.is_empty()) {
+            Some(page_id) => match self.get_page(page_id).await? {
+                Some(page) => Some(page),
+                None => self.find_page(&target.space_key, title).await?,
+            },
+            None => self.find_page(&target.space_key, title).await?,
+        };
+        let Some(mut page) = existing else {
+            return Ok((self.create_page(target, title, storage).await?, true));
+        };
+        // One retry on a version conflict, against the freshly read version.
+        for _ in 0..2 {
+            match self.update_page(&page, title, storage).await? {
+                Ok(updated) => return Ok((updated, false)),
+                Err(_) => {
+                    page = self
+                        .get_page(&page.id)
+                        .await?
+                        .ok_or_else(|| format!("Confluence page {} disappeared during update", page.id))?;
+                }
+            }
+        }
+        Err(format!("Confluence page {} kept changing; version conflict not resolved", page.id))
+    }
+}
+
+/// Convert the Markdown release draft into Confluence storage format (XHTML).
+///
+/// Covers what the drafts use: headings, paragraphs, bullet/numbered lists, pipe tables,
+/// fenced code (as the `code` macro), block quotes, rules, and inline code/bold/links.
+pub fn markdown_to_storage(markdown: &str) -> String {
+    render_markdown(markdown, false)
+}
+
+/// Same subset as [`markdown_to_storage`], as plain HTML (code fences become `<pre><code>`).
+pub fn markdown_to_html(markdown: &str) -> String {
+    render_markdown(markdown, true)
+}
+
+fn render_markdown(markdown: &str, html: bool) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut list: Option<&str> = None;
+    let lines = markdown.lines().collect::<Vec<_>>();
+    let mut index = 0;
+
+    let flush = |out: &mut String, paragraph: &mut Vec<String>, list: &mut Option<&str>| {
+        if !paragraph.is_empty() {
+            out.push_str(&format!("<p>{}</p>", inline_to_storage(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+        if let Some(tag) = list.take() {
+            out.push_str(&format!("</{}>", tag));
+        }
+    };
+
+    while index < lines.len() {
+        let line = lines[index].trim_end();
+        let trimmed = line.trim_start();
+        index += 1;
+
+        if let Some(language) = trimmed.strip_prefix("```") {
+            flush(&mut out, &mut paragraph, &mut list);
+            let mut code = Vec::new();
+            while index < lines.len() && !lines[index].trim_start().starts_with("```") {
+                code.push(lines[index]);
+                index += 1;
+            }
+            index += 1;
+            if html {
+                let class = if language.trim().is_empty() {
+                    String::new()
+                } else {
+                    format!(" class=\"language-{}\"", escape_xml(language.trim()))
+                };
+                out.push_str(&format!("<pre><code{}>{}</code></pre>", class, escape_xml(&code.join("\n"))));
+                continue;
+            }
+            out.push_str("<ac:structured-macro ac:name=\"code\">");
+            if !language.trim().is_empty() {
+                out.push_str(&format!(
+                    "<ac:parameter ac:name=\"language\">{}</ac:parameter>",
+                    escape_xml(language.trim())
+                ));
+            }
+            out.push_str(&format!(
+                "<ac:plain-text-body><![CDATA[{}]]></ac:plain-text-body></ac:structured-macro>",
+                code.join("\n").replace("]]>", "]]]]><![CDATA[>")
+            ));
+            continue;
+        }
+        if trimmed.is_empty() {
+            flush(&mut out, &mut paragraph, &mut list);
+            continue;
+        }
+        let level = trimmed.chars().take_while(|ch| *ch == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            flush(&mut out, &mut paragraph, &mut list);
+            out.push_str(&format!("<h{0}>{1}</h{0}>", level, inline_to_storage(trimmed[level..].trim())));
+            continue;
+        }
+        if trimmed.starts_with('|') {
+            flush(&mut out, &mut paragraph, &mut list);
+            let mut rows = vec![trimmed];
+            while index < lines.len() && lines[index].trim_start().starts_with('|') {
+                rows.push(lines[index].trim());
+                index += 1;
+            }
+            out.push_str(&table_to_storage(&rows));
+            continue;
+        }
+        if matches!(trimmed, "---" | "***" | "___") {
+            flush(&mut out, &mut paragraph, &mut list);
+            out.push_str("<hr />");
+            continue;
+        }
+        if let Some(quote) = trimmed.strip_prefix("> ") {
+            flush(&mut out, &mut paragraph, &mut list);
+            out.push_str(&format!("<blockquote><p>{}</p></blockquote>", inline_to_storage(quote)));
+            continue;
+        }
+        let bullet = ["- ", "* ", "+ "].iter().find_map(|marker| trimmed.strip_prefix(marker));
+        let numbered = trimmed
+            .split_once(". ")
+            .filter(|(number, _)| !number.is_empty() && number.chars().all(|ch| ch.is_ascii_digit()))
+            .map(|(_, item)| item);
+        if let Some((tag, item)) = bullet.map(|item| ("ul", item)).or(numbered.map(|item| ("ol", item))) {
+            if !paragraph.is_empty() || list.is_some_and(|open| open != tag) {
+                flush(&mut out, &mut paragraph, &mut list);
+            }
+            if list.is_none() {
+                out.push_str(&format!("<{}>", tag));
+                list = Some(tag);
+            }
+            out.push_str(&format!("<li>{}</li>", inline_to_storage(item)));
+            continue;
+        }
+        if list.is_some() {
+            flush(&mut out, &mut paragraph, &mut list);
+        }
+        paragraph.push(trimmed.to_string());
+    }
+    flush(&mut out, &mut paragraph, &mut list);
+    out
+}
+
+fn table_to_storage(rows: &[&str]) -> String {
+    let is_separator = |row: &str| {
+        table_cells(row)
+            .iter()
+            .all(|cell| !cell.is_empty() && cell.chars().all(|ch| matches!(ch, '-' | ':')))
+    };
+    let has_header = rows.len() > 1 && is_separator(rows[1]);
+    let mut out = String::from("<table><tbody>");
+    for (position, row) in rows.iter().enumerate() {
+        if has_header && position == 1 {
+            continue;
+        }
+        let tag = if has_header && position == 0 { "th" } else { "td" };
+        out.push_str("<tr>");
+        for cell in table_cells(row) {
+            out.push_str(&format!("<{0}>{1}</{0}>", tag, inline_to_storage(&cell)));
+        }
+        out.push_str("</tr>");
+    }
+    out.push_str("</tbody></table>");
+    out
+}
+
+/// Split a pipe-table row into trimmed cells; `\|` is a literal pipe inside a cell.
+fn table_cells(row: &str) -> Vec<String> {
+    let row = row.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let row = match row.strip_suffix('|') {
+        Some(inner) if !inner.ends_with('\\') => inner,
+        _ => row,
+    };
+    let mut cells = vec![String::new()];
+    let mut chars = row.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                cells.last_mut().expect("at least one cell").push('|');
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut().expect("at least one cell").push(ch),
+        }
+    }
+    cells.into_iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+/// Inline Markdown: `code`, **bold** and [text](url); everything else is escaped text.
+fn inline_to_storage(text: &str) -> String {
+    text.split('`')
+        .enumerate()
+        .map(|(position, segment)| {
+            if position % 2 == 1 {
+                format!("<code>{}</code>", escape_xml(segment))
+            } else {
+                links_to_storage(&bold_to_storage(&escape_xml(segment)))
+            }
+        })
+        .collect()
+}
+
+fn bold_to_storage(escaped: &str) -> String {
+    let parts = escaped.split("**").collect::<Vec<_>>();
+    if parts.len() < 3 {
+        return escaped.to_string();
+    }
+    let mut out = String::new();
+    for (position, part) in parts.iter().enumerate() {
+        // An unpaired trailing `**` stays literal.
+        let closes_pair = position % 2 == 1 && position + 1 < parts.len();
+        match (position, closes_pair) {
+            (0, _) => {}
+            (_, true) => out.push_str("<strong>"),
+            _ if position % 2 == 0 => out.push_str("</strong>"),
+            _ => out.push_str("**"),
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+fn links_to_storage(escaped: &str) -> String {
+    let mut out = String::new();
+    let mut rest = escaped;
+    while let Some(open) = rest.find('[') {
+        let parsed = rest[open + 1..].split_once("](").and_then(|(label, tail)| {
+            let (url, after) = tail.split_once(')')?;
+            (!label.contains('[') && !url.contains(' ')).then_some((label, url, after))
+        });
+        match parsed {
+            Some((label, url, after)) => {
+                out.push_str(&rest[..open]);
+                out.push_str(&format!("<a href=\"{}\">{}</a>", url, label));
+                rest = after;
+            }
+            None => {
+                out.push_str(&rest[..=open]);
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// In-process stand-in for the Confluence content API, for tests that publish for real.
+#[cfg(test)]
+pub(crate) mod mock_server {
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[derive(Default)]
+    pub struct MockState {
+        pub pages: BTreeMap<String, Value>,
+        pub requests: Vec<(String, String, Option<String>)>,
+        /// Number of upcoming updates to reject with 409, to exercise the retry.
+        pub conflicts: usize,
+    }
+
+    pub struct MockConfluence {
+        pub base_url: String,
+        pub state: Arc<Mutex<MockState>>,
+    }
+
+    impl MockConfluence {
+        pub fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock Confluence");
+            let base_url = format!("http://{}", listener.local_addr().expect("mock address"));
+            let state = Arc::new(Mutex::new(MockState::default()));
+            let shared = state.clone();
+            let base = base_url.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let state = shared.clone();
+                    let base = base.clone();
+                    thread::spawn(move || handle(stream, &state, &base));
+                }
+            });
+            Self { base_url, state }
+        }
+    }
+
+    fn handle(stream: TcpStream, state: &Mutex<MockState>, base: &str) {
+        let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or("").to_string();
+        let target = parts.next().unwrap_or("").to_string();
+        let (mut length, mut authorization) = (0, None);
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap_or(0);
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => length = value.trim().parse().unwrap_or(0),
+                    "authorization" => authorization = Some(value.trim().to_string()),
+                    _ => {}
+                }
+            }
+        }
+        let mut body = vec![0; length];
+        let _ = reader.read_exact(&mut body);
+        let body = serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null);
+
+        let (status, response) = {
+            let mut state = state.lock().unwrap();
+            state.requests.push((method.clone(), target.clone(), authorization));
+            route(&mut state, &method, &target, body, base)
+        };
+        let payload = response.to_string();
+        let mut stream = stream;
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            payload.len(),
+            payload
+        );
+    }
+
+    fn route(state: &mut MockState, method: &str, target: &str, body: Value, base: &str) -> (u16, Value) {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let param = |name: &str| {
+            query.split('&').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                (key == name).then(|| urlencoding::decode(value).map(|value| value.into_owned()).unwrap_or_default())
+            })
+        };
+        let page_id = path.strip_prefix("/rest/api/content/").map(str::to_string);
+        match (method, page_id) {
+            ("GET", None) => {
+                let (space, title) = (param("spaceKey").unwrap_or_default(), param("title").unwrap_or_default());
+                let results = state
+                    .pages
+                    .values()
+                    .filter(|page| page["space"]["key"] == space.as_str() && page["title"] == title.as_str())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (200, json!({ "results": results }))
+            }
+            ("GET", Some(id)) => match state.pages.get(&id) {
+                Some(page) => (200, page.clone()),
+                None => (404, json!({ "message": "No content found" })),
+            },
+            ("POST", None) => {
+                let id = format!("{}", 1000 + state.pages.len());
+                let page = json!({
+                    "id": id,
+                    "type": "page",
+                    "title": body["title"],
+                    "space": body["space"],
+                    "ancestors": body["ancestors"],
+                    "version": { "number": 1 },
+                    "body": body["body"],
+                    "_links": { "base": base, "webui": format!("/spaces/{}/pages/{}", body["space"]["key"].as_str().unwrap_or(""), id) }
+                });
+                state.pages.insert(id, page.clone());
+                (200, page)
+            }
+            ("PUT", Some(id)) => {
+                if state.conflicts > 0 {
+                    state.conflicts -= 1;
+                    if let Some(page) = state.pages.get_mut(&id) {
+                        let bumped = page["version"]["number"].as_u64().unwrap_or(1) + 1;
+                        page["version"]["number"] = json!(bumped);
+                    }
+                    return (409, json!({ "message": "Version must be incremented on update" }));
+                }
+                let Some(page) = state.pages.get_mut(&id) else {
+                    return (404, json!({ "message": "No content found" }));
+                };
+                let expected = page["version"]["number"].as_u64().unwrap_or(1) + 1;
+                if body["version"]["number"].as_u64() != Some(expected) {
+                    return (409, json!({ "message": "Version must be incremented on update" }));
+                }
+                page["title"] = body["title"].clone();
+                page["body"] = body["body"].clone();
+                page["version"]["number"] = json!(expected);
+                (200, page.clone())
+            }
+            _ => (404, json!({ "message": "Not mocked" })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock_server::MockConfluence;
+    use super::*;
+
+    #[test]
+    fn converts_markdown_to_storage_format() {
+        let storage = markdown_to_storage(
+            "# Release 3.8.2\n\nShips **two** fixes & a [link](https://x.test/a?b=1&c=2).\n\n- `web` ok\n- admin\n\n| Repo | SHA |\n| --- | --- |\n| web | <abc> |\n\n```bash\necho ]]>\n```",
+        );
+        assert!(storage.starts_with("<h1>Release 3.8.2</h1>"));
+        assert!(storage.contains("<p>Ships <strong>two</strong> fixes &amp; a <a href=\"https://x.test/a?b=1&amp;c=2\">link</a>.</p>"));
+        assert!(storage.contains("<ul><li><code>web</code> ok</li><li>admin</li></ul>"));
+        assert!(storage.contains("<tr><th>Repo</th><th>SHA</th></tr><tr><td>web</td><td>&lt;abc&gt;</td></tr>"));
+        assert!(storage.contains("<ac:parameter ac:name=\"language\">bash</ac:parameter>"));
+        assert!(storage.contains("echo ]]]]><![CDATA[>"));
+    }
+
+    #[tokio::test]
+    async fn creates_then_updates_the_page_with_versions() {
+        let mock = MockConfluence::start();
+        let target = ConfluenceTarget {
+            base_url: mock.base_url.clone(),
+            space_key: "OPS".to_string(),
+            parent_page_id: "42".to_string(),
+            ..ConfluenceTarget::default()
+        };
+        let client = ConfluenceClient::new(&mock.base_url, Some("ops@example.com"), Some("token"));
+        let title = target.page_title("3.8.2");
+
+        let (created, is_new) = client.publish(&target, &title, "<p>v1</p>", None).await.expect("create");
+        assert!(is_new);
+        assert_eq!(created.version, 1);
+        assert!(created.url.starts_with(&mock.base_url));
+
+        mock.state.lock().unwrap().conflicts = 1;
+        let (updated, is_new) = client.publish(&target, &title, "<p>v2</p>", Some(&created.id)).await.expect("update");
+        assert!(!is_new);
+        assert_eq!(updated.id, created.id);
+        assert_eq!(updated.version, 3);
+
+        let state = mock.state.lock().unwrap();
+        assert_eq!(state.pages[&created.id]["body"]["storage"]["value"], "<p>v2</p>");
+        assert_eq!(state.pages[&created.id]["ancestors"][0]["id"], "42");
+        assert!(state.requests.iter().all(|(_, _, auth)| auth.as_deref().is_some_and(|auth| auth.starts_with("Basic "))));
+    }
+}
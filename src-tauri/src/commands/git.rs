@@ -1,12 +1,10 @@
-use std::process::Command;
+use super::git_remote::{default_pr_remote, parse_remote_url};
+use super::git_service::{git_cli, working_tree_status, BranchPage, BranchQuery, GitRepo};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 async fn run_git(project_path: String, args: Vec<String>) -> Result<(bool, String, String), String> {
     tauri::async_runtime::spawn_blocking(move || {
-        let output = Command::new("git")
-            .args(args)
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("Git command failed: {}", e))?;
+        let output = git_cli(&project_path, &args)?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -16,85 +14,70 @@ async fn run_git(project_path: String, args: Vec<String>) -> Result<(bool, Strin
     .map_err(|e| format!("Git task join failed: {}", e))?
 }
 
+/// Branches with upstream, ahead/behind, last commit and merged/stale flags, newest first.
+/// `query` filters by kind, name, merged and stale state and selects a page.
 #[tauri::command]
-pub async fn git_list_branches(project_path: String) -> Result<Vec<String>, String> {
-    let (ok, stdout, stderr) = run_git(project_path, vec!["branch".into(), "-a".into()]).await?;
-    if !ok {
-        return Err(stderr);
-    }
-
-    let branches: Vec<String> = stdout
-        .lines()
-        .map(|line| line.trim().trim_start_matches("* ").to_string())
-        .filter(|b| !b.contains("HEAD"))
-        .collect();
-
-    Ok(branches)
+pub async fn git_list_branches(project_path: String, query: Option<BranchQuery>) -> Result<BranchPage, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as i64)
+            .unwrap_or_default();
+        GitRepo::open(&project_path)?.list_branches(&query.unwrap_or_default(), now_ms)
+    })
+    .await
+    .map_err(|e| format!("Git task join failed: {}", e))?
 }
 
 #[tauri::command]
 pub async fn git_check_working_tree(project_path: String) -> Result<serde_json::Value, String> {
     tauri::async_runtime::spawn_blocking(move || {
-        let status = Command::new("git")
-            .args(["status", "--porcelain"])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("Failed to check status: {}", e))?;
-
-        let status_output = String::from_utf8_lossy(&status.stdout).to_string();
-        let has_changes = !status_output.trim().is_empty();
-
-        let mut staged_files: Vec<String> = Vec::new();
-        let mut unstaged_files: Vec<String> = Vec::new();
-        let mut untracked_files: Vec<String> = Vec::new();
-
-        for line in status_output.lines() {
-            if line.len() < 3 { continue; }
-            let index_status = line.chars().nth(0).unwrap_or(' ');
-            let worktree_status = line.chars().nth(1).unwrap_or(' ');
-            let filename = line[3..].to_string();
-
-            if index_status != ' ' && index_status != '?' {
-                staged_files.push(filename.clone());
-            }
-            if worktree_status != ' ' && worktree_status != '?' {
-                unstaged_files.push(filename.clone());
-            }
-            if index_status == '?' && worktree_status == '?' {
-                untracked_files.push(filename);
-            }
-        }
-
-        let current_branch = Command::new("git")
-            .args(["branch", "--show-current"])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("Failed to get current branch: {}", e))?;
-
-        let current_branch_name = String::from_utf8_lossy(&current_branch.stdout).trim().to_string();
-
-        let unpushed = Command::new("git")
-            .args(["log", "@{u}..", "--oneline"])
-            .current_dir(&project_path)
-            .output();
-
-        let has_unpushed = match unpushed {
-            Ok(output) => !String::from_utf8_lossy(&output.stdout).trim().is_empty(),
-            Err(_) => false
-        };
+        let status = working_tree_status(&project_path).map_err(|e| format!("Failed to check status: {}", e))?;
+        let has_changes = !status.is_clean();
+
+        let staged_files: Vec<&str> = status.entries.iter()
+            .filter(|entry| entry.staged())
+            .map(|entry| entry.path.as_str())
+            .collect();
+        let unstaged_files: Vec<&str> = status.entries.iter()
+            .filter(|entry| entry.unstaged())
+            .map(|entry| entry.path.as_str())
+            .collect();
+        let renamed_files: Vec<_> = status.entries.iter()
+            .filter_map(|entry| Some(serde_json::json!({
+                "path": entry.path,
+                "originalPath": entry.original_path.as_ref()?
+            })))
+            .collect();
+        let submodules: Vec<_> = status.entries.iter()
+            .filter_map(|entry| Some(serde_json::json!({
+                "path": entry.path,
+                "state": entry.submodule.as_ref()?
+            })))
+            .collect();
 
         Ok(serde_json::json!({
             "clean": !has_changes,
-            "currentBranch": current_branch_name,
+            "currentBranch": status.branch.clone().unwrap_or_default(),
+            "detached": status.branch.is_none(),
             "stagedFiles": staged_files,
             "unstagedFiles": unstaged_files,
-            "untrackedFiles": untracked_files,
-            "hasUnpushedCommits": has_unpushed,
-            "summary": if has_changes {
+            "untrackedFiles": status.untracked,
+            "renamedFiles": renamed_files,
+            "conflictedFiles": status.conflicts,
+            "submodules": submodules,
+            "upstream": status.upstream,
+            "ahead": status.ahead,
+            "behind": status.behind,
+            "hasUnpushedCommits": status.ahead.is_some_and(|ahead| ahead > 0),
+            "summary": if !has_changes {
+                "Working tree clean".to_string()
+            } else if status.conflicts.is_empty() {
                 format!("{} staged, {} unstaged, {} untracked",
-                    staged_files.len(), unstaged_files.len(), untracked_files.len())
+                    staged_files.len(), unstaged_files.len(), status.untracked.len())
             } else {
-                "Working tree clean".to_string()
+                format!("{} conflicted, {} staged, {} unstaged, {} untracked",
+                    status.conflicts.len(), staged_files.len(), unstaged_files.len(), status.untracked.len())
             }
         }))
     })
@@ -105,23 +88,8 @@ pub async fn git_check_working_tree(project_path: String) -> Result<serde_json::
 #[tauri::command]
 pub async fn git_branch_exists(project_path: String, branch_name: String) -> Result<bool, String> {
     tauri::async_runtime::spawn_blocking(move || {
-        let local = Command::new("git")
-            .args(["branch", "--list", &branch_name])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("Failed to check local branch: {}", e))?;
-
-        if !String::from_utf8_lossy(&local.stdout).trim().is_empty() {
-            return Ok(true);
-        }
-
-        let remote = Command::new("git")
-            .args(["branch", "-r", "--list", &format!("origin/{}", branch_name)])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("Failed to check remote branch: {}", e))?;
-
-        Ok(!String::from_utf8_lossy(&remote.stdout).trim().is_empty())
+        let git = GitRepo::open(&project_path)?;
+        Ok(git.local_branch_exists(&branch_name) || git.remote_branch_exists("origin", &branch_name))
     })
     .await
     .map_err(|e| format!("Git task join failed: {}", e))?
@@ -134,26 +102,14 @@ pub async fn git_create_branch(
     new_branch: String,
 ) -> Result<String, String> {
     tauri::async_runtime::spawn_blocking(move || {
-        let status = Command::new("git")
-            .args(["status", "--porcelain"])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("安全检查失败: {}", e))?;
-
-        let status_output = String::from_utf8_lossy(&status.stdout).to_string();
-        if !status_output.trim().is_empty() {
-            let modified_files: Vec<&str> = status_output.lines()
-                .filter(|line| !line.starts_with("??"))
-                .take(5)
-                .collect();
+        let git = GitRepo::open(&project_path).map_err(|e| format!("安全检查失败: {}", e))?;
+        if !git.is_clean().map_err(|e| format!("安全检查失败: {}", e))? {
+            let modified_files = git.dirty_paths(false).map_err(|e| format!("安全检查失败: {}", e))?;
 
             let file_list = if modified_files.is_empty() {
                 "有未跟踪的新文件".to_string()
             } else {
-                modified_files.iter()
-                    .map(|f| f.get(3..).unwrap_or(f))
-                    .collect::<Vec<_>>()
-                    .join(", ")
+                modified_files.iter().take(5).cloned().collect::<Vec<_>>().join(", ")
             };
 
             return Err(format!(
@@ -162,60 +118,31 @@ pub async fn git_create_branch(
             ));
         }
 
-        let local_branch = Command::new("git")
-            .args(["branch", "--list", &new_branch])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("检查本地分支失败: {}", e))?;
-
-        if !String::from_utf8_lossy(&local_branch.stdout).trim().is_empty() {
+        if git.local_branch_exists(&new_branch) {
             return Err(format!("⚠️ 本地分支 '{}' 已存在", new_branch));
         }
 
-        let remote_branch = Command::new("git")
-            .args(["branch", "-r", "--list", &format!("origin/{}", new_branch)])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("检查远程分支失败: {}", e))?;
-
-        if !String::from_utf8_lossy(&remote_branch.stdout).trim().is_empty() {
+        if git.remote_branch_exists("origin", &new_branch) {
             return Err(format!("⚠️ 远程分支 'origin/{}' 已存在", new_branch));
         }
 
-        let base_exists = Command::new("git")
-            .args(["rev-parse", "--verify", &format!("origin/{}", base_branch)])
-            .current_dir(&project_path)
-            .output();
-
-        let base_branch_ref = match base_exists {
-            Ok(output) if output.status.success() => format!("origin/{}", base_branch),
-            _ => {
-                let local_base = Command::new("git")
-                    .args(["rev-parse", "--verify", &base_branch])
-                    .current_dir(&project_path)
-                    .output();
-
-                match local_base {
-                    Ok(output) if output.status.success() => base_branch.clone(),
-                    _ => return Err(format!("⚠️ Base 分支 '{}' 不存在", base_branch))
-                }
-            }
+        let remote_base = format!("origin/{}", base_branch);
+        let base_branch_ref = if git.resolve_commit(&remote_base).is_ok() {
+            remote_base
+        } else if git.resolve_commit(&base_branch).is_ok() {
+            base_branch.clone()
+        } else {
+            return Err(format!("⚠️ Base 分支 '{}' 不存在", base_branch));
         };
 
-        let fetch = Command::new("git")
-            .args(["fetch", "origin"])
-            .current_dir(&project_path)
-            .output()
+        let fetch = git_cli(&project_path, &["fetch".into(), "origin".into()])
             .map_err(|e| format!("Fetch 失败: {}", e))?;
 
         if !fetch.status.success() {
             return Err(format!("Fetch 失败: {}", String::from_utf8_lossy(&fetch.stderr)));
         }
 
-        let create = Command::new("git")
-            .args(["checkout", "-b", &new_branch, &base_branch_ref])
-            .current_dir(&project_path)
-            .output()
+        let create = git_cli(&project_path, &["checkout".into(), "-b".into(), new_branch.clone(), base_branch_ref])
             .map_err(|e| format!("创建分支失败: {}", e))?;
 
         if !create.status.success() {
@@ -236,10 +163,11 @@ pub async fn git_create_branch(
 }
 
 #[tauri::command]
-pub async fn git_push_branch(project_path: String, branch: String) -> Result<String, String> {
+pub async fn git_push_branch(project_path: String, branch: String, remote: Option<String>) -> Result<String, String> {
+    let remote = remote.unwrap_or_else(|| "origin".to_string());
     let (ok, _stdout, stderr) = run_git(
         project_path,
-        vec!["push".into(), "-u".into(), "origin".into(), branch.clone()],
+        vec!["push".into(), "-u".into(), remote, branch.clone()],
     )
     .await?;
 
@@ -250,23 +178,52 @@ pub async fn git_push_branch(project_path: String, branch: String) -> Result<Str
     Ok(format!("Pushed branch: {}", branch))
 }
 
+/// Every configured remote with its parsed host, owner, repo and forge, plus the remote PRs
+/// should target (`prRemote`: `upstream` in a fork, otherwise `origin`).
 #[tauri::command]
-pub async fn git_get_remote_info(project_path: String) -> Result<String, String> {
-    let (ok, stdout, stderr) = run_git(project_path, vec!["remote".into(), "get-url".into(), "origin".into()]).await?;
-    if !ok {
-        return Err(stderr);
-    }
-    let url = stdout.trim().to_string();
+pub async fn git_list_remotes(project_path: String) -> Result<serde_json::Value, String> {
+    let remotes = tauri::async_runtime::spawn_blocking(move || GitRepo::open(&project_path)?.remotes())
+        .await
+        .map_err(|e| format!("Git task join failed: {}", e))??;
+
+    let names: Vec<&str> = remotes.iter().map(|(name, _)| name.as_str()).collect();
+    let pr_remote = default_pr_remote(&names);
+    let entries: Vec<_> = remotes.iter()
+        .map(|(name, url)| {
+            let parsed = parse_remote_url(url);
+            serde_json::json!({
+                "name": name,
+                "url": url,
+                "parsed": parsed,
+                "fullName": parsed.as_ref().map(|remote| remote.full_name()),
+                "webUrl": parsed.as_ref().map(|remote| remote.web_url()),
+                "apiBaseUrl": parsed.as_ref().and_then(|remote| remote.api_base_url())
+            })
+        })
+        .collect();
 
-    let repo_info = if url.starts_with("git@") {
-        url.trim_start_matches("git@github.com:")
-            .trim_end_matches(".git")
-            .to_string()
-    } else {
-        url.trim_start_matches("https://github.com/")
-            .trim_end_matches(".git")
-            .to_string()
+    Ok(serde_json::json!({
+        "remotes": entries,
+        "prRemote": pr_remote
+    }))
+}
+
+/// `owner/repo` of `remote`, or of the default PR remote when none is given.
+#[tauri::command]
+pub async fn git_get_remote_info(project_path: String, remote: Option<String>) -> Result<String, String> {
+    let remotes = tauri::async_runtime::spawn_blocking(move || GitRepo::open(&project_path)?.remotes())
+        .await
+        .map_err(|e| format!("Git task join failed: {}", e))??;
+
+    let names: Vec<&str> = remotes.iter().map(|(name, _)| name.as_str()).collect();
+    let name = match remote.as_deref() {
+        Some(name) => name,
+        None => default_pr_remote(&names).ok_or("Repository has no remotes")?,
     };
+    let (_, url) = remotes.iter()
+        .find(|(candidate, _)| candidate == name)
+        .ok_or_else(|| format!("Remote '{}' does not exist", name))?;
+    let parsed = parse_remote_url(url).ok_or_else(|| format!("Cannot parse URL of remote '{}': {}", name, url))?;
 
-    Ok(repo_info)
+    Ok(parsed.full_name())
 }
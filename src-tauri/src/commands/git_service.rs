@@ -0,0 +1,855 @@
+//! Git access shared by the commands. Local reads and ref updates go through libgit2, which is
+//! fast across many repos and independent of git's localized output. Operations that talk to a
+//! remote (fetch, push, ls-remote) or rewrite the working tree (checkout, merge) still run the
+//! `git` CLI, so credential helpers, SSH config and hooks keep applying. Working-tree status is
+//! read from `status --porcelain=v2 -z`, a stable format that also reports submodule state.
+
+use git2::{BranchType, ObjectType, Repository, RepositoryState, StatusOptions, TreeWalkMode, TreeWalkResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Output};
+
+pub struct GitRepo {
+    repo: Repository,
+}
+
+fn git_error(error: git2::Error) -> String {
+    error.message().to_string()
+}
+
+impl GitRepo {
+    pub fn open(path: &str) -> Result<Self, String> {
+        Repository::open(path)
+            .map(|repo| Self { repo })
+            .map_err(|e| format!("Failed to open git repository {}: {}", path, e.message()))
+    }
+
+    fn commit(&self, spec: &str) -> Result<git2::Commit<'_>, String> {
+        self.repo
+            .revparse_single(spec)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| format!("Unknown revision {}: {}", spec, e.message()))
+    }
+
+    /// Tree of a commit, tag or tree id.
+    fn tree(&self, spec: &str) -> Result<git2::Tree<'_>, String> {
+        self.repo
+            .revparse_single(spec)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|e| format!("Unknown revision {}: {}", spec, e.message()))
+    }
+
+    /// Object id `spec` names, not peeled, like `rev-parse <spec>`.
+    pub fn resolve(&self, spec: &str) -> Result<String, String> {
+        self.repo
+            .revparse_single(spec)
+            .map(|object| object.id().to_string())
+            .map_err(|e| format!("Unknown revision {}: {}", spec, e.message()))
+    }
+
+    /// Full SHA of the commit `spec` names, like `rev-parse <spec>^{commit}`.
+    pub fn resolve_commit(&self, spec: &str) -> Result<String, String> {
+        Ok(self.commit(spec)?.id().to_string())
+    }
+
+    pub fn local_branch_exists(&self, name: &str) -> bool {
+        self.repo.find_branch(name, BranchType::Local).is_ok()
+    }
+
+    pub fn remote_branch_exists(&self, remote: &str, name: &str) -> bool {
+        self.repo.find_branch(&format!("{}/{}", remote, name), BranchType::Remote).is_ok()
+    }
+
+    /// Paths with staged or unstaged changes, plus untracked ones when asked. Ignored files never count.
+    pub fn dirty_paths(&self, include_untracked: bool) -> Result<Vec<String>, String> {
+        let mut options = StatusOptions::new();
+        options.include_untracked(include_untracked).include_ignored(false);
+        let statuses = self.repo.statuses(Some(&mut options)).map_err(git_error)?;
+        Ok(statuses.iter().filter_map(|entry| entry.path().map(str::to_string)).collect())
+    }
+
+    pub fn is_clean(&self) -> Result<bool, String> {
+        Ok(self.dirty_paths(true)?.is_empty())
+    }
+
+    /// Commits only reachable from `left` and only from `right`
+    /// (`rev-list --left-right --count left...right`).
+    pub fn ahead_behind(&self, left: &str, right: &str) -> Result<(usize, usize), String> {
+        let (left, right) = (self.commit(left)?.id(), self.commit(right)?.id());
+        self.repo.graph_ahead_behind(left, right).map_err(git_error)
+    }
+
+    pub fn merge_base(&self, left: &str, right: &str) -> Result<String, String> {
+        let (left_id, right_id) = (self.commit(left)?.id(), self.commit(right)?.id());
+        self.repo
+            .merge_base(left_id, right_id)
+            .map(|oid| oid.to_string())
+            .map_err(|e| format!("No merge base between {} and {}: {}", left, right, e.message()))
+    }
+
+    /// Whether `ancestor` is in the history of `descendant` (`merge-base --is-ancestor`).
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool, String> {
+        let (ancestor, descendant) = (self.commit(ancestor)?.id(), self.commit(descendant)?.id());
+        Ok(ancestor == descendant || self.repo.graph_descendant_of(descendant, ancestor).map_err(git_error)?)
+    }
+
+    /// Paths that differ between the trees of two revisions (`diff --name-only`).
+    pub fn diff_names(&self, left: &str, right: &str) -> Result<Vec<String>, String> {
+        let (left, right) = (self.tree(left)?, self.tree(right)?);
+        let diff = self.repo.diff_tree_to_tree(Some(&left), Some(&right), None).map_err(git_error)?;
+        Ok(diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path().or_else(|| delta.old_file().path()))
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    /// Content of `path` at `spec`, byte for byte (not trimmed), so it can be written back as is.
+    pub fn read_file(&self, spec: &str, path: &str) -> Result<String, String> {
+        let entry = self
+            .tree(spec)?
+            .get_path(Path::new(path))
+            .map_err(|_| format!("{} does not exist in {}", path, spec))?;
+        let blob = entry.to_object(&self.repo).and_then(|object| object.peel_to_blob()).map_err(git_error)?;
+        Ok(String::from_utf8_lossy(blob.content()).into_owned())
+    }
+
+    /// Every file in the tree of `spec` (`ls-tree -r --name-only`).
+    pub fn list_files(&self, spec: &str) -> Result<Vec<String>, String> {
+        let mut files = Vec::new();
+        self.tree(spec)?
+            .walk(TreeWalkMode::PreOrder, |root, entry| {
+                if entry.kind() != Some(ObjectType::Tree) {
+                    if let Some(name) = entry.name() {
+                        files.push(format!("{}{}", root, name));
+                    }
+                }
+                TreeWalkResult::Ok
+            })
+            .map_err(git_error)?;
+        Ok(files)
+    }
+
+    /// Commit tag `name` points at, if the tag exists.
+    pub fn tag_commit(&self, name: &str) -> Option<String> {
+        self.resolve_commit(&format!("refs/tags/{}", name)).ok()
+    }
+
+    /// Create lightweight tag `name` on `target`, returning the tagged commit.
+    pub fn create_tag(&self, name: &str, target: &str) -> Result<String, String> {
+        let commit = self.commit(target)?;
+        self.repo
+            .tag_lightweight(name, commit.as_object(), false)
+            .map_err(|e| format!("Failed to create tag {}: {}", name, e.message()))?;
+        Ok(commit.id().to_string())
+    }
+
+    pub fn delete_tag(&self, name: &str) -> Result<(), String> {
+        self.repo
+            .tag_delete(name)
+            .map_err(|e| format!("Failed to delete tag {}: {}", name, e.message()))
+    }
+
+    /// A merge stopped on conflicts and is waiting to be committed or aborted.
+    pub fn merge_in_progress(&self) -> bool {
+        self.repo.state() == RepositoryState::Merge
+    }
+
+    /// In-memory merge of `theirs` into `ours` through libgit2, for git older than 2.38. libgit2
+    /// has no conflict messages, so `kinds` is derived from which sides are present, and the
+    /// tree is left empty when the merge conflicts.
+    pub fn merge_preview(&self, ours: &str, theirs: &str) -> Result<MergePreview, String> {
+        let (our_commit, their_commit) = (self.commit(ours)?, self.commit(theirs)?);
+        let mut index = self.repo.merge_commits(&our_commit, &their_commit, None).map_err(git_error)?;
+        if !index.has_conflicts() {
+            let tree = index.write_tree_to(&self.repo).map_err(git_error)?;
+            return Ok(MergePreview { tree: tree.to_string(), conflicts: Vec::new() });
+        }
+
+        let mut conflicts = Vec::new();
+        for conflict in index.conflicts().map_err(git_error)? {
+            let conflict = conflict.map_err(git_error)?;
+            let Some(entry) = conflict.our.as_ref().or(conflict.their.as_ref()).or(conflict.ancestor.as_ref()) else {
+                continue;
+            };
+            let path = String::from_utf8_lossy(&entry.path).into_owned();
+            let (kind, hunks) = match (&conflict.ancestor, &conflict.our, &conflict.their) {
+                (Some(ancestor), Some(our), Some(their)) => {
+                    let mut options = git2::MergeFileOptions::new();
+                    options.our_label(ours).their_label(theirs);
+                    let hunks = self
+                        .repo
+                        .merge_file_from_index(ancestor, our, their, Some(&mut options))
+                        .map(|merged| conflict_hunks(&String::from_utf8_lossy(merged.content())))
+                        .unwrap_or_default();
+                    ("contents", hunks)
+                }
+                (None, Some(_), Some(_)) => ("add/add", Vec::new()),
+                _ => ("modify/delete", Vec::new()),
+            };
+            conflicts.push(MergeConflict { path, kinds: vec![kind.to_string()], messages: Vec::new(), hunks });
+        }
+        Ok(MergePreview { tree: String::new(), conflicts })
+    }
+
+    /// Branch the others are checked against for `merged`: the given one, else the remote
+    /// default (`origin/HEAD`), else a local `main` or `master`.
+    fn default_branch(&self, requested: Option<&str>) -> Option<(String, git2::Oid)> {
+        let candidates = match requested {
+            Some(requested) => vec![requested.to_string()],
+            None => {
+                let remote_head = self
+                    .repo
+                    .find_reference("refs/remotes/origin/HEAD")
+                    .ok()
+                    .and_then(|head| head.symbolic_target().map(|target| target.trim_start_matches("refs/remotes/").to_string()));
+                remote_head.into_iter().chain(["main".to_string(), "master".to_string()]).collect()
+            }
+        };
+        candidates
+            .into_iter()
+            .find_map(|name| Some((name.clone(), self.commit(&name).ok()?.id())))
+    }
+
+    /// Branches with their last commit, upstream and merged/stale flags, filtered by `query` and
+    /// sorted newest first. `now_ms` is the current time in milliseconds, for `stale`.
+    pub fn list_branches(&self, query: &BranchQuery, now_ms: i64) -> Result<BranchPage, String> {
+        let default_branch = self.default_branch(query.default_branch.as_deref());
+        let stale_before = now_ms - i64::from(query.stale_days.unwrap_or(DEFAULT_STALE_DAYS)) * 86_400_000;
+        let needle = query.search.as_deref().map(str::to_lowercase).filter(|needle| !needle.is_empty());
+        let kinds = match query.kind.as_deref() {
+            Some("local") => vec![BranchType::Local],
+            Some("remote") => vec![BranchType::Remote],
+            _ => vec![BranchType::Local, BranchType::Remote],
+        };
+        let is_merged = |name: &str, id: git2::Oid| match &default_branch {
+            Some((default_name, _)) if default_name == name => false,
+            Some((_, default_id)) => *default_id == id || self.repo.graph_descendant_of(*default_id, id).unwrap_or(false),
+            None => false,
+        };
+
+        let mut branches = Vec::new();
+        for kind in kinds {
+            for branch in self.repo.branches(Some(kind)).map_err(git_error)? {
+                let (branch, _) = branch.map_err(git_error)?;
+                // `origin/HEAD` is an alias of the remote default branch, not a branch of its own.
+                if branch.get().kind() == Some(git2::ReferenceType::Symbolic) {
+                    continue;
+                }
+                let Some(name) = branch.name().map_err(git_error)?.map(str::to_string) else { continue };
+                if needle.as_ref().is_some_and(|needle| !name.to_lowercase().contains(needle)) {
+                    continue;
+                }
+                let Ok(commit) = branch.get().peel_to_commit() else { continue };
+                let committed_at = commit.time().seconds() * 1000;
+                let stale = committed_at < stale_before;
+                if query.stale.is_some_and(|wanted| wanted != stale) {
+                    continue;
+                }
+                // `merged` walks history, so before paging it is only worked out to filter on it.
+                let merged = match query.merged {
+                    Some(wanted) => {
+                        let merged = is_merged(&name, commit.id());
+                        if merged != wanted {
+                            continue;
+                        }
+                        merged
+                    }
+                    None => false,
+                };
+                let remote = match kind {
+                    BranchType::Remote => branch
+                        .get()
+                        .name()
+                        .and_then(|refname| self.repo.branch_remote_name(refname).ok())
+                        .and_then(|remote| remote.as_str().map(str::to_string)),
+                    BranchType::Local => None,
+                };
+                let author = commit.author();
+                branches.push(BranchInfo {
+                    name,
+                    kind: if kind == BranchType::Local { "local" } else { "remote" },
+                    remote,
+                    is_head: branch.is_head(),
+                    upstream: None,
+                    ahead: None,
+                    behind: None,
+                    sha: commit.id().to_string(),
+                    subject: commit.summary().unwrap_or("").to_string(),
+                    author_name: author.name().unwrap_or("").to_string(),
+                    author_email: author.email().unwrap_or("").to_string(),
+                    committed_at,
+                    merged,
+                    stale,
+                });
+            }
+        }
+        branches.sort_by(|left, right| right.committed_at.cmp(&left.committed_at).then_with(|| left.name.cmp(&right.name)));
+
+        let total = branches.len();
+        let offset = query.offset.unwrap_or(0).min(total);
+        let limit = query.limit.unwrap_or(DEFAULT_BRANCH_PAGE_SIZE).clamp(1, MAX_BRANCH_PAGE_SIZE);
+        let mut page = branches.into_iter().skip(offset).take(limit).collect::<Vec<_>>();
+        // Upstream counts and `merged` walk history, so they are only worked out for the branches returned.
+        if query.merged.is_none() {
+            for info in page.iter_mut() {
+                info.merged = git2::Oid::from_str(&info.sha).is_ok_and(|id| is_merged(&info.name, id));
+            }
+        }
+        for info in page.iter_mut().filter(|info| info.kind == "local") {
+            let Ok(branch) = self.repo.find_branch(&info.name, BranchType::Local) else { continue };
+            let Ok(upstream) = branch.upstream() else { continue };
+            info.upstream = upstream.name().ok().flatten().map(str::to_string);
+            if let (Some(local), Some(remote)) = (branch.get().target(), upstream.get().target()) {
+                if let Ok((ahead, behind)) = self.repo.graph_ahead_behind(local, remote) {
+                    info.ahead = Some(ahead);
+                    info.behind = Some(behind);
+                }
+            }
+        }
+        Ok(BranchPage {
+            has_more: offset + page.len() < total,
+            branches: page,
+            total,
+            offset,
+            limit,
+            default_branch: default_branch.map(|(name, _)| name),
+        })
+    }
+
+    /// Configured remotes as `(name, fetch url)`, in config order.
+    pub fn remotes(&self) -> Result<Vec<(String, String)>, String> {
+        let names = self.repo.remotes().map_err(git_error)?;
+        let mut remotes = Vec::new();
+        for name in names.iter().flatten() {
+            let remote = self.repo.find_remote(name).map_err(git_error)?;
+            remotes.push((name.to_string(), remote.url().unwrap_or("").to_string()));
+        }
+        Ok(remotes)
+    }
+}
+
+const DEFAULT_STALE_DAYS: u32 = 90;
+const DEFAULT_BRANCH_PAGE_SIZE: usize = 100;
+const MAX_BRANCH_PAGE_SIZE: usize = 500;
+
+/// Filters and page for `list_branches`; every field is optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BranchQuery {
+    /// `local`, `remote` or anything else for both.
+    pub kind: Option<String>,
+    /// Case-insensitive substring of the branch name.
+    pub search: Option<String>,
+    pub merged: Option<bool>,
+    pub stale: Option<bool>,
+    /// Days without commits after which a branch counts as stale (default 90).
+    pub stale_days: Option<u32>,
+    /// Branch `merged` is checked against, e.g. `origin/latest`.
+    pub default_branch: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchInfo {
+    /// `feature/x` for local branches, `origin/feature/x` for remote-tracking ones.
+    pub name: String,
+    pub kind: &'static str,
+    pub remote: Option<String>,
+    pub is_head: bool,
+    pub upstream: Option<String>,
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    pub sha: String,
+    pub subject: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Commit time in milliseconds since the epoch.
+    pub committed_at: i64,
+    /// Last commit is already in the default branch.
+    pub merged: bool,
+    pub stale: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchPage {
+    pub branches: Vec<BranchInfo>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub has_more: bool,
+    pub default_branch: Option<String>,
+}
+
+/// One changed path from `status --porcelain=v2`. `index` and `worktree` are the `XY` codes,
+/// with `.` for unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusEntry {
+    pub path: String,
+    /// Source path of a rename or copy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_path: Option<String>,
+    pub index: char,
+    pub worktree: char,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submodule: Option<SubmoduleState>,
+}
+
+impl StatusEntry {
+    pub fn staged(&self) -> bool {
+        self.index != '.'
+    }
+
+    pub fn unstaged(&self) -> bool {
+        self.worktree != '.'
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmoduleState {
+    pub commit_changed: bool,
+    pub modified: bool,
+    pub untracked: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictEntry {
+    pub path: String,
+    /// `bothModified`, `bothAdded`, `bothDeleted`, `addedByUs`, `addedByThem`, `deletedByUs` or `deletedByThem`.
+    pub conflict: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingTreeStatus {
+    /// Current branch; `None` when HEAD is detached.
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    /// Commits ahead of and behind the upstream; `None` without one.
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    /// Tracked changes, excluding conflicts.
+    pub entries: Vec<StatusEntry>,
+    pub conflicts: Vec<ConflictEntry>,
+    pub untracked: Vec<String>,
+}
+
+impl WorkingTreeStatus {
+    pub fn is_clean(&self) -> bool {
+        self.entries.is_empty() && self.conflicts.is_empty() && self.untracked.is_empty()
+    }
+}
+
+fn conflict_kind(xy: &str) -> &'static str {
+    match xy {
+        "DD" => "bothDeleted",
+        "AU" => "addedByUs",
+        "UD" => "deletedByThem",
+        "UA" => "addedByThem",
+        "DU" => "deletedByUs",
+        "AA" => "bothAdded",
+        _ => "bothModified",
+    }
+}
+
+/// Parse `git status --porcelain=v2 -z --branch`. Paths are NUL-terminated and never quoted, so
+/// spaces and non-ASCII names come through as is; a rename's source path is the next record.
+pub fn parse_porcelain_v2(output: &str) -> Result<WorkingTreeStatus, String> {
+    let mut status = WorkingTreeStatus::default();
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        let malformed = || format!("Unexpected git status line: {:?}", record);
+        let (kind, rest) = record.split_once(' ').ok_or_else(malformed)?;
+        match kind {
+            "#" => {
+                let (header, value) = rest.split_once(' ').unwrap_or((rest, ""));
+                match header {
+                    "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                    "branch.upstream" => status.upstream = Some(value.to_string()),
+                    "branch.ab" => {
+                        let mut counts = value.split(' ').map(|count| count.trim_start_matches(['+', '-']).parse().ok());
+                        status.ahead = counts.next().flatten();
+                        status.behind = counts.next().flatten();
+                    }
+                    _ => {}
+                }
+            }
+            "1" | "2" => {
+                // 1 XY sub mH mI mW hH hI path; 2 adds the rename score before the path.
+                let fields = rest.splitn(if kind == "1" { 8 } else { 9 }, ' ').collect::<Vec<_>>();
+                let (Some(xy), Some(submodule), Some(path)) = (fields.first(), fields.get(1), fields.last()) else {
+                    return Err(malformed());
+                };
+                let mut codes = xy.chars();
+                let (Some(index), Some(worktree)) = (codes.next(), codes.next()) else {
+                    return Err(malformed());
+                };
+                let original_path = if kind == "2" {
+                    Some(records.next().ok_or_else(malformed)?.to_string())
+                } else {
+                    None
+                };
+                let submodule = submodule.strip_prefix('S').map(|flags| {
+                    let flag = |at: usize, set: char| flags.chars().nth(at) == Some(set);
+                    SubmoduleState { commit_changed: flag(0, 'C'), modified: flag(1, 'M'), untracked: flag(2, 'U') }
+                });
+                status.entries.push(StatusEntry { path: path.to_string(), original_path, index, worktree, submodule });
+            }
+            "u" => {
+                let fields = rest.splitn(10, ' ').collect::<Vec<_>>();
+                let (Some(xy), Some(path)) = (fields.first(), fields.get(9)) else {
+                    return Err(malformed());
+                };
+                status.conflicts.push(ConflictEntry { path: path.to_string(), conflict: conflict_kind(xy) });
+            }
+            "?" => status.untracked.push(rest.to_string()),
+            "!" => {}
+            _ => return Err(malformed()),
+        }
+    }
+    Ok(status)
+}
+
+pub fn working_tree_status(project_path: &str) -> Result<WorkingTreeStatus, String> {
+    let output = git_cli(
+        project_path,
+        &["status".into(), "--porcelain=v2".into(), "-z".into(), "--branch".into()],
+    )?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    parse_porcelain_v2(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// One path the merge could not resolve. `kinds` are the `CONFLICT (<kind>)` labels git reported
+/// for it, e.g. `contents`, `modify/delete` or `rename/rename`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub path: String,
+    pub kinds: Vec<String>,
+    pub messages: Vec<String>,
+    /// Conflict-marker sections of the merged file, each with a little surrounding context.
+    pub hunks: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergePreview {
+    /// Tree the merge would produce, with conflict markers in conflicted files. Empty when
+    /// libgit2 produced the preview and the merge conflicts.
+    pub tree: String,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+const HUNK_CONTEXT_LINES: usize = 3;
+const MAX_HUNKS_PER_FILE: usize = 5;
+const MAX_HUNK_LINES: usize = 60;
+
+/// Parse `git merge-tree --write-tree --name-only -z`: the tree id, the conflicted paths, an
+/// empty record, then messages as `<count> <path>... <type> <message>`.
+pub fn parse_merge_tree(output: &str) -> Result<MergePreview, String> {
+    let malformed = || "Unexpected git merge-tree output".to_string();
+    let mut records = output.split('\0');
+    let tree = records.next().filter(|tree| !tree.is_empty()).ok_or_else(malformed)?.to_string();
+    let mut conflicts = Vec::new();
+    for path in records.by_ref() {
+        if path.is_empty() {
+            break;
+        }
+        if !conflicts.iter().any(|conflict: &MergeConflict| conflict.path == path) {
+            conflicts.push(MergeConflict { path: path.to_string(), kinds: Vec::new(), messages: Vec::new(), hunks: Vec::new() });
+        }
+    }
+    while let Some(count) = records.next().filter(|count| !count.is_empty()) {
+        let count = count.parse::<usize>().map_err(|_| malformed())?;
+        let paths = records.by_ref().take(count).collect::<Vec<_>>();
+        let (Some(kind), Some(message)) = (records.next(), records.next()) else {
+            return Err(malformed());
+        };
+        let Some(kind) = kind.strip_prefix("CONFLICT (").and_then(|kind| kind.strip_suffix(')')) else {
+            continue;
+        };
+        for conflict in conflicts.iter_mut().filter(|conflict| paths.contains(&conflict.path.as_str())) {
+            if !conflict.kinds.iter().any(|known| known == kind) {
+                conflict.kinds.push(kind.to_string());
+            }
+            conflict.messages.push(message.trim_end().to_string());
+        }
+    }
+    Ok(MergePreview { tree, conflicts })
+}
+
+/// Sections between `<<<<<<<` and `>>>>>>>` with a few lines of context, capped per file.
+pub fn conflict_hunks(content: &str) -> Vec<String> {
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut hunks = Vec::new();
+    let mut index = 0;
+    while index < lines.len() && hunks.len() < MAX_HUNKS_PER_FILE {
+        if !lines[index].starts_with("<<<<<<<") {
+            index += 1;
+            continue;
+        }
+        let start = index.saturating_sub(HUNK_CONTEXT_LINES);
+        let end = lines[index..]
+            .iter()
+            .position(|line| line.starts_with(">>>>>>>"))
+            .map_or(lines.len(), |offset| index + offset + 1);
+        let end_with_context = (end + HUNK_CONTEXT_LINES).min(lines.len());
+        let mut hunk = lines[start..end_with_context].iter().take(MAX_HUNK_LINES).copied().collect::<Vec<_>>();
+        if end_with_context - start > MAX_HUNK_LINES {
+            hunk.push("…");
+        }
+        hunks.push(hunk.join("\n"));
+        index = end;
+    }
+    hunks
+}
+
+/// Merge `theirs` into `ours` in memory with `merge-tree --write-tree`: nothing in the working
+/// tree or refs changes. Conflicted files get a hunk preview from the merged tree. git older than
+/// 2.38 lacks `--write-tree`, so the merge is done through libgit2 instead.
+pub fn merge_preview(project_path: &str, ours: &str, theirs: &str) -> Result<MergePreview, String> {
+    let git = GitRepo::open(project_path)?;
+    let output = git_cli(
+        project_path,
+        &["merge-tree".into(), "--write-tree".into(), "--name-only".into(), "-z".into(), ours.into(), theirs.into()],
+    )?;
+    // Exit status 1 means the merge has conflicts; anything else besides 0 is a failure.
+    if !matches!(output.status.code(), Some(0 | 1)) {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if stderr.contains("--write-tree") || stderr.contains("usage:") {
+            return git.merge_preview(ours, theirs);
+        }
+        return Err(stderr);
+    }
+    let mut preview = parse_merge_tree(&String::from_utf8_lossy(&output.stdout))?;
+    for conflict in &mut preview.conflicts {
+        if let Ok(content) = git.read_file(&preview.tree, &conflict.path) {
+            conflict.hunks = conflict_hunks(&content);
+        }
+    }
+    Ok(preview)
+}
+
+/// Run the `git` CLI. Reserved for network operations and working-tree rewrites.
+pub fn git_cli(project_path: &str, args: &[String]) -> Result<Output, String> {
+    Command::new("git")
+        .args(args)
+        .current_dir(project_path)
+        .output()
+        .map_err(|e| format!("git {:?} failed: {}", args, e))
+}
+
+/// Trimmed stdout of a successful `git` run, or its stderr as the error.
+pub fn git_cli_text(project_path: &str, args: &[String]) -> Result<String, String> {
+    let output = git_cli(project_path, args)?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = Command::new("git")
+            .args(["-c", "user.name=FlowDesk", "-c", "user.email=flowdesk@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("git should run");
+        assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    }
+
+    fn commit(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).expect("create dir");
+        fs::write(path, content).expect("write file");
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-m", name]);
+    }
+
+    fn create_repo(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("flow-desk-git-service-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create repo dir");
+        git(&dir, &["init", "-q", "-b", "latest"]);
+        dir
+    }
+
+    #[test]
+    fn parses_porcelain_v2_renames_conflicts_and_submodules() {
+        let output = [
+            "# branch.oid 1111111111111111111111111111111111111111",
+            "# branch.head release/v1",
+            "# branch.upstream origin/release/v1",
+            "# branch.ab +2 -1",
+            "1 M. N... 100644 100644 100644 aaaa bbbb src/app config.json",
+            "2 R. N... 100644 100644 100644 aaaa aaaa R100 docs/新文件.md",
+            "docs/old name.md",
+            "1 .M SC.U 160000 160000 160000 cccc cccc vendor/lib",
+            "u UU N... 100644 100644 100644 100644 aaaa bbbb cccc package.json",
+            "u DU N... 100644 000000 100644 100644 aaaa 0000 cccc removed.txt",
+            "? notes draft.txt",
+            "",
+        ]
+        .join("\0");
+        let status = parse_porcelain_v2(&output).expect("parse status");
+        assert_eq!(status.branch.as_deref(), Some("release/v1"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/release/v1"));
+        assert_eq!((status.ahead, status.behind), (Some(2), Some(1)));
+        assert_eq!(status.entries.len(), 3);
+        assert_eq!(status.entries[0].path, "src/app config.json");
+        assert!(status.entries[0].staged() && !status.entries[0].unstaged());
+        assert_eq!(status.entries[1].path, "docs/新文件.md");
+        assert_eq!(status.entries[1].original_path.as_deref(), Some("docs/old name.md"));
+        assert_eq!(
+            status.entries[2].submodule,
+            Some(SubmoduleState { commit_changed: true, modified: false, untracked: true })
+        );
+        assert_eq!(
+            status.conflicts,
+            vec![
+                ConflictEntry { path: "package.json".to_string(), conflict: "bothModified" },
+                ConflictEntry { path: "removed.txt".to_string(), conflict: "deletedByUs" },
+            ]
+        );
+        assert_eq!(status.untracked, vec!["notes draft.txt"]);
+
+        let detached = parse_porcelain_v2("# branch.oid 1111\0# branch.head (detached)\0").unwrap();
+        assert_eq!(detached.branch, None);
+        assert!(detached.is_clean() && detached.ahead.is_none());
+    }
+
+    #[test]
+    fn lists_merge_conflicts_by_path_with_hunks() {
+        let dir = create_repo("merge-tree");
+        commit(&dir, "app config.txt", "a\nb\nc\n");
+        commit(&dir, "gone.txt", "keep\n");
+        commit(&dir, "clean.txt", "1\n2\n3\n4\n5\n6\n7\n8\n");
+        git(&dir, &["checkout", "-q", "-b", "release/v1"]);
+        commit(&dir, "app config.txt", "a\nrelease\nc\n");
+        git(&dir, &["rm", "-q", "gone.txt"]);
+        git(&dir, &["commit", "-q", "-m", "remove gone"]);
+        commit(&dir, "clean.txt", "1\n2\n3\n4\n5\n6\n7\nrelease\n");
+        git(&dir, &["checkout", "-q", "latest"]);
+        commit(&dir, "app config.txt", "a\nlatest\nc\n");
+        commit(&dir, "gone.txt", "changed\n");
+        commit(&dir, "clean.txt", "latest\n2\n3\n4\n5\n6\n7\n8\n");
+
+        let path = dir.to_str().unwrap();
+        let preview = merge_preview(path, "latest", "release/v1").expect("merge preview");
+        let summary = preview
+            .conflicts
+            .iter()
+            .map(|conflict| (conflict.path.as_str(), conflict.kinds.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![("app config.txt", vec!["contents".to_string()]), ("gone.txt", vec!["modify/delete".to_string()])]
+        );
+        let hunk = &preview.conflicts[0].hunks[0];
+        assert!(hunk.starts_with("a\n<<<<<<< latest\nlatest\n=======\nrelease\n>>>>>>> release/v1"), "{}", hunk);
+        assert!(preview.conflicts[1].hunks.is_empty());
+
+        assert!(merge_preview(path, "latest", "latest").unwrap().conflicts.is_empty());
+
+        // The libgit2 fallback used on git older than 2.38 finds the same conflicts.
+        let fallback = GitRepo::open(path).unwrap().merge_preview("latest", "release/v1").expect("libgit2 merge preview");
+        let fallback_summary = fallback
+            .conflicts
+            .iter()
+            .map(|conflict| (conflict.path.as_str(), conflict.kinds.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(fallback_summary, summary);
+        assert!(fallback.conflicts[0].hunks[0].starts_with("a\n<<<<<<< latest\nlatest\n=======\nrelease\n>>>>>>> release/v1"));
+        assert!(GitRepo::open(path).unwrap().merge_preview("latest", "latest").unwrap().conflicts.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lists_branches_with_metadata_filters_and_pages() {
+        let dir = create_repo("branches");
+        commit(&dir, "a.txt", "a\n");
+        git(&dir, &["branch", "feature/merged"]);
+        git(&dir, &["checkout", "-q", "-b", "feature/open"]);
+        commit(&dir, "b.txt", "b\n");
+        git(&dir, &["checkout", "-q", "latest"]);
+        git(&dir, &["remote", "add", "origin", "."]);
+        git(&dir, &["update-ref", "refs/remotes/origin/feature/open", "latest"]);
+        git(&dir, &["symbolic-ref", "refs/remotes/origin/HEAD", "refs/remotes/origin/latest"]);
+        git(&dir, &["update-ref", "refs/remotes/origin/latest", "latest"]);
+        git(&dir, &["branch", "--set-upstream-to", "origin/feature/open", "feature/open"]);
+
+        let repo = GitRepo::open(dir.to_str().unwrap()).expect("open repo");
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis() as i64;
+        let page = repo.list_branches(&BranchQuery::default(), now).unwrap();
+        assert_eq!(page.default_branch.as_deref(), Some("origin/latest"));
+        assert_eq!(page.total, 5);
+        let open = page.branches.iter().find(|branch| branch.name == "feature/open").unwrap();
+        assert_eq!(open.subject, "b.txt");
+        assert_eq!(open.author_name, "FlowDesk");
+        assert_eq!((open.upstream.as_deref(), open.ahead, open.behind), (Some("origin/feature/open"), Some(1), Some(0)));
+        assert!(!open.merged && !open.stale);
+        assert!(serde_json::to_value(open).unwrap()["committedAt"].is_i64());
+        let head = page.branches.iter().find(|branch| branch.is_head).unwrap();
+        assert_eq!(head.name, "latest");
+        let remote = page.branches.iter().find(|branch| branch.name == "origin/feature/open").unwrap();
+        assert_eq!((remote.kind, remote.remote.as_deref(), remote.merged), ("remote", Some("origin"), true));
+
+        let unmerged = BranchQuery { merged: Some(false), ..BranchQuery::default() };
+        let names = |page: BranchPage| page.branches.into_iter().map(|branch| branch.name).collect::<Vec<_>>();
+        assert_eq!(names(repo.list_branches(&unmerged, now).unwrap()), vec!["feature/open", "origin/latest"]);
+        let search = BranchQuery { kind: Some("local".to_string()), search: Some("FEATURE".to_string()), ..BranchQuery::default() };
+        assert_eq!(repo.list_branches(&search, now).unwrap().total, 2);
+        let paged = BranchQuery { offset: Some(1), limit: Some(2), ..BranchQuery::default() };
+        let paged = repo.list_branches(&paged, now).unwrap();
+        assert_eq!((paged.branches.len(), paged.offset, paged.has_more), (2, 1, true));
+        let stale = BranchQuery { stale: Some(true), ..BranchQuery::default() };
+        assert_eq!(repo.list_branches(&stale, now + 91 * 86_400_000).unwrap().total, 5);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_history_trees_and_tags_without_the_cli() {
+        let dir = create_repo("history");
+        commit(&dir, "config/app.json", "{\"a\": 1}\n");
+        git(&dir, &["checkout", "-q", "-b", "release/v1"]);
+        commit(&dir, "config/app.json", "{\"a\": 2}\n");
+        commit(&dir, "src/main.js", "main\n");
+
+        let repo = GitRepo::open(dir.to_str().unwrap()).expect("open repo");
+        assert_eq!(repo.ahead_behind("release/v1", "latest"), Ok((2, 0)));
+        assert_eq!(repo.merge_base("release/v1", "latest"), repo.resolve_commit("latest"));
+        assert_eq!(repo.is_ancestor("latest", "release/v1"), Ok(true));
+        assert_eq!(repo.is_ancestor("release/v1", "latest"), Ok(false));
+        assert_eq!(repo.diff_names("latest", "release/v1").unwrap(), vec!["config/app.json", "src/main.js"]);
+        assert_eq!(repo.read_file("latest", "config/app.json").unwrap(), "{\"a\": 1}\n");
+        assert!(repo.read_file("latest", "src/main.js").is_err());
+        assert_eq!(repo.list_files("release/v1").unwrap(), vec!["config/app.json", "src/main.js"]);
+
+        let head = repo.resolve_commit("HEAD").unwrap();
+        assert_eq!(repo.create_tag("v1.0.0", "HEAD").as_ref(), Ok(&head));
+        assert!(repo.create_tag("v1.0.0", "latest").is_err());
+        assert_eq!(repo.tag_commit("v1.0.0"), Some(head));
+        repo.delete_tag("v1.0.0").expect("delete tag");
+        assert_eq!(repo.tag_commit("v1.0.0"), None);
+
+        assert_eq!(repo.is_clean(), Ok(true));
+        fs::write(dir.join("notes.txt"), "draft").unwrap();
+        assert_eq!(repo.dirty_paths(false).unwrap(), Vec::<String>::new());
+        assert_eq!(repo.dirty_paths(true).unwrap(), vec!["notes.txt"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,5 +1,14 @@
 use crate::HttpResponse;
 
+/// `https://api.github.com`, or the GitHub Enterprise API root `git_list_remotes` reported.
+pub fn github_api_base(api_base_url: Option<&str>) -> String {
+    api_base_url
+        .map(|url| url.trim_end_matches('/'))
+        .filter(|url| !url.is_empty())
+        .unwrap_or("https://api.github.com")
+        .to_string()
+}
+
 fn github_headers(token: &str) -> Vec<(&str, String)> {
     vec![
         ("Authorization", format!("Bearer {}", token)),
@@ -10,6 +19,7 @@ fn github_headers(token: &str) -> Vec<(&str, String)> {
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn github_create_draft_pr(
     owner: String,
     repo: String,
@@ -18,9 +28,10 @@ pub async fn github_create_draft_pr(
     base: String,
     body: String,
     token: String,
+    api_base_url: Option<String>,
 ) -> Result<HttpResponse, String> {
     let client = reqwest::Client::new();
-    let url = format!("https://api.github.com/repos/{}/{}/pulls", owner, repo);
+    let url = format!("{}/repos/{}/{}/pulls", github_api_base(api_base_url.as_deref()), owner, repo);
 
     let payload = serde_json::json!({
         "title": title,
@@ -52,11 +63,12 @@ pub async fn github_list_open_prs(
     owner: String,
     repo: String,
     token: String,
+    api_base_url: Option<String>,
 ) -> Result<HttpResponse, String> {
     let client = reqwest::Client::new();
     let url = format!(
-        "https://api.github.com/repos/{}/{}/pulls?state=open&per_page=50",
-        owner, repo
+        "{}/repos/{}/{}/pulls?state=open&per_page=50",
+        github_api_base(api_base_url.as_deref()), owner, repo
     );
 
     let mut req = client.get(&url);
@@ -76,10 +88,10 @@ pub async fn github_list_open_prs(
 }
 
 #[tauri::command]
-pub async fn github_get_current_user(token: String) -> Result<HttpResponse, String> {
+pub async fn github_get_current_user(token: String, api_base_url: Option<String>) -> Result<HttpResponse, String> {
     let client = reqwest::Client::new();
 
-    let mut req = client.get("https://api.github.com/user");
+    let mut req = client.get(format!("{}/user", github_api_base(api_base_url.as_deref())));
     for (key, val) in github_headers(&token) {
         req = req.header(key, val);
     }
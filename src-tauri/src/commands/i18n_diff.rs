@@ -0,0 +1,390 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::config_diff::ConfigFormat;
+use super::i18n_formats::{parse_js_module, parse_po};
+
+/// Flattened message key (`home.title`, `steps[0]`) to its text.
+pub type Messages = BTreeMap<String, String>;
+
+pub const LOCALE_PLACEHOLDER: &str = "{locale}";
+/// Locale of gettext `.pot` templates, which hold source strings for every locale.
+pub const TEMPLATE_LOCALE: &str = "template";
+
+/// Directory names that mark translation files.
+const LOCALE_DIRS: &[&str] = &[
+    "i18n", "l10n", "intl", "lang", "langs", "language", "languages", "locale", "locales", "messages", "translation",
+    "translations",
+];
+/// File-name prefixes for `messages.fr.json`-style names that sit outside a locale directory.
+const LOCALE_FILE_PREFIXES: &[&str] = &["messages", "translation", "translations", "strings", "locale", "i18n"];
+const LOCALE_EXTENSIONS: &[&str] = &["json", "yaml", "yml", "po", "pot", "js", "mjs", "ts"];
+/// Three-letter ISO 639 codes worth recognising without a region; other three-letter names
+/// (`app`, `src`, `lib`) are far more likely to be directories.
+const THREE_LETTER_LANGUAGES: &[&str] = &["ast", "ckb", "fil", "haw", "kab", "yue"];
+
+/// A translation file identified by path convention. Files of the same set share a
+/// `namespace`, the path with the locale replaced by `{locale}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleFile {
+    pub path: String,
+    pub locale: String,
+    pub namespace: String,
+}
+
+/// Recognise `locales/<locale>/<name>.json`, `i18n/<locale>.json`,
+/// `locale/<locale>/LC_MESSAGES/<domain>.po` and `messages.<locale>.json` layouts, plus any
+/// gettext `.pot` template (locale [`TEMPLATE_LOCALE`]).
+/// `package.json`, `tsconfig.json` and other files without a locale in their path are not
+/// translation files.
+pub fn classify_locale_file(path: &str) -> Option<LocaleFile> {
+    let segments = path.split('/').collect::<Vec<_>>();
+    if segments.contains(&"node_modules") {
+        return None;
+    }
+    let (file_name, dirs) = segments.split_last()?;
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    if !LOCALE_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+        return None;
+    }
+    if extension.eq_ignore_ascii_case("pot") {
+        return Some(LocaleFile {
+            path: path.to_string(),
+            locale: TEMPLATE_LOCALE.to_string(),
+            namespace: path.to_string(),
+        });
+    }
+    let is_locale_dir = |dir: &str| LOCALE_DIRS.contains(&dir.to_lowercase().as_str());
+    let in_locale_dir = dirs.iter().any(|dir| is_locale_dir(dir)) || extension.eq_ignore_ascii_case("po");
+    let namespace = |index: usize, segment: String| {
+        let mut segments = segments.iter().map(|segment| segment.to_string()).collect::<Vec<_>>();
+        segments[index] = segment;
+        segments.join("/")
+    };
+    let file_index = segments.len() - 1;
+
+    // `i18n/zh-CN.json`
+    if in_locale_dir {
+        if let Some(locale) = normalize_locale(stem) {
+            return Some(LocaleFile {
+                path: path.to_string(),
+                locale,
+                namespace: namespace(file_index, format!("{}.{}", LOCALE_PLACEHOLDER, extension)),
+            });
+        }
+    }
+    // `messages.fr.json`, `i18n/app.zh_CN.json`
+    if let Some((prefix, candidate)) = stem.rsplit_once('.') {
+        let prefix_name = prefix.rsplit('.').next().unwrap_or(prefix).to_lowercase();
+        if in_locale_dir || LOCALE_FILE_PREFIXES.contains(&prefix_name.as_str()) {
+            if let Some(locale) = normalize_locale(candidate) {
+                return Some(LocaleFile {
+                    path: path.to_string(),
+                    locale,
+                    namespace: namespace(file_index, format!("{}.{}.{}", prefix, LOCALE_PLACEHOLDER, extension)),
+                });
+            }
+        }
+    }
+    // `locales/en/common.json`, `locale/fr/LC_MESSAGES/django.po`
+    for (index, dir) in dirs.iter().enumerate() {
+        let after_marker = index > 0 && is_locale_dir(dirs[index - 1]);
+        let before_lc_messages = dirs.get(index + 1).is_some_and(|next| *next == "LC_MESSAGES");
+        if after_marker || before_lc_messages {
+            if let Some(locale) = normalize_locale(dir) {
+                return Some(LocaleFile {
+                    path: path.to_string(),
+                    locale,
+                    namespace: namespace(index, LOCALE_PLACEHOLDER.to_string()),
+                });
+            }
+        }
+    }
+    None
+}
+
+/// `zh_cn`, `zh-CN` and `zh-Hans` to BCP 47 casing (`zh-CN`, `zh-Hans`); `None` if the text
+/// does not look like a locale code.
+pub fn normalize_locale(code: &str) -> Option<String> {
+    let mut parts = code.split(['-', '_']);
+    let language = parts.next()?;
+    let is_lower = |text: &str| text.chars().all(|ch| ch.is_ascii_lowercase());
+    let has_subtags = code.contains(['-', '_']);
+    let language_ok = is_lower(language)
+        && (language.len() == 2 || (language.len() == 3 && (has_subtags || THREE_LETTER_LANGUAGES.contains(&language))));
+    if !language_ok {
+        return None;
+    }
+    let mut normalized = language.to_string();
+    let mut seen_region = false;
+    for (index, part) in parts.enumerate() {
+        let subtag = if part.len() == 4 && index == 0 && part.chars().all(|ch| ch.is_ascii_alphabetic()) {
+            let (first, rest) = part.split_at(1);
+            format!("{}{}", first.to_uppercase(), rest.to_lowercase())
+        } else if !seen_region
+            && ((part.len() == 2 && part.chars().all(|ch| ch.is_ascii_alphabetic()))
+                || (part.len() == 3 && part.chars().all(|ch| ch.is_ascii_digit())))
+        {
+            seen_region = true;
+            part.to_uppercase()
+        } else {
+            return None;
+        };
+        normalized.push('-');
+        normalized.push_str(&subtag);
+    }
+    Some(normalized)
+}
+
+/// Parse a translation file into flattened messages; `None` when the format has no parser
+/// and the file can only be compared as a whole. JSON, YAML and JS/TS modules flatten nested
+/// objects into `a.b` keys; gettext files use the `msgid` (see [`parse_po`]).
+pub fn parse_messages(path: &str, content: &str) -> Option<Result<Messages, String>> {
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension.to_lowercase())?;
+    let parsed = match extension.as_str() {
+        _ if content.trim().is_empty() && LOCALE_EXTENSIONS.contains(&extension.as_str()) => Ok(Messages::new()),
+        "json" => serde_json::from_str::<Value>(content)
+            .map(|value| flatten_messages(&value))
+            .map_err(|e| format!("Invalid JSON: {}", e)),
+        "yaml" | "yml" => ConfigFormat::Yaml
+            .parse(content)
+            .map(|value| flatten_messages(without_locale_root(&value))),
+        "po" | "pot" => parse_po(content, extension == "pot"),
+        "js" | "mjs" | "ts" => parse_js_module(content).map(|value| flatten_messages(&value)),
+        _ => return None,
+    };
+    Some(parsed)
+}
+
+/// Rails-style YAML nests everything under the locale (`en: {...}`); keys should not repeat it.
+fn without_locale_root(value: &Value) -> &Value {
+    let Some(map) = value.as_object().filter(|map| map.len() == 1) else { return value };
+    match map.iter().next() {
+        Some((root, inner)) if normalize_locale(root).is_some() && inner.is_object() => inner,
+        _ => value,
+    }
+}
+
+/// Like `flatten_json_keys`, but string leaves keep their text instead of a JSON literal.
+pub fn flatten_messages(value: &Value) -> Messages {
+    fn walk(prefix: &str, value: &Value, messages: &mut Messages) {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    let next = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    walk(&next, child, messages);
+                }
+            }
+            Value::Array(items) => {
+                for (index, child) in items.iter().enumerate() {
+                    walk(&format!("{}[{}]", prefix, index), child, messages);
+                }
+            }
+            Value::String(text) => {
+                messages.insert(prefix.to_string(), text.clone());
+            }
+            Value::Null => {
+                messages.insert(prefix.to_string(), String::new());
+            }
+            other => {
+                messages.insert(prefix.to_string(), other.to_string());
+            }
+        }
+    }
+    let mut messages = Messages::new();
+    walk("", value, &mut messages);
+    messages
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct I18nKeyChange {
+    pub key: String,
+    /// `added`, `changed` or `removed`.
+    pub change_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<String>,
+}
+
+pub fn diff_messages(old: &Messages, new: &Messages) -> Vec<I18nKeyChange> {
+    let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (old_value, new_value) = (old.get(key), new.get(key));
+            let change_type = match (old_value, new_value) {
+                (None, Some(_)) => "added",
+                (Some(_), None) => "removed",
+                (Some(old_value), Some(new_value)) if old_value != new_value => "changed",
+                _ => return None,
+            };
+            Some(I18nKeyChange {
+                key: key.clone(),
+                change_type: change_type.to_string(),
+                old_value: old_value.cloned(),
+                new_value: new_value.cloned(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleCoverage {
+    pub locale: String,
+    /// `None` when the locale has no file for this namespace yet.
+    pub file: Option<String>,
+    pub translated: usize,
+    pub total: usize,
+    /// Keys added to the source locale in this release that this locale lacks or leaves empty.
+    pub missing_added: Vec<String>,
+    pub missing_total: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceCoverage {
+    pub namespace: String,
+    pub source_locale: String,
+    pub source_file: String,
+    pub added_keys: Vec<String>,
+    pub locales: Vec<LocaleCoverage>,
+}
+
+impl NamespaceCoverage {
+    pub fn missing_added_count(&self) -> usize {
+        self.locales.iter().map(|locale| locale.missing_added.len()).sum()
+    }
+}
+
+/// Compare every locale of one namespace against the source locale. `files` holds the parsed
+/// files of the namespace (the source among them) and `locales` every locale known in the repo,
+/// so a locale without a file shows up as fully missing. `None` without a source file.
+pub fn namespace_coverage(
+    namespace: &str,
+    source_locale: &str,
+    files: &[(LocaleFile, Messages)],
+    locales: &BTreeSet<String>,
+    added_keys: &[String],
+) -> Option<NamespaceCoverage> {
+    let (source_file, source) = files.iter().find(|(file, _)| file.locale == source_locale)?;
+    let total = source.len();
+    let coverage = locales
+        .iter()
+        .filter(|locale| locale.as_str() != source_locale && locale.as_str() != TEMPLATE_LOCALE)
+        .map(|locale| {
+            let file = files.iter().find(|(file, _)| &file.locale == locale);
+            let translated_key = |key: &String| {
+                file.and_then(|(_, messages)| messages.get(key))
+                    .is_some_and(|text| !text.trim().is_empty())
+            };
+            let translated = source.keys().filter(|key| translated_key(key)).count();
+            LocaleCoverage {
+                locale: locale.clone(),
+                file: file.map(|(file, _)| file.path.clone()),
+                translated,
+                total,
+                missing_added: added_keys.iter().filter(|key| !translated_key(key)).cloned().collect(),
+                missing_total: total - translated,
+            }
+        })
+        .collect();
+    Some(NamespaceCoverage {
+        namespace: namespace.to_string(),
+        source_locale: source_locale.to_string(),
+        source_file: source_file.path.clone(),
+        added_keys: added_keys.to_vec(),
+        locales: coverage,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(pairs: &[(&str, &str)]) -> Messages {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn classifies_locale_files_by_convention() {
+        let locale = |path: &str| classify_locale_file(path).map(|file| (file.locale, file.namespace));
+        assert_eq!(
+            locale("web/public/locales/zh_CN/common.json"),
+            Some(("zh-CN".to_string(), "web/public/locales/{locale}/common.json".to_string()))
+        );
+        assert_eq!(locale("src/i18n/en.json"), Some(("en".to_string(), "src/i18n/{locale}.json".to_string())));
+        assert_eq!(locale("src/lang/zh-Hans.ts"), Some(("zh-Hans".to_string(), "src/lang/{locale}.ts".to_string())));
+        assert_eq!(
+            locale("app/locale/pt_BR/LC_MESSAGES/django.po"),
+            Some(("pt-BR".to_string(), "app/locale/{locale}/LC_MESSAGES/django.po".to_string()))
+        );
+        assert_eq!(locale("config/messages.ja.json"), Some(("ja".to_string(), "config/messages.{locale}.json".to_string())));
+
+        for path in [
+            "package.json",
+            "tsconfig.json",
+            "src/i18n/index.ts",
+            "src/locales/app/config.json",
+            "node_modules/pkg/locales/en.json",
+            "src/i18n/en.css",
+        ] {
+            assert_eq!(classify_locale_file(path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn parses_each_locale_format_into_the_same_keys() {
+        let expected = messages(&[("home.title", "Accueil")]);
+        assert_eq!(parse_messages("locales/fr.json", r#"{"home":{"title":"Accueil"}}"#), Some(Ok(expected.clone())));
+        assert_eq!(parse_messages("config/locales/fr.yml", "fr:\n  home:\n    title: Accueil\n"), Some(Ok(expected.clone())));
+        assert_eq!(parse_messages("src/lang/fr.ts", "export default { home: { title: 'Accueil' } }"), Some(Ok(expected)));
+        assert_eq!(
+            parse_messages("locale/fr/LC_MESSAGES/app.po", "msgid \"Home\"\nmsgstr \"Accueil\"\n"),
+            Some(Ok(messages(&[("Home", "Accueil")])))
+        );
+        assert_eq!(parse_messages("locales/fr.properties", "a=b"), None);
+        assert_eq!(classify_locale_file("locale/app.pot").map(|file| file.locale), Some(TEMPLATE_LOCALE.to_string()));
+    }
+
+    #[test]
+    fn diffs_added_changed_and_removed_keys() {
+        let old = flatten_messages(&serde_json::json!({ "home": { "title": "Home", "old": "Gone" }, "count": 1 }));
+        let new = flatten_messages(&serde_json::json!({ "home": { "title": "Start", "new": "Hi" }, "count": 1 }));
+        let changes = diff_messages(&old, &new)
+            .into_iter()
+            .map(|change| (change.key, change.change_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                ("home.new".to_string(), "added".to_string()),
+                ("home.old".to_string(), "removed".to_string()),
+                ("home.title".to_string(), "changed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_added_keys_missing_in_other_locales() {
+        let file = |locale: &str| classify_locale_file(&format!("locales/{}/app.json", locale)).unwrap();
+        let files = vec![
+            (file("en"), messages(&[("a", "A"), ("b", "B"), ("c", "C")])),
+            (file("zh-CN"), messages(&[("a", "甲"), ("b", "")])),
+        ];
+        let locales = ["en", "zh-CN", "ja"].iter().map(|locale| locale.to_string()).collect();
+        let added = vec!["b".to_string(), "c".to_string()];
+        let coverage = namespace_coverage("locales/{locale}/app.json", "en", &files, &locales, &added).unwrap();
+
+        assert_eq!(coverage.locales.len(), 2);
+        let ja = &coverage.locales[0];
+        assert_eq!((ja.locale.as_str(), ja.file.as_deref(), ja.missing_total), ("ja", None, 3));
+        let zh = &coverage.locales[1];
+        assert_eq!(zh.missing_added, vec!["b", "c"]);
+        assert_eq!((zh.translated, zh.total), (1, 3));
+        assert_eq!(coverage.missing_added_count(), 4);
+    }
+}
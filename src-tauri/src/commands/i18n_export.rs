@@ -0,0 +1,331 @@
+use calamine::{open_workbook_from_rs, Reader, Xlsx};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::escape::escape;
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::path::Path;
+
+use super::i18n_diff::normalize_locale;
+
+const XLIFF_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:2.0";
+const FIXED_COLUMNS: [&str; 5] = ["Repo", "Namespace", "Key", "Change", "Context"];
+
+/// One message a translator works on: the source-locale text plus the current text in every
+/// other locale of the same namespace (empty when missing).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationUnit {
+    pub repo_key: String,
+    pub namespace: String,
+    pub key: String,
+    /// How the source text changed in this release: `added`, `changed` or `unchanged`.
+    pub change_type: String,
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_source: Option<String>,
+    pub source_file: String,
+    pub targets: BTreeMap<String, String>,
+}
+
+impl TranslationUnit {
+    /// Where the text lives and what it replaced, for the translator's context column/note.
+    pub fn context(&self) -> String {
+        match &self.previous_source {
+            Some(previous) => format!("{} (was: {})", self.source_file, previous),
+            None => self.source_file.clone(),
+        }
+    }
+
+    /// `repoKey:namespace`, which XLIFF `<file original>` carries so an import finds its way back.
+    fn origin(&self) -> String {
+        format!("{}:{}", self.repo_key, self.namespace)
+    }
+}
+
+/// A translated text read back from a vendor file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedTranslation {
+    pub repo_key: String,
+    pub namespace: String,
+    pub key: String,
+    pub locale: String,
+    pub text: String,
+}
+
+/// A spreadsheet with one row per unit: the fixed columns, `Source (<locale>)`, then one
+/// column per target locale headed by its code.
+pub fn units_xlsx(units: &[TranslationUnit], source_locale: &str, locales: &[String]) -> Result<Vec<u8>, String> {
+    let xlsx_error = |e: rust_xlsxwriter::XlsxError| format!("Failed to write XLSX: {}", e);
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Translations").map_err(xlsx_error)?;
+    let header = Format::new().set_bold();
+    let wrap = Format::new().set_text_wrap();
+
+    let source_header = format!("Source ({})", source_locale);
+    let headers = FIXED_COLUMNS
+        .iter()
+        .copied()
+        .chain([source_header.as_str()])
+        .chain(locales.iter().map(String::as_str));
+    for (column, title) in headers.enumerate() {
+        let column = column as u16;
+        sheet.write_string_with_format(0, column, title, &header).map_err(xlsx_error)?;
+        let width = if column < 3 { 24 } else if column < 5 { 16 } else { 40 };
+        sheet.set_column_width(column, width).map_err(xlsx_error)?;
+    }
+    sheet.set_freeze_panes(1, 3).map_err(xlsx_error)?;
+
+    for (index, unit) in units.iter().enumerate() {
+        let row = index as u32 + 1;
+        let context = unit.context();
+        let cells = [&unit.repo_key, &unit.namespace, &unit.key, &unit.change_type, &context, &unit.source]
+            .into_iter()
+            .cloned()
+            .chain(locales.iter().map(|locale| unit.targets.get(locale).cloned().unwrap_or_default()));
+        for (column, text) in cells.enumerate() {
+            sheet
+                .write_string_with_format(row, column as u16, &text, &wrap)
+                .map_err(xlsx_error)?;
+        }
+    }
+    workbook.save_to_buffer().map_err(xlsx_error)
+}
+
+/// An XLIFF 2.0 document for one target locale; each namespace is a `<file>` and each key a
+/// `<unit name>`. Units that already have a translation are marked `translated`.
+pub fn units_xliff(units: &[TranslationUnit], source_locale: &str, target_locale: &str) -> String {
+    let mut files = BTreeMap::<String, Vec<&TranslationUnit>>::new();
+    for unit in units {
+        files.entry(unit.origin()).or_default().push(unit);
+    }
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xliff xmlns=\"{}\" version=\"2.0\" srcLang=\"{}\" trgLang=\"{}\">\n",
+        XLIFF_NAMESPACE,
+        escape(source_locale),
+        escape(target_locale)
+    );
+    for (file_index, (origin, units)) in files.iter().enumerate() {
+        xml.push_str(&format!("  <file id=\"f{}\" original=\"{}\">\n", file_index + 1, escape(origin.as_str())));
+        for (unit_index, unit) in units.iter().enumerate() {
+            let target = unit.targets.get(target_locale).map(String::as_str).unwrap_or("");
+            xml.push_str(&format!("    <unit id=\"u{}\" name=\"{}\">\n", unit_index + 1, escape(unit.key.as_str())));
+            xml.push_str("      <notes>\n");
+            xml.push_str(&format!("        <note category=\"context\">{}</note>\n", escape(unit.context().as_str())));
+            xml.push_str(&format!("        <note category=\"change\">{}</note>\n", escape(unit.change_type.as_str())));
+            xml.push_str("      </notes>\n");
+            let state = if target.trim().is_empty() { "initial" } else { "translated" };
+            xml.push_str(&format!("      <segment state=\"{}\">\n", state));
+            xml.push_str(&format!("        <source>{}</source>\n", escape(unit.source.as_str())));
+            if !target.is_empty() {
+                xml.push_str(&format!("        <target>{}</target>\n", escape(target)));
+            }
+            xml.push_str("      </segment>\n    </unit>\n");
+        }
+        xml.push_str("  </file>\n");
+    }
+    xml.push_str("</xliff>\n");
+    xml
+}
+
+/// Read translations back from an `.xlsx` written by [`units_xlsx`] or an XLIFF 2.0 file
+/// written by [`units_xliff`]. Empty targets are dropped.
+pub fn read_translations(path: &Path, bytes: &[u8]) -> Result<Vec<ImportedTranslation>, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let translations = match extension.as_str() {
+        "xlsx" => read_xlsx(bytes)?,
+        "xlf" | "xliff" => read_xliff(&String::from_utf8_lossy(bytes))?,
+        _ => return Err(format!("Unsupported translation file {}: use .xlsx, .xlf or .xliff", path.display())),
+    };
+    Ok(translations
+        .into_iter()
+        .filter(|translation| !translation.text.trim().is_empty())
+        .collect())
+}
+
+fn read_xlsx(bytes: &[u8]) -> Result<Vec<ImportedTranslation>, String> {
+    let mut workbook: Xlsx<_> =
+        open_workbook_from_rs(Cursor::new(bytes)).map_err(|e| format!("Invalid XLSX file: {}", e))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| "XLSX file has no worksheet".to_string())?
+        .map_err(|e| format!("Invalid XLSX worksheet: {}", e))?;
+    let mut rows = range.rows();
+    let headers = rows
+        .next()
+        .ok_or_else(|| "XLSX worksheet is empty".to_string())?
+        .iter()
+        .map(|cell| cell.to_string().trim().to_string())
+        .collect::<Vec<_>>();
+    let column = |title: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(title))
+            .ok_or_else(|| format!("XLSX worksheet has no {} column", title))
+    };
+    let (repo_column, namespace_column, key_column) = (column("Repo")?, column("Namespace")?, column("Key")?);
+    let locale_columns = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, header)| !header.starts_with("Source"))
+        .filter_map(|(index, header)| normalize_locale(header).map(|locale| (index, locale)))
+        .collect::<Vec<_>>();
+    if locale_columns.is_empty() {
+        return Err("XLSX worksheet has no locale columns".to_string());
+    }
+
+    let mut translations = Vec::new();
+    for row in rows {
+        let cell = |index: usize| row.get(index).map(|cell| cell.to_string()).unwrap_or_default();
+        let key = cell(key_column);
+        if key.trim().is_empty() {
+            continue;
+        }
+        for (index, locale) in &locale_columns {
+            translations.push(ImportedTranslation {
+                repo_key: cell(repo_column),
+                namespace: cell(namespace_column),
+                key: key.clone(),
+                locale: locale.clone(),
+                text: cell(*index),
+            });
+        }
+    }
+    Ok(translations)
+}
+
+fn read_xliff(xml: &str) -> Result<Vec<ImportedTranslation>, String> {
+    let xml_error = |e: &dyn std::fmt::Display| format!("Invalid XLIFF file: {}", e);
+    let attribute = |element: &BytesStart, name: &str| -> Result<Option<String>, String> {
+        match element.try_get_attribute(name).map_err(|e| xml_error(&e))? {
+            Some(value) => Ok(Some(value.unescape_value().map_err(|e| xml_error(&e))?.to_string())),
+            None => Ok(None),
+        }
+    };
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut locale = None;
+    let mut origin = None;
+    let mut unit_key = None;
+    let mut target: Option<String> = None;
+    let mut in_target = false;
+    let mut translations = Vec::new();
+
+    loop {
+        match reader.read_event().map_err(|e| xml_error(&e))? {
+            Event::Start(element) => match element.local_name().as_ref() {
+                b"xliff" => {
+                    let code = attribute(&element, "trgLang")?
+                        .ok_or_else(|| "XLIFF file has no trgLang".to_string())?;
+                    locale = Some(normalize_locale(&code).ok_or_else(|| format!("Unknown XLIFF trgLang: {}", code))?);
+                }
+                b"file" => origin = attribute(&element, "original")?,
+                b"unit" => {
+                    unit_key = attribute(&element, "name")?;
+                    target = None;
+                }
+                b"target" => {
+                    in_target = true;
+                    target.get_or_insert_with(String::new);
+                }
+                _ => {}
+            },
+            Event::Text(text) if in_target => {
+                let text = text.unescape().map_err(|e| xml_error(&e))?;
+                target.get_or_insert_with(String::new).push_str(&text);
+            }
+            Event::CData(data) if in_target => {
+                target
+                    .get_or_insert_with(String::new)
+                    .push_str(&String::from_utf8_lossy(&data.into_inner()));
+            }
+            Event::End(element) => match element.local_name().as_ref() {
+                b"target" => in_target = false,
+                b"unit" => {
+                    let (Some(locale), Some(origin), Some(key)) = (&locale, &origin, unit_key.take()) else {
+                        continue;
+                    };
+                    let Some((repo_key, namespace)) = origin.split_once(':') else {
+                        return Err(format!("XLIFF file original is not repo:namespace: {}", origin));
+                    };
+                    if let Some(text) = target.take() {
+                        translations.push(ImportedTranslation {
+                            repo_key: repo_key.to_string(),
+                            namespace: namespace.to_string(),
+                            key,
+                            locale: locale.clone(),
+                            text,
+                        });
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if locale.is_none() {
+        return Err("Not an XLIFF 2.0 file".to_string());
+    }
+    Ok(translations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units() -> Vec<TranslationUnit> {
+        vec![TranslationUnit {
+            repo_key: "web".to_string(),
+            namespace: "locales/{locale}/app.json".to_string(),
+            key: "cart.total".to_string(),
+            change_type: "changed".to_string(),
+            source: "Total: \"{amount}\" & <tax>".to_string(),
+            previous_source: Some("Total".to_string()),
+            source_file: "locales/en/app.json".to_string(),
+            targets: BTreeMap::from([("zh-CN".to_string(), "合计".to_string()), ("ja".to_string(), String::new())]),
+        }]
+    }
+
+    #[test]
+    fn round_trips_translations_through_xliff() {
+        let xliff = units_xliff(&units(), "en", "ja");
+        assert!(xliff.contains("trgLang=\"ja\""));
+        assert!(xliff.contains("<source>Total: &quot;{amount}&quot; &amp; &lt;tax&gt;</source>"));
+        assert!(xliff.contains("state=\"initial\""));
+
+        let translated = xliff.replace(
+            "<segment state=\"initial\">",
+            "<segment state=\"translated\">",
+        )
+        .replace("</source>\n", "</source>\n        <target>合計: &quot;{amount}&quot;</target>\n");
+        let imported = read_translations(Path::new("ja.xlf"), translated.as_bytes()).unwrap();
+        assert_eq!(
+            imported,
+            vec![ImportedTranslation {
+                repo_key: "web".to_string(),
+                namespace: "locales/{locale}/app.json".to_string(),
+                key: "cart.total".to_string(),
+                locale: "ja".to_string(),
+                text: "合計: \"{amount}\"".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn round_trips_translations_through_xlsx() {
+        let locales = vec!["ja".to_string(), "zh-CN".to_string()];
+        let bytes = units_xlsx(&units(), "en", &locales).unwrap();
+        let imported = read_translations(Path::new("vendor.xlsx"), &bytes).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!((imported[0].locale.as_str(), imported[0].text.as_str()), ("zh-CN", "合计"));
+        assert_eq!(imported[0].key, "cart.total");
+        assert!(read_translations(Path::new("vendor.csv"), b"").is_err());
+    }
+}
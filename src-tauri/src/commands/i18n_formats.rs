@@ -0,0 +1,629 @@
+use serde_json::{Map, Number, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::collections::BTreeSet;
+
+use super::i18n_diff::{normalize_locale, Messages};
+
+/// Key separator between `msgctxt` and `msgid`, so the same text in two contexts stays apart.
+pub const PO_CONTEXT_SEPARATOR: &str = "|";
+
+#[derive(Default)]
+struct PoEntry {
+    context: Option<String>,
+    id: Option<String>,
+    plural_id: Option<String>,
+    strings: Vec<(usize, String)>,
+    fuzzy: bool,
+}
+
+/// Parse gettext `.po`/`.pot` into `msgid` (or `msgctxt|msgid`) keys. Plural forms become
+/// `msgid[0]`, `msgid[1]`, ... The header and obsolete (`#~`) entries are skipped and fuzzy
+/// entries count as untranslated. A `.pot` has no translations, so its values are the source
+/// text instead.
+pub fn parse_po(content: &str, template: bool) -> Result<Messages, String> {
+    let mut messages = Messages::new();
+    let mut entry = PoEntry::default();
+    // Which string the next bare `"..."` continuation line extends.
+    let mut target: Option<&'static str> = None;
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = raw_line.trim();
+        let line_error = |message: &str| format!("Invalid PO at line {}: {}", index + 1, message);
+        if line.is_empty() {
+            finish_po_entry(&mut entry, template, &mut messages);
+            target = None;
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            // A comment after a complete entry starts the next one, even without a blank line.
+            if entry.id.is_some() && !entry.strings.is_empty() {
+                finish_po_entry(&mut entry, template, &mut messages);
+                target = None;
+            }
+            if comment.starts_with(',') && comment.contains("fuzzy") {
+                entry.fuzzy = true;
+            }
+            continue;
+        }
+        if line.starts_with('"') {
+            let text = po_string(line).ok_or_else(|| line_error("unterminated string"))?;
+            let slot = match target {
+                Some("msgctxt") => entry.context.as_mut(),
+                Some("msgid") => entry.id.as_mut(),
+                Some("msgid_plural") => entry.plural_id.as_mut(),
+                Some(_) => entry.strings.last_mut().map(|(_, text)| text),
+                None => None,
+            };
+            slot.ok_or_else(|| line_error("string outside an entry"))?.push_str(&text);
+            continue;
+        }
+        let (keyword, rest) = line.split_once(char::is_whitespace).ok_or_else(|| line_error(line))?;
+        let text = po_string(rest.trim()).ok_or_else(|| line_error("expected a quoted string"))?;
+        if (keyword == "msgid" || keyword == "msgctxt") && entry.id.is_some() && !entry.strings.is_empty() {
+            finish_po_entry(&mut entry, template, &mut messages);
+        }
+        target = Some(match keyword {
+            "msgctxt" => {
+                entry.context = Some(text);
+                "msgctxt"
+            }
+            "msgid" => {
+                entry.id = Some(text);
+                "msgid"
+            }
+            "msgid_plural" => {
+                entry.plural_id = Some(text);
+                "msgid_plural"
+            }
+            "msgstr" => {
+                entry.strings.push((0, text));
+                "msgstr"
+            }
+            _ => {
+                let index = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .ok_or_else(|| line_error(&format!("unknown keyword {}", keyword)))?;
+                entry.strings.push((index, text));
+                "msgstr"
+            }
+        });
+    }
+    finish_po_entry(&mut entry, template, &mut messages);
+    Ok(messages)
+}
+
+fn finish_po_entry(entry: &mut PoEntry, template: bool, messages: &mut Messages) {
+    let PoEntry { context, id, plural_id, strings, fuzzy } = std::mem::take(entry);
+    let Some(id) = id.filter(|id| !id.is_empty()) else { return };
+    let key = match context {
+        Some(context) => format!("{}{}{}", context, PO_CONTEXT_SEPARATOR, id),
+        None => id.clone(),
+    };
+    let text = |index: usize, translated: String| {
+        if template {
+            if index == 0 { id.clone() } else { plural_id.clone().unwrap_or_default() }
+        } else if fuzzy {
+            String::new()
+        } else {
+            translated
+        }
+    };
+    if plural_id.is_some() {
+        for (index, translated) in strings {
+            messages.insert(format!("{}[{}]", key, index), text(index, translated));
+        }
+    } else {
+        let translated = strings.into_iter().next().map(|(_, text)| text).unwrap_or_default();
+        messages.insert(key, text(0, translated));
+    }
+}
+
+/// Unquote a PO string literal (C escapes).
+fn po_string(literal: &str) -> Option<String> {
+    let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+        match chars.next()? {
+            'n' => text.push('\n'),
+            't' => text.push('\t'),
+            'r' => text.push('\r'),
+            other => text.push(other),
+        }
+    }
+    Some(text)
+}
+
+/// Parse the object literal of an `export default {...}` (or `module.exports = {...}`) locale
+/// module. Only data is supported: nested objects and arrays of strings, numbers, booleans and
+/// `null`, with quoted or bare keys, comments and trailing commas. Anything computed (spreads,
+/// identifiers, functions, `${}` in template strings) is an error rather than a guess.
+pub fn parse_js_module(content: &str) -> Result<Value, String> {
+    let start = ["export default", "module.exports"]
+        .iter()
+        .find_map(|marker| content.find(marker).map(|index| index + marker.len()))
+        .ok_or_else(|| "No `export default` or `module.exports` in locale module".to_string())?;
+    let mut parser = JsParser { chars: content.chars().collect(), pos: content[..start].chars().count() };
+    parser.skip_trivia();
+    if parser.peek() == Some('=') {
+        parser.pos += 1;
+    }
+    parser.skip_trivia();
+    if parser.peek() != Some('{') {
+        return Err(parser.error("expected an object literal"));
+    }
+    parser.value()
+}
+
+struct JsParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|ch| **ch == '\n').count() + 1;
+        format!("Unsupported locale module syntax at line {}: {}", line, message)
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.pos += 1;
+            }
+            let next = self.chars.get(self.pos + 1).copied();
+            match (self.peek(), next) {
+                (Some('/'), Some('/')) => {
+                    while self.peek().is_some_and(|ch| ch != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    self.pos += 2;
+                    while self.pos < self.chars.len() && !(self.peek() == Some('*') && self.chars.get(self.pos + 1) == Some(&'/')) {
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.chars.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_trivia();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some(quote @ ('\'' | '"' | '`')) => self.string(quote).map(Value::String),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => self.number(),
+            Some(ch) if is_identifier_char(ch) => match self.identifier().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                other => Err(self.error(&format!("expected a literal, found {}", other))),
+            },
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            self.skip_trivia();
+            let key = match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(map));
+                }
+                Some(quote @ ('\'' | '"' | '`')) => self.string(quote)?,
+                Some(ch) if is_identifier_char(ch) => self.identifier(),
+                _ => return Err(self.error("expected a property name")),
+            };
+            self.skip_trivia();
+            if self.peek() != Some(':') {
+                return Err(self.error(&format!("expected ':' after {}", key)));
+            }
+            self.pos += 1;
+            let value = self.value()?;
+            map.insert(key, value);
+            if !self.separator('}')? {
+                self.pos += 1;
+                return Ok(Value::Object(map));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            if !self.separator(']')? {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+        }
+    }
+
+    /// After a member: `true` when a `,` was consumed, `false` when `close` comes next.
+    fn separator(&mut self, close: char) -> Result<bool, String> {
+        self.skip_trivia();
+        match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(ch) if ch == close => Ok(false),
+            _ => Err(self.error(&format!("expected ',' or '{}'", close))),
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<String, String> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            let ch = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match ch {
+                _ if ch == quote => return Ok(text),
+                '\n' if quote != '`' => return Err(self.error("unterminated string")),
+                '$' if quote == '`' && self.peek() == Some('{') => {
+                    return Err(self.error("template string interpolation"));
+                }
+                '\\' => {
+                    let escaped = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match escaped {
+                        'n' => text.push('\n'),
+                        't' => text.push('\t'),
+                        'r' => text.push('\r'),
+                        '\n' => {}
+                        'u' => text.push(self.unicode_escape()?),
+                        other => text.push(other),
+                    }
+                }
+                _ => text.push(ch),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let braced = self.peek() == Some('{');
+        if braced {
+            self.pos += 1;
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|ch| ch.is_ascii_hexdigit()) && (braced || self.pos - start < 4) {
+            self.pos += 1;
+        }
+        let digits = self.chars[start..self.pos].iter().collect::<String>();
+        if braced {
+            if self.peek() != Some('}') {
+                return Err(self.error("invalid unicode escape"));
+            }
+            self.pos += 1;
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        self.pos += 1;
+        while self.peek().is_some_and(|ch| ch.is_ascii_alphanumeric() || ch == '.' || ch == '_') {
+            self.pos += 1;
+        }
+        let literal = self.chars[start..self.pos].iter().filter(|ch| **ch != '_').collect::<String>();
+        serde_json::from_str::<Number>(&literal)
+            .map(Value::Number)
+            .map_err(|_| self.error(&format!("unsupported number {}", literal)))
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(is_identifier_char) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '$'
+}
+
+/// Write `updates` (flattened key to text) into a locale file's content. Returns the new
+/// content and the keys that could not be placed. JSON and YAML gain missing keys (YAML is
+/// re-serialized, so comments are lost); `.po` files only fill existing entries, in place.
+pub fn update_messages(path: &str, content: &str, updates: &Messages) -> Result<(String, Vec<String>), String> {
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension.to_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "json" | "yaml" | "yml" => {
+            // Parsed as a YAML value for both formats: its mappings keep the file's key order.
+            let yaml = extension != "json";
+            let mut root = if content.trim().is_empty() {
+                YamlValue::Mapping(Mapping::new())
+            } else if yaml {
+                serde_yaml::from_str(content).map_err(|e| format!("Invalid YAML: {}", e))?
+            } else {
+                serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?
+            };
+            // Rails-style `en: {...}` files keep their locale root.
+            let locale_root = root
+                .as_mapping()
+                .filter(|map| map.len() == 1)
+                .and_then(|map| map.iter().next())
+                .and_then(|(key, value)| key.as_str().filter(|key| normalize_locale(key).is_some() && value.is_mapping()))
+                .map(str::to_string);
+            let target = match &locale_root {
+                Some(key) if yaml => &mut root[key.as_str()],
+                _ => &mut root,
+            };
+            let missing = updates
+                .iter()
+                .filter(|(key, text)| !set_message(target, key, text))
+                .map(|(key, _)| key.clone())
+                .collect();
+            let written = if yaml {
+                serde_yaml::to_string(&root).map_err(|e| format!("Failed to write YAML: {}", e))?
+            } else {
+                serde_json::to_string_pretty(&root).map_err(|e| format!("Failed to write JSON: {}", e))? + "\n"
+            };
+            Ok((written, missing))
+        }
+        "po" => Ok(update_po(content, updates)),
+        _ => Err(format!("Importing into .{} locale files is not supported; update {} by hand", extension, path)),
+    }
+}
+
+/// Set `a.b[0].c` in a message tree, creating missing mappings. `false` when the path runs into a
+/// non-mapping value or an array index that does not exist.
+fn set_message(root: &mut YamlValue, key: &str, text: &str) -> bool {
+    let mut segments = Vec::new();
+    for part in key.split('.') {
+        let (name, indexes) = part.split_once('[').map_or((part, ""), |(name, rest)| (name, rest));
+        if !name.is_empty() {
+            segments.push(Err(name));
+        }
+        for index in indexes.split('[').filter(|index| !index.is_empty()) {
+            match index.trim_end_matches(']').parse::<usize>() {
+                Ok(index) => segments.push(Ok(index)),
+                Err(_) => return false,
+            }
+        }
+    }
+    let mut current = root;
+    for segment in segments {
+        if current.is_null() {
+            *current = YamlValue::Mapping(Mapping::new());
+        }
+        let next = match segment {
+            Err(name) => current.as_mapping_mut().map(|map| map.entry(name.into()).or_insert(YamlValue::Null)),
+            Ok(index) => current.as_sequence_mut().and_then(|items| items.get_mut(index)),
+        };
+        let Some(next) = next else { return false };
+        current = next;
+    }
+    if current.is_mapping() || current.is_sequence() {
+        return false;
+    }
+    *current = YamlValue::String(text.to_string());
+    true
+}
+
+/// Replace the `msgstr` of entries whose key is in `updates`, keeping everything else as is.
+fn update_po(content: &str, updates: &Messages) -> (String, Vec<String>) {
+    let mut lines = Vec::new();
+    let mut used = BTreeSet::new();
+    let (mut context, mut id, mut plural) = (None::<String>, String::new(), false);
+    let mut target = "";
+    let mut replacing = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('"') {
+            if replacing {
+                continue;
+            }
+            let text = po_string(trimmed).unwrap_or_default();
+            match target {
+                "msgctxt" => context.get_or_insert_with(String::new).push_str(&text),
+                "msgid" => id.push_str(&text),
+                _ => {}
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+        replacing = false;
+        let keyword = trimmed.split_whitespace().next().unwrap_or("");
+        let text = trimmed.split_once(char::is_whitespace).and_then(|(_, rest)| po_string(rest.trim()));
+        if target.starts_with("msgstr") && (keyword == "msgctxt" || keyword == "msgid") {
+            (context, id, plural) = (None, String::new(), false);
+        }
+        match keyword {
+            "msgctxt" => context = Some(text.unwrap_or_default()),
+            "msgid" => id = text.unwrap_or_default(),
+            "msgid_plural" => plural = true,
+            _ if keyword.starts_with("msgstr") && !id.is_empty() => {
+                let base = match &context {
+                    Some(context) => format!("{}{}{}", context, PO_CONTEXT_SEPARATOR, id),
+                    None => id.clone(),
+                };
+                let key = match keyword.strip_prefix("msgstr[").and_then(|rest| rest.strip_suffix(']')) {
+                    Some(index) if plural => format!("{}[{}]", base, index),
+                    _ => base,
+                };
+                if let Some(text) = updates.get(&key) {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    lines.push(format!("{}{} {}", indent, keyword, po_quote(text)));
+                    used.insert(key);
+                    replacing = true;
+                    target = "msgstr";
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        if !keyword.is_empty() && !keyword.starts_with('#') {
+            target = if keyword.starts_with("msgstr") { "msgstr" } else { keyword };
+        }
+        lines.push(line.to_string());
+    }
+    let mut written = lines.join("\n");
+    if content.ends_with('\n') {
+        written.push('\n');
+    }
+    let missing = updates.keys().filter(|key| !used.contains(*key)).cloned().collect();
+    (written, missing)
+}
+
+fn po_quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_po_entries_with_context_plurals_and_continuations() {
+        let po = r#"# Translator comment
+msgid ""
+msgstr ""
+"Language: fr\n"
+
+#: src/app.py:10
+msgid "Save"
+msgstr "Enregistrer"
+
+msgctxt "menu"
+msgid "Open"
+msgstr ""
+"Ouvrir"
+
+#, fuzzy
+msgid "Close"
+msgstr "Fermer"
+
+msgid "One file"
+msgid_plural "%d files"
+msgstr[0] "Un fichier"
+msgstr[1] "%d fichiers"
+
+#~ msgid "Old"
+#~ msgstr "Ancien"
+"#;
+        let messages = parse_po(po, false).unwrap();
+        let pairs = messages.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                ("Close", ""),
+                ("One file[0]", "Un fichier"),
+                ("One file[1]", "%d fichiers"),
+                ("Save", "Enregistrer"),
+                ("menu|Open", "Ouvrir"),
+            ]
+        );
+
+        let template = parse_po("msgid \"Save\"\nmsgstr \"\"\n\nmsgid \"Line\\n\"\nmsgstr \"\"\n", true).unwrap();
+        assert_eq!(template.get("Save").map(String::as_str), Some("Save"));
+        assert_eq!(template.get("Line\n").map(String::as_str), Some("Line\n"));
+        assert!(parse_po("msgid \"Save\nmsgstr \"\"", false).is_err());
+    }
+
+    #[test]
+    fn writes_translations_back_into_locale_files() {
+        let updates = |pairs: &[(&str, &str)]| -> Messages {
+            pairs.iter().map(|(key, text)| (key.to_string(), text.to_string())).collect()
+        };
+
+        let json = "{\n  \"b\": \"B\",\n  \"a\": { \"x\": \"\" },\n  \"list\": [\"one\"]\n}\n";
+        let (written, missing) =
+            update_messages("ja.json", json, &updates(&[("a.x", "エックス"), ("a.new", "新"), ("list[3]", "x"), ("b.c", "x")])).unwrap();
+        assert_eq!(missing, vec!["b.c", "list[3]"]);
+        let value = serde_json::from_str::<Value>(&written).unwrap();
+        assert_eq!(value["a"]["x"], "エックス");
+        assert_eq!(value["a"]["new"], "新");
+        assert!(written.find("\"b\"").unwrap() < written.find("\"a\"").unwrap());
+
+        let (yaml, _) =
+            update_messages("fr.yml", "fr:\n  home:\n    title: ''\n    body: ''\n", &updates(&[("home.title", "Accueil")])).unwrap();
+        assert_eq!(yaml, "fr:\n  home:\n    title: Accueil\n    body: ''\n");
+
+        let po = "# header\nmsgid \"Save\"\nmsgstr \"\"\n\nmsgctxt \"menu\"\nmsgid \"Open\"\nmsgstr \"\"\n\"old\"\n\nmsgid \"File\"\nmsgid_plural \"Files\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n";
+        let (written, missing) = update_messages(
+            "fr.po",
+            po,
+            &updates(&[("Save", "Enregistrer \"tout\""), ("menu|Open", "Ouvrir"), ("File[1]", "Fichiers"), ("Gone", "x")]),
+        )
+        .unwrap();
+        assert_eq!(missing, vec!["Gone"]);
+        assert_eq!(
+            written,
+            "# header\nmsgid \"Save\"\nmsgstr \"Enregistrer \\\"tout\\\"\"\n\nmsgctxt \"menu\"\nmsgid \"Open\"\nmsgstr \"Ouvrir\"\n\nmsgid \"File\"\nmsgid_plural \"Files\"\nmsgstr[0] \"\"\nmsgstr[1] \"Fichiers\"\n"
+        );
+        assert!(update_messages("fr.ts", "export default {}", &updates(&[("a", "b")])).is_err());
+    }
+
+    #[test]
+    fn parses_export_default_locale_modules() {
+        let module = r#"
+// Generated by hand
+import type { Messages } from './types'
+
+export default {
+  home: {
+    title: 'Accueil', /* short */
+    "sub-title": "Bienvenueé",
+    greeting: `Bonjour
+tout le monde`,
+  },
+  count: 3,
+  enabled: true,
+  steps: ['un', 'deux',],
+} satisfies Messages
+"#;
+        assert_eq!(
+            parse_js_module(module).unwrap(),
+            json!({
+                "home": { "title": "Accueil", "sub-title": "Bienvenueé", "greeting": "Bonjour\ntout le monde" },
+                "count": 3,
+                "enabled": true,
+                "steps": ["un", "deux"]
+            })
+        );
+        assert_eq!(parse_js_module("module.exports = { a: 'b' };").unwrap(), json!({ "a": "b" }));
+
+        let spread = parse_js_module("export default {\n  ...base,\n}").unwrap_err();
+        assert!(spread.contains("line 2"), "{}", spread);
+        assert!(parse_js_module("export default { a: `Hi ${name}` }").is_err());
+        assert!(parse_js_module("export default messages").is_err());
+    }
+}
@@ -1,8 +1,25 @@
 pub mod http;
 pub mod jira;
 pub mod git;
+pub mod git_remote;
+pub mod git_service;
 pub mod github;
 pub mod ai;
+pub mod config_diff;
+pub mod config_merge;
+pub mod confluence;
+pub mod i18n_diff;
+pub mod i18n_export;
+pub mod i18n_formats;
 pub mod sidecar;
 pub mod release;
+pub mod release_audit;
+pub mod release_build;
+pub mod release_notes;
+pub mod release_pool;
+pub mod release_profile;
+pub mod release_promotion;
+pub mod release_session;
+pub mod release_store;
+pub mod release_template;
 pub mod agent;
@@ -1,8 +1,33 @@
-use std::process::Command;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
-use std::{env, fs};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
 use crate::HttpResponse;
+use super::release_session::{
+    Approval, ApprovalDecision, ApprovalRefusal, ReleaseSession, SessionStatus, StepStatus, APPROVAL_TTL_MS,
+};
+use super::config_diff::{diff_config_file, ConfigFormat};
+use super::config_merge::{merge_config_file, ConfigSelection, MergeOutcome};
+use super::i18n_diff::{
+    classify_locale_file, diff_messages, namespace_coverage, parse_messages, LocaleFile, Messages, LOCALE_PLACEHOLDER,
+    TEMPLATE_LOCALE,
+};
+use super::i18n_export::{read_translations, units_xliff, units_xlsx, TranslationUnit};
+use super::i18n_formats::update_messages;
+use super::confluence::{markdown_to_html, markdown_to_storage, ConfluenceClient};
+use super::release_build::{cancel_build, new_build_id, resolve_build_command, run_streaming_build, BuildRequest};
+use super::release_notes::{issue_from_jira, markdown_html_document, pr_from_github, ReleaseNotes};
+use super::release_pool::{run_bounded, ReleaseProgress};
+use super::git_remote::{default_pr_remote, parse_remote_url, Forge};
+use super::github::github_api_base;
+use super::git_service::{git_cli, git_cli_text, merge_preview, GitRepo};
+use super::release_profile::ReleaseProfile;
+use super::release_promotion::{
+    latest_deployment, promotion_gate, promotion_step_id, DeployedRef, Deployment, EnvironmentCheck, PromotionRefusal,
+};
+use super::release_template::{validate_template_name, ReleaseTemplate, TemplateError};
+use super::release_audit::{append_audit_entry, read_audit_log, verify_audit_log, AuditEntry};
+use super::release_store::ReleaseStore;
 use base64::Engine;
 use serde_json::{json, Value};
 
@@ -97,156 +122,122 @@ pub async fn read_package_version(project_path: String) -> Result<serde_json::Va
     .map_err(|e| format!("Task join failed: {}", e))?
 }
 
-/// Fetch + check whether a branch exists on remote (origin).
+/// Fetch + check whether a branch exists on remote (`origin` unless given).
 /// Returns JSON with `{ exists: bool, ref: string }`.
 #[tauri::command]
 pub async fn git_remote_branch_exists(
     project_path: String,
     branch_name: String,
+    remote: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    let remote = remote.unwrap_or_else(|| "origin".to_string());
     tauri::async_runtime::spawn_blocking(move || {
-        let _ = Command::new("git")
-            .args(["fetch", "origin", "--prune"])
-            .current_dir(&project_path)
-            .output();
-
-        let output = Command::new("git")
-            .args(["branch", "-r", "--list", &format!("origin/{}", branch_name)])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("git branch -r failed: {}", e))?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let exists = !stdout.trim().is_empty();
+        let _ = git_cli(&project_path, &["fetch".into(), remote.clone(), "--prune".into()]);
+        let exists = GitRepo::open(&project_path)?.remote_branch_exists(&remote, &branch_name);
 
         Ok(serde_json::json!({
             "exists": exists,
-            "ref": format!("origin/{}", branch_name)
+            "ref": format!("{}/{}", remote, branch_name)
         }))
     })
     .await
     .map_err(|e| format!("Task join failed: {}", e))?
 }
 
-/// Check behind/ahead between two branches and detect merge conflicts
-/// via `git merge-tree` (dry run, no working tree modification).
+/// Check behind/ahead between two branches and list the files that conflict when they are merged,
+/// via `git merge-tree --write-tree` (dry run, no working tree modification).
 #[tauri::command]
 pub async fn git_merge_conflict_check(
     project_path: String,
     source_branch: String,
     target_branch: String,
+    remote: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    let remote = remote.unwrap_or_else(|| "origin".to_string());
     tauri::async_runtime::spawn_blocking(move || {
-        let _ = Command::new("git")
-            .args(["fetch", "origin", "--prune"])
-            .current_dir(&project_path)
-            .output();
-
-        let source_ref = format!("origin/{}", source_branch);
-        let target_ref = format!("origin/{}", target_branch);
-
-        // behind: commits in source not in target
-        let behind_out = Command::new("git")
-            .args(["rev-list", "--count", &format!("{}..{}", target_ref, source_ref)])
-            .current_dir(&project_path)
-            .output();
-        let behind: u32 = behind_out
-            .as_ref()
-            .ok()
-            .filter(|o| o.status.success())
-            .map(|o| String::from_utf8_lossy(&o.stdout).trim().parse().unwrap_or(0))
-            .unwrap_or(0);
-
-        // ahead: commits in target not in source
-        let ahead_out = Command::new("git")
-            .args(["rev-list", "--count", &format!("{}..{}", source_ref, target_ref)])
-            .current_dir(&project_path)
-            .output();
-        let ahead: u32 = ahead_out
-            .as_ref()
-            .ok()
-            .filter(|o| o.status.success())
-            .map(|o| String::from_utf8_lossy(&o.stdout).trim().parse().unwrap_or(0))
-            .unwrap_or(0);
-
-        // Merge base
-        let base_out = Command::new("git")
-            .args(["merge-base", &source_ref, &target_ref])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("merge-base failed: {}", e))?;
+        let _ = git_cli(&project_path, &["fetch".into(), remote.clone(), "--prune".into()]);
+        let git = GitRepo::open(&project_path)?;
+
+        let source_ref = format!("{}/{}", remote, source_branch);
+        let target_ref = format!("{}/{}", remote, target_branch);
 
-        if !base_out.status.success() {
+        // behind: commits in source not in target; ahead: commits in target not in source
+        let (behind, ahead) = git.ahead_behind(&source_ref, &target_ref).unwrap_or((0, 0));
+
+        if git.merge_base(&source_ref, &target_ref).is_err() {
             return Ok(serde_json::json!({
                 "behind": behind,
                 "ahead": ahead,
                 "hasConflict": false,
+                "conflicts": [],
                 "error": "No common ancestor found"
             }));
         }
 
-        let base_sha = String::from_utf8_lossy(&base_out.stdout).trim().to_string();
-
-        // git merge-tree <base> <source> <target> — prints conflict markers if any
-        let merge_tree_out = Command::new("git")
-            .args(["merge-tree", &base_sha, &source_ref, &target_ref])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("merge-tree failed: {}", e))?;
-
-        let merge_output = String::from_utf8_lossy(&merge_tree_out.stdout).to_string();
-        let has_conflict = merge_output.contains("<<<<<<<")
-            || merge_output.contains("changed in both");
-
+        let preview = merge_preview(&project_path, &source_ref, &target_ref)?;
         Ok(serde_json::json!({
             "behind": behind,
             "ahead": ahead,
-            "hasConflict": has_conflict,
-            "conflictPreview": if has_conflict {
-                merge_output.chars().take(2000).collect::<String>()
-            } else {
-                String::new()
-            }
+            "hasConflict": !preview.conflicts.is_empty(),
+            "conflicts": preview.conflicts
         }))
     })
     .await
     .map_err(|e| format!("Task join failed: {}", e))?
 }
 
-/// Execute `pnpm run build` in a project directory.
-/// Returns status, truncated stdout/stderr, and elapsed time.
+/// Build a project directory with its detected (or profile-overridden) command, streaming output
+/// as `release-build-log-{buildId}`. Kept under its old name; it no longer always runs pnpm.
+/// Returns status, truncated stdout/stderr, elapsed time, the resolved command and the full log path.
 #[tauri::command]
-pub async fn run_pnpm_build(project_path: String) -> Result<serde_json::Value, String> {
+pub async fn run_pnpm_build(
+    app: tauri::AppHandle,
+    project_path: String,
+    build_id: Option<String>,
+    repo_key: Option<String>,
+    workspace_path: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let profile = ReleaseProfile::load(workspace_path.as_deref())?;
+    let build_id = build_id.unwrap_or_else(|| new_build_id(repo_key.as_deref().unwrap_or("")));
     tauri::async_runtime::spawn_blocking(move || {
-        let start = std::time::Instant::now();
-
-        let output = Command::new("pnpm")
-            .args(["run", "build"])
-            .current_dir(&project_path)
-            .output()
-            .map_err(|e| format!("Failed to run pnpm build: {}", e))?;
-
-        let elapsed_ms = start.elapsed().as_millis() as u64;
-        let success = output.status.success();
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-
-        let max_log = 4000;
-        let truncated_stdout: String = stdout.chars().rev().take(max_log).collect::<String>().chars().rev().collect();
-        let truncated_stderr: String = stderr.chars().rev().take(max_log).collect::<String>().chars().rev().collect();
-
-        Ok(serde_json::json!({
-            "success": success,
-            "exitCode": output.status.code().unwrap_or(-1),
-            "stdout": truncated_stdout,
-            "stderr": truncated_stderr,
-            "elapsedMs": elapsed_ms
-        }))
+        let request = repo_build_request(repo_key.as_deref().unwrap_or(""), &project_path, build_id, None, &profile)?;
+        run_streaming_build(&request, Some(app))
     })
     .await
     .map_err(|e| format!("Task join failed: {}", e))?
 }
 
+/// Stop a running build and its child processes.
+#[tauri::command]
+pub async fn release_cancel_build(build_id: String) -> Result<serde_json::Value, String> {
+    let cancelled = cancel_build(&build_id);
+    Ok(serde_json::json!({
+        "ok": cancelled,
+        "buildId": build_id,
+        "summary": if cancelled { "已请求取消构建。" } else { "没有找到正在运行的该构建。" }
+    }))
+}
+
+fn repo_build_request(
+    repo_key: &str,
+    project_path: &str,
+    build_id: String,
+    session_id: Option<&str>,
+    profile: &ReleaseProfile,
+) -> Result<BuildRequest, String> {
+    let resolved = resolve_build_command(Path::new(project_path), profile.build_overrides.get(repo_key))?;
+    let log_path = release_store()?.build_log_path(session_id, &build_id)?;
+    Ok(BuildRequest {
+        build_id,
+        project_path: project_path.to_string(),
+        program: resolved.program.clone(),
+        args: resolved.args.clone(),
+        log_path,
+        timeouts: profile.build_timeouts(),
+        resolved: Some(resolved),
+    })
+}
+
 /// List all open PRs for a repo whose head branch contains a Jira issue key pattern.
 /// Used for matching Jira issues -> GitHub PRs.
 #[tauri::command]
@@ -254,11 +245,12 @@ pub async fn github_list_all_open_prs(
     owner: String,
     repo: String,
     token: String,
+    api_base_url: Option<String>,
 ) -> Result<HttpResponse, String> {
     let client = reqwest::Client::new();
     let url = format!(
-        "https://api.github.com/repos/{}/{}/pulls?state=open&per_page=100",
-        owner, repo
+        "{}/repos/{}/{}/pulls?state=open&per_page=100",
+        github_api_base(api_base_url.as_deref()), owner, repo
     );
 
     let response = client
@@ -911,10 +903,12 @@ pub async fn github_list_merged_prs(
     repo: String,
     base_branch: String,
     token: String,
+    api_base_url: Option<String>,
 ) -> Result<HttpResponse, String> {
     let client = reqwest::Client::new();
     let url = format!(
-        "https://api.github.com/repos/{}/{}/pulls?state=closed&base={}&per_page=100",
+        "{}/repos/{}/{}/pulls?state=closed&base={}&per_page=100",
+        github_api_base(api_base_url.as_deref()),
         owner,
         repo,
         urlencoding::encode(&base_branch)
@@ -936,16 +930,12 @@ pub async fn github_list_merged_prs(
     Ok(HttpResponse { status, body })
 }
 
-fn release_store_path() -> PathBuf {
-    env::temp_dir().join("flowdesk-release-store.json")
+fn release_store() -> Result<ReleaseStore, String> {
+    ReleaseStore::open_default()
 }
 
 fn release_artifact_dir(session_id: &str) -> Result<PathBuf, String> {
-    let dir = env::temp_dir()
-        .join("flowdesk-release-artifacts")
-        .join(session_id);
-    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create artifact directory: {}", e))?;
-    Ok(dir)
+    release_store()?.artifact_dir(session_id)
 }
 
 fn timestamp_string() -> String {
@@ -956,169 +946,167 @@ fn timestamp_string() -> String {
         .to_string()
 }
 
-fn load_release_store() -> Result<Value, String> {
-    let path = release_store_path();
-    if !path.exists() {
-        return Ok(json!({ "sessions": [] }));
-    }
-
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read release store: {}", e))?;
-    serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse release store: {}", e))
-}
-
-fn save_release_store(store: &Value) -> Result<(), String> {
-    let path = release_store_path();
-    let serialized = serde_json::to_string_pretty(store)
-        .map_err(|e| format!("Failed to serialize release store: {}", e))?;
-    fs::write(&path, serialized)
-        .map_err(|e| format!("Failed to persist release store: {}", e))
+fn read_release_session(session_id: &str) -> Result<ReleaseSession, String> {
+    let value = release_store()?.read_session(session_id)?;
+    Ok(ReleaseSession::from_value(value)?)
 }
 
-fn get_sessions_mut(store: &mut Value) -> Result<&mut Vec<Value>, String> {
-    store
-        .get_mut("sessions")
-        .and_then(Value::as_array_mut)
-        .ok_or_else(|| "Release store is missing sessions array".to_string())
+fn release_profile_for(session: &ReleaseSession) -> Result<ReleaseProfile, String> {
+    ReleaseProfile::load(Some(&session.workspace_path))
 }
 
-fn find_session_index(sessions: &[Value], session_id: &str) -> Option<usize> {
-    sessions.iter().position(|session| {
-        session
-            .get("sessionId")
-            .and_then(Value::as_str)
-            .map(|id| id == session_id)
-            .unwrap_or(false)
+/// Apply `mutate` to the latest persisted session under the store lock.
+fn update_release_session<T>(
+    session_id: &str,
+    mutate: impl FnOnce(&mut ReleaseSession) -> Result<T, String>,
+) -> Result<(Value, T), String> {
+    release_store()?.update_session(session_id, |value| {
+        let mut session = ReleaseSession::from_value(value.clone())?;
+        let output = mutate(&mut session)?;
+        *value = session.to_value()?;
+        Ok(output)
     })
 }
 
-fn read_release_session_value(session_id: &str) -> Result<Value, String> {
-    let store = load_release_store()?;
-    let sessions = store
-        .get("sessions")
-        .and_then(Value::as_array)
-        .ok_or_else(|| "Release store is missing sessions array".to_string())?;
-    let index = find_session_index(sessions, session_id)
-        .ok_or_else(|| format!("Release Session not found: {}", session_id))?;
-    Ok(sessions[index].clone())
+/// Persist the outcome of a step the backend just executed so gates do not depend on
+/// the frontend having synced its copy of the session first.
+fn record_release_step_outcome(session_id: &str, step_id: &str, result: &Value) -> Result<(), String> {
+    let ok = result.get("ok").and_then(Value::as_bool).unwrap_or(false);
+    let summary = result.get("summary").and_then(Value::as_str).unwrap_or("");
+    update_release_session(session_id, |session| {
+        session.record_step_outcome(step_id, ok, summary, result.clone(), &timestamp_string());
+        Ok(())
+    })?;
+    Ok(())
 }
 
-fn upsert_release_session_value(session: Value) -> Result<Value, String> {
-    let session_id = session
-        .get("sessionId")
-        .and_then(Value::as_str)
-        .ok_or_else(|| "session.sessionId is required".to_string())?
-        .to_string();
-
-    let mut store = load_release_store()?;
-    let sessions = get_sessions_mut(&mut store)?;
-
-    match find_session_index(sessions, &session_id) {
-        Some(index) => sessions[index] = session.clone(),
-        None => sessions.push(session.clone()),
+/// Check the step gate and consume its approval in one locked update, so two concurrent
+/// invocations cannot both spend the same approval.
+/// Returns the session and the id of the consumed approval.
+fn authorize_dangerous_step(session_id: &str, step_id: &str) -> Result<Result<(ReleaseSession, String), Value>, String> {
+    let (saved, consumed) = update_release_session(session_id, |session| {
+        session.ensure_step_can_start(step_id)?;
+        Ok(session.consume_approval(step_id, &timestamp_string()))
+    })?;
+    match consumed {
+        Ok(approval) => Ok(Ok((ReleaseSession::from_value(saved)?, approval.approval_id))),
+        Err(refusal) => Ok(Err(approval_required_response(step_id, refusal))),
     }
-
-    save_release_store(&store)?;
-    Ok(session)
 }
 
-fn session_step_status(session: &Value, step_id: &str) -> String {
-    session
-        .get("steps")
-        .and_then(|steps| steps.get(step_id))
-        .and_then(|step| step.get("status"))
-        .and_then(Value::as_str)
-        .unwrap_or("pending")
-        .to_string()
+/// Hand the approval back when the gated operation failed before changing any repo, so a
+/// fetch or checkout error does not cost a fresh human approval.
+fn settle_approval(session_id: &str, approval_id: &str, started: bool) -> Result<(), String> {
+    if !started {
+        update_release_session(session_id, |session| {
+            session.refund_approval(approval_id);
+            Ok(())
+        })?;
+    }
+    Ok(())
 }
 
-fn session_step_result(session: &Value, step_id: &str) -> Value {
-    session
-        .get("steps")
-        .and_then(|steps| steps.get(step_id))
-        .and_then(|step| step.get("result"))
-        .cloned()
-        .unwrap_or_else(|| json!(null))
+/// Whether any per-repo entry of a step result matches `predicate`.
+fn any_repo_result(result: &Result<Value, String>, predicate: impl Fn(&Value) -> bool) -> bool {
+    result
+        .as_ref()
+        .ok()
+        .and_then(|result| result.get("results"))
+        .and_then(Value::as_array)
+        .is_some_and(|results| results.iter().any(predicate))
 }
 
-fn repo_key(repo: &Value) -> String {
-    repo.get("key").and_then(Value::as_str).unwrap_or("").to_string()
+/// Who ran a release command: the caller-supplied actor, else the OS user.
+fn release_actor(actor: Option<String>) -> String {
+    actor
+        .filter(|actor| !actor.trim().is_empty())
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .unwrap_or_else(|| "unknown".to_string())
 }
 
-fn repo_path(repo: &Value) -> String {
-    repo.get("path").and_then(Value::as_str).unwrap_or("").to_string()
-}
+/// Append the invocation and its outcome to the session's audit log. A failed audit write
+/// is reported on the result rather than hiding the outcome of an action that already ran.
+fn audit_release_action(
+    session_id: &str,
+    action: &str,
+    actor: &str,
+    inputs: Value,
+    result: &mut Result<Value, String>,
+) {
+    let (outcome, summary, shas) = match result {
+        Ok(value) => {
+            let outcome = if value.get("blocked").and_then(Value::as_bool).unwrap_or(false) {
+                "blocked"
+            } else if value.get("ok").and_then(Value::as_bool).unwrap_or(false) {
+                "ok"
+            } else {
+                "failed"
+            };
+            let shas = value
+                .get("results")
+                .and_then(Value::as_array)
+                .map(|results| {
+                    results
+                        .iter()
+                        .filter(|item| item.get("sha").and_then(Value::as_str).is_some_and(|sha| !sha.is_empty()))
+                        .map(|item| {
+                            json!({
+                                "repoKey": item.get("repoKey").cloned().unwrap_or(Value::Null),
+                                "sha": item.get("sha").cloned().unwrap_or(Value::Null),
+                                "tag": item.get("tag").cloned().unwrap_or(Value::Null)
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let summary = value.get("summary").and_then(Value::as_str).unwrap_or("").to_string();
+            (outcome, summary, shas)
+        }
+        Err(error) => ("error", error.clone(), Vec::new()),
+    };
 
-fn run_git_output(project_path: &str, args: &[String]) -> Result<std::process::Output, String> {
-    Command::new("git")
-        .args(args)
-        .current_dir(project_path)
-        .output()
-        .map_err(|e| format!("git {:?} failed: {}", args, e))
+    let entry = AuditEntry {
+        ts: timestamp_string(),
+        session_id: session_id.to_string(),
+        action: action.to_string(),
+        actor: actor.to_string(),
+        inputs,
+        outcome: outcome.to_string(),
+        summary,
+        shas,
+        ..AuditEntry::default()
+    };
+    let appended = release_store().and_then(|store| append_audit_entry(&store, entry));
+    if let (Err(audit_error), Ok(Value::Object(map))) = (appended, result) {
+        map.insert("auditError".to_string(), json!(audit_error));
+    }
 }
 
-fn run_git_text(project_path: &str, args: &[String]) -> Result<String, String> {
-    let output = run_git_output(project_path, args)?;
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
-    }
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+/// Structured refusal returned instead of running a dangerous step without a usable approval.
+fn approval_required_response(step_id: &str, refusal: ApprovalRefusal) -> Value {
+    json!({
+        "ok": false,
+        "blocked": true,
+        "requiresApproval": true,
+        "stepId": step_id,
+        "reason": refusal,
+        "summary": format!("{} 需要有效的人工审批：{}", step_id, refusal.describe())
+    })
 }
 
-fn git_status_clean(project_path: &str) -> Result<bool, String> {
-    let output = run_git_text(project_path, &vec!["status".into(), "--porcelain".into()])?;
-    Ok(output.trim().is_empty())
+fn repo_key(repo: &Value) -> String {
+    repo.get("key").and_then(Value::as_str).unwrap_or("").to_string()
 }
 
-fn git_diff_name_only(project_path: &str, left_ref: &str, right_ref: &str) -> Result<Vec<String>, String> {
-    let output = run_git_text(project_path, &vec![
-        "diff".into(),
-        "--name-only".into(),
-        left_ref.into(),
-        right_ref.into(),
-    ])?;
-    Ok(output
-        .lines()
-        .map(str::trim)
-        .filter(|line| !line.is_empty())
-        .map(str::to_string)
-        .collect())
-}
-
-fn git_show_file(project_path: &str, git_ref: &str, relative_path: &str) -> Result<String, String> {
-    run_git_text(project_path, &vec![
-        "show".into(),
-        format!("{}:{}", git_ref, relative_path),
-    ])
-}
-
-fn flatten_json_keys(prefix: &str, value: &Value, acc: &mut Vec<(String, String)>) {
-    match value {
-        Value::Object(map) => {
-            for (key, child) in map {
-                let next_prefix = if prefix.is_empty() {
-                    key.to_string()
-                } else {
-                    format!("{}.{}", prefix, key)
-                };
-                flatten_json_keys(&next_prefix, child, acc);
-            }
-        }
-        Value::Array(items) => {
-            for (index, child) in items.iter().enumerate() {
-                flatten_json_keys(&format!("{}[{}]", prefix, index), child, acc);
-            }
-        }
-        _ => {
-            acc.push((prefix.to_string(), value.to_string()));
-        }
-    }
+fn repo_path(repo: &Value) -> String {
+    repo.get("path").and_then(Value::as_str).unwrap_or("").to_string()
 }
 
 fn detect_config_file(path: &str) -> bool {
     let lower = path.to_lowercase();
-    lower.ends_with(".json")
+    ConfigFormat::detect(path) == Some(ConfigFormat::Env)
+        || lower.ends_with(".json")
         || lower.ends_with(".yaml")
         || lower.ends_with(".yml")
         || lower.ends_with(".toml")
@@ -1126,39 +1114,18 @@ fn detect_config_file(path: &str) -> bool {
         || lower.contains("/public/")
 }
 
-fn detect_i18n_file(path: &str) -> bool {
-    let lower = path.to_lowercase();
-    lower.contains("i18n")
-        || lower.contains("locale")
-        || lower.contains("locales")
-        || lower.contains("messages")
-        || lower.ends_with(".po")
-        || lower.ends_with(".json")
-}
-
 #[tauri::command]
 pub async fn release_session_create(
     version: Option<String>,
     environment: String,
+    workspace_path: Option<String>,
 ) -> Result<Value, String> {
+    // Fail early on a broken profile instead of at the first git step.
+    ReleaseProfile::load(workspace_path.as_deref())?;
     let session_id = format!("release-session-{}", timestamp_string());
-    let now = timestamp_string();
-    let session = json!({
-        "sessionId": session_id,
-        "version": version.unwrap_or_default(),
-        "environment": environment,
-        "status": "draft",
-        "steps": {},
-        "approvals": [],
-        "artifacts": [],
-        "repos": [],
-        "blockedSteps": [],
-        "pendingApprovals": [],
-        "currentGate": Value::Null,
-        "createdAt": now,
-        "updatedAt": now
-    });
-    let saved = upsert_release_session_value(session)?;
+    let mut session = ReleaseSession::new(session_id, version.unwrap_or_default(), environment, timestamp_string());
+    session.workspace_path = workspace_path.unwrap_or_default();
+    let saved = release_store()?.create_session(&session.to_value()?)?;
     Ok(json!({
         "ok": true,
         "session": saved,
@@ -1168,20 +1135,31 @@ pub async fn release_session_create(
 
 #[tauri::command]
 pub async fn release_session_read(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
+    let session = read_release_session(&session_id)?;
     Ok(json!({
         "ok": true,
-        "session": session
+        "session": session.to_value()?
     }))
 }
 
 #[tauri::command]
 pub async fn release_session_update(session: Value) -> Result<Value, String> {
-    let mut next_session = session.clone();
-    if let Some(object) = next_session.as_object_mut() {
-        object.insert("updatedAt".to_string(), json!(timestamp_string()));
-    }
-    let saved = upsert_release_session_value(next_session)?;
+    let mut next_session = ReleaseSession::from_value(session)?;
+    let session_id = next_session.session_id.clone();
+    let (saved, _) = update_release_session(&session_id, |current| {
+        current.validate_transition(&next_session)?;
+        // Approvals are owned by the backend and only change through release_approval_create/decide;
+        // promotions only through release_promote and release_record_environment_check.
+        next_session.approvals = std::mem::take(&mut current.approvals);
+        next_session.promotions = std::mem::take(&mut current.promotions);
+        if !next_session.promotions.is_empty() {
+            next_session.environment = std::mem::take(&mut current.environment);
+        }
+        next_session.workspace_path = std::mem::take(&mut current.workspace_path);
+        next_session.updated_at = timestamp_string();
+        *current = next_session;
+        Ok(())
+    })?;
     Ok(json!({
         "ok": true,
         "session": saved
@@ -1190,13 +1168,9 @@ pub async fn release_session_update(session: Value) -> Result<Value, String> {
 
 #[tauri::command]
 pub async fn release_session_list(status: Option<String>) -> Result<Value, String> {
-    let store = load_release_store()?;
-    let sessions = store
-        .get("sessions")
-        .and_then(Value::as_array)
-        .cloned()
-        .unwrap_or_default();
-    let filtered = sessions
+    let listing = release_store()?.list_sessions()?;
+    let filtered = listing
+        .sessions
         .into_iter()
         .filter(|session| {
             status
@@ -1208,7 +1182,136 @@ pub async fn release_session_list(status: Option<String>) -> Result<Value, Strin
     Ok(json!({
         "ok": true,
         "sessions": filtered,
-        "summary": format!("找到 {} 个 Release Session。", filtered.len())
+        "unreadable": listing.unreadable,
+        "summary": if listing.unreadable.is_empty() {
+            format!("找到 {} 个 Release Session。", filtered.len())
+        } else {
+            format!("找到 {} 个 Release Session，{} 个记录无法读取。", filtered.len(), listing.unreadable.len())
+        }
+    }))
+}
+
+#[tauri::command]
+pub async fn release_audit_log_read(session_id: String, verify: Option<bool>) -> Result<Value, String> {
+    let store = release_store()?;
+    let entries = read_audit_log(&store, &session_id)?;
+    let verification = if verify.unwrap_or(false) {
+        Some(verify_audit_log(&store, &session_id)?)
+    } else {
+        None
+    };
+    let summary = match &verification {
+        Some(check) if !check.ok => format!(
+            "审计日志校验失败（第 {} 条）：{}",
+            check.broken_at.unwrap_or(0),
+            check.reason.as_deref().unwrap_or("")
+        ),
+        Some(_) => format!("审计日志共 {} 条，hash 链校验通过。", entries.len()),
+        None => format!("审计日志共 {} 条。", entries.len()),
+    };
+    Ok(json!({
+        "ok": verification.as_ref().map(|check| check.ok).unwrap_or(true),
+        "sessionId": session_id,
+        "entries": entries,
+        "verification": verification,
+        "summary": summary
+    }))
+}
+
+#[tauri::command]
+pub async fn release_profile_read(workspace_path: Option<String>, version: Option<String>) -> Result<Value, String> {
+    let profile = ReleaseProfile::load(workspace_path.as_deref())?;
+    let path = workspace_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(|path| ReleaseProfile::path(Path::new(path)));
+    let exists = path.as_ref().map(|path| path.exists()).unwrap_or(false);
+    let resolved = version.as_deref().map(|version| profile.resolve(version));
+    Ok(json!({
+        "ok": true,
+        "profile": profile,
+        "path": path.map(|path| path.display().to_string()),
+        "exists": exists,
+        "resolved": resolved,
+        "summary": if exists { "已读取工作区发布配置。" } else { "工作区未配置发布规则，使用默认分支约定。" }
+    }))
+}
+
+#[tauri::command]
+pub async fn release_profile_save(workspace_path: String, profile: Value) -> Result<Value, String> {
+    let workspace = workspace_path.trim();
+    if workspace.is_empty() {
+        return Err("workspacePath is required to save a release profile".to_string());
+    }
+    let profile = serde_json::from_value::<ReleaseProfile>(profile)
+        .map_err(|e| format!("Invalid release profile: {}", e))?;
+    let path = profile.save(Path::new(workspace))?;
+    Ok(json!({
+        "ok": true,
+        "profile": profile,
+        "path": path.display().to_string(),
+        "summary": "发布配置已保存。"
+    }))
+}
+
+#[tauri::command]
+pub async fn release_template_read(workspace_path: Option<String>, name: Option<String>) -> Result<Value, String> {
+    let name = match name {
+        Some(name) => name,
+        None => ReleaseProfile::load(workspace_path.as_deref())?.doc_template,
+    };
+    let template = ReleaseTemplate::load(workspace_path.as_deref(), &name)?;
+    Ok(json!({
+        "ok": true,
+        "name": template.name,
+        "source": template.source,
+        "builtin": template.path.is_none(),
+        "path": template.path.as_ref().map(|path| path.display().to_string()),
+        "templates": ReleaseTemplate::list(workspace_path.as_deref()),
+        "summary": format!("已读取发布文档模板 {}。", template.name)
+    }))
+}
+
+/// Check a template against sample release notes; nothing is written.
+#[tauri::command]
+pub async fn release_template_validate(source: String) -> Result<Value, String> {
+    let errors = ReleaseTemplate::inline(source).validate(&ReleaseNotes::sample().context());
+    Ok(json!({
+        "ok": errors.is_empty(),
+        "errors": errors,
+        "summary": match errors.first() {
+            None => "模板校验通过。".to_string(),
+            Some(error) => format!("模板校验失败：{}", error.message),
+        }
+    }))
+}
+
+/// Save a workspace template; a template that fails validation is not written.
+#[tauri::command]
+pub async fn release_template_save(workspace_path: String, name: String, source: String) -> Result<Value, String> {
+    let workspace = workspace_path.trim();
+    if workspace.is_empty() {
+        return Err("workspacePath is required to save a release template".to_string());
+    }
+    validate_template_name(name.trim())?;
+    let template = ReleaseTemplate { name: name.trim().to_string(), source, path: None };
+    let errors = template.validate(&ReleaseNotes::sample().context());
+    if !errors.is_empty() {
+        return Ok(json!({
+            "ok": false,
+            "name": template.name,
+            "errors": errors,
+            "summary": format!("模板校验失败，未保存：{}", errors[0].message)
+        }));
+    }
+    let path = template.save(Path::new(workspace))?;
+    Ok(json!({
+        "ok": true,
+        "name": template.name,
+        "path": path.display().to_string(),
+        "errors": [],
+        "summary": format!("发布文档模板 {} 已保存。", template.name)
     }))
 }
 
@@ -1219,36 +1322,48 @@ pub async fn release_approval_create(
     action: String,
     target: String,
     summary: String,
+    actor: Option<String>,
 ) -> Result<Value, String> {
-    let mut session = read_release_session_value(&session_id)?;
-    let approval_id = format!("approval-{}-{}", step_id, timestamp_string());
-    let approval = json!({
-        "approvalId": approval_id,
-        "sessionId": session_id,
-        "stepId": step_id,
-        "action": action,
-        "target": target,
-        "summary": summary,
-        "requestedBy": "system",
-        "approvedBy": "",
-        "decision": "pending",
-        "ts": timestamp_string()
-    });
+    let inputs = json!({ "stepId": step_id, "action": action, "target": target });
+    let requested_by = release_actor(actor);
+    let mut result = create_release_approval(session_id.clone(), step_id, action, target, summary, requested_by.clone());
+    audit_release_action(&session_id, "release_approval_create", &requested_by, inputs, &mut result);
+    result
+}
 
-    session["approvals"]
-        .as_array_mut()
-        .ok_or_else(|| "Session approvals is not an array".to_string())?
-        .push(approval.clone());
-    session["currentGate"] = json!({
-        "approvalId": approval_id,
-        "stepId": approval["stepId"],
-        "action": approval["action"],
-        "target": approval["target"],
-        "summary": approval["summary"]
-    });
-    session["updatedAt"] = json!(timestamp_string());
+fn create_release_approval(
+    session_id: String,
+    step_id: String,
+    action: String,
+    target: String,
+    summary: String,
+    requested_by: String,
+) -> Result<Value, String> {
+    let approval_id = format!("approval-{}-{}", step_id, timestamp_string());
+    let approval = Approval {
+        approval_id: approval_id.clone(),
+        session_id,
+        step_id,
+        action,
+        target,
+        summary,
+        requested_by,
+        ts: timestamp_string(),
+        ..Approval::default()
+    };
 
-    let saved = upsert_release_session_value(session)?;
+    let (saved, _) = update_release_session(&approval.session_id, |session| {
+        session.current_gate = Some(json!({
+            "approvalId": approval_id,
+            "stepId": approval.step_id,
+            "action": approval.action,
+            "target": approval.target,
+            "summary": approval.summary
+        }));
+        session.approvals.push(approval.clone());
+        session.updated_at = timestamp_string();
+        Ok(())
+    })?;
     Ok(json!({
         "ok": true,
         "session": saved,
@@ -1263,34 +1378,47 @@ pub async fn release_approval_decide(
     decision: String,
     actor: String,
 ) -> Result<Value, String> {
-    let mut session = read_release_session_value(&session_id)?;
-    let approvals = session["approvals"]
-        .as_array_mut()
-        .ok_or_else(|| "Session approvals is not an array".to_string())?;
-
-    let mut updated = None;
-    for approval in approvals.iter_mut() {
-        if approval.get("approvalId").and_then(Value::as_str) == Some(approval_id.as_str()) {
-            approval["decision"] = json!(decision.clone());
-            approval["approvedBy"] = json!(actor.clone());
-            approval["ts"] = json!(timestamp_string());
-            updated = Some(approval.clone());
-            break;
-        }
-    }
-
-    if updated.is_none() {
-        return Err(format!("Approval not found: {}", approval_id));
-    }
+    let inputs = json!({ "approvalId": approval_id, "decision": decision });
+    let audit_actor = actor.clone();
+    let mut result = decide_release_approval(&session_id, &approval_id, &decision, actor);
+    audit_release_action(&session_id, "release_approval_decide", &audit_actor, inputs, &mut result);
+    result
+}
 
-    session["currentGate"] = Value::Null;
-    session["updatedAt"] = json!(timestamp_string());
+fn decide_release_approval(
+    session_id: &str,
+    approval_id: &str,
+    decision: &str,
+    actor: String,
+) -> Result<Value, String> {
+    let decision = ApprovalDecision::parse(decision)?;
+    let (saved, updated) = update_release_session(session_id, |session| {
+        let approval = session
+            .approvals
+            .iter_mut()
+            .find(|approval| approval.approval_id == approval_id)
+            .ok_or_else(|| format!("Approval not found: {}", approval_id))?;
+        if approval.consumed_at.is_some() {
+            return Err(format!("Approval already used: {}", approval_id));
+        }
+        let now = timestamp_string();
+        approval.decision = decision;
+        approval.approved_by = actor;
+        approval.expires_at = match decision {
+            ApprovalDecision::Approved => now.parse::<u128>().ok().map(|ms| (ms + APPROVAL_TTL_MS).to_string()),
+            _ => None,
+        };
+        approval.ts = now.clone();
+        let updated = approval.clone();
 
-    let saved = upsert_release_session_value(session)?;
+        session.current_gate = None;
+        session.updated_at = now;
+        Ok(updated)
+    })?;
     Ok(json!({
         "ok": true,
         "session": saved,
-        "approval": updated.unwrap()
+        "approval": updated
     }))
 }
 
@@ -1300,24 +1428,43 @@ pub async fn release_collect_config_changes(
     version: String,
     repos: Vec<Value>,
 ) -> Result<Value, String> {
-    let release_ref = format!("origin/release/v{}", version.trim_start_matches('v'));
+    let profile = release_profile_for(&read_release_session(&session_id)?)?;
+    let release_ref = profile.remote_release_ref(&version);
+    let stable_ref = profile.remote_stable_ref();
     let mut changes = Vec::new();
+    let (mut key_changes, mut masked_changes) = (0, 0);
 
     for repo in repos {
         let repo_path = repo_path(&repo);
         if repo_path.is_empty() {
             continue;
         }
-        let diff_files = git_diff_name_only(&repo_path, "origin/latest", &release_ref).unwrap_or_default();
+        let Ok(git) = GitRepo::open(&repo_path) else { continue };
+        let diff_files = git.diff_names(&stable_ref, &release_ref).unwrap_or_default();
         let matched_files = diff_files
             .into_iter()
             .filter(|file| detect_config_file(file))
             .collect::<Vec<_>>();
         if !matched_files.is_empty() {
+            let diffs = matched_files
+                .iter()
+                .map(|file| {
+                    let stable = git.read_file(&stable_ref, file).ok();
+                    let release = git.read_file(&release_ref, file).ok();
+                    diff_config_file(file, stable.as_deref(), release.as_deref())
+                })
+                .collect::<Vec<_>>();
+            key_changes += diffs.iter().map(|diff| diff.changes.len()).sum::<usize>();
+            masked_changes += diffs
+                .iter()
+                .flat_map(|diff| &diff.changes)
+                .filter(|change| change.masked)
+                .count();
             changes.push(json!({
                 "repoKey": repo_key(&repo),
                 "repoPath": repo_path,
-                "files": matched_files
+                "files": matched_files,
+                "diffs": diffs
             }));
         }
     }
@@ -1328,18 +1475,26 @@ pub async fn release_collect_config_changes(
         "sessionId": session_id,
         "hasChanges": !changes.is_empty(),
         "changes": changes,
+        "keyChanges": key_changes,
+        "maskedChanges": masked_changes,
         "summary": if changes.is_empty() {
-            "未发现需要单独处理的配置变更。"
+            "未发现需要单独处理的配置变更。".to_string()
+        } else if masked_changes > 0 {
+            format!(
+                "检测到 {} 处配置键变更（其中 {} 处疑似密钥已脱敏），请在审批后决定是否应用。",
+                key_changes, masked_changes
+            )
         } else {
-            "检测到配置变更，请在审批后决定是否应用。"
+            format!("检测到 {} 处配置键变更，请在审批后决定是否应用。", key_changes)
         }
     }))
 }
 
 #[tauri::command]
 pub async fn release_preview_config_changes(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
-    let changes = session_step_result(&session, "configChanges")
+    let session = read_release_session(&session_id)?;
+    let changes = session
+        .step_result("configChanges")
         .get("changes")
         .cloned()
         .unwrap_or_else(|| json!([]));
@@ -1352,171 +1507,674 @@ pub async fn release_preview_config_changes(session_id: String) -> Result<Value,
 }
 
 #[tauri::command]
-pub async fn release_apply_config_changes(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
-    let version = session.get("version").and_then(Value::as_str).unwrap_or("").trim().to_string();
-    let release_ref = format!("origin/release/v{}", version.trim_start_matches('v'));
-    let changes = session_step_result(&session, "configChanges")
+pub async fn release_apply_config_changes(
+    session_id: String,
+    selection: Option<Vec<ConfigSelection>>,
+    actor: Option<String>,
+) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id, "selection": selection });
+    let mut result = apply_release_config_changes(&session_id, selection.as_deref());
+    audit_release_action(&session_id, "release_apply_config_changes", &release_actor(actor), inputs, &mut result);
+    result
+}
+
+/// One file written by an apply, as recorded in the backup manifest.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigBackupEntry {
+    repo_key: String,
+    file: String,
+    path: String,
+    backup_path: String,
+    existed: bool,
+}
+
+/// Three-way merges each collected config file (merge base of the stable and release branches,
+/// the workspace file, the release branch) instead of overwriting it, so local edits survive.
+/// Conflicting files are left untouched and reported; every written file is backed up first.
+fn apply_release_config_changes(session_id: &str, selection: Option<&[ConfigSelection]>) -> Result<Value, String> {
+    let (session, approval_id) = match authorize_dangerous_step(session_id, "applyConfigChanges")? {
+        Ok(authorized) => authorized,
+        Err(refusal) => return Ok(refusal),
+    };
+    let mut written = false;
+    let outcome = merge_config_changes(&session, selection, &mut written);
+    settle_approval(session_id, &approval_id, written)?;
+    let result = outcome?;
+    record_release_step_outcome(session_id, "applyConfigChanges", &result)?;
+    Ok(result)
+}
+
+/// Does the work of `apply_release_config_changes`; `written` turns true once a workspace file is touched.
+fn merge_config_changes(session: &ReleaseSession, selection: Option<&[ConfigSelection]>, written: &mut bool) -> Result<Value, String> {
+    let profile = release_profile_for(session)?;
+    let release_ref = profile.remote_release_ref(&session.version);
+    let stable_ref = profile.remote_stable_ref();
+    let changes = session
+        .step_result("configChanges")
         .get("changes")
         .and_then(Value::as_array)
         .cloned()
         .unwrap_or_default();
-
-    let mut applied = Vec::new();
+    let backup_id = timestamp_string();
+    let artifact_dir = release_artifact_dir(&session.session_id)?;
+    let backup_dir = artifact_dir.join("config-backups").join(&backup_id);
+    let conflict_dir = artifact_dir.join("config-conflicts").join(&backup_id);
+
+    let (mut applied, mut unchanged, mut conflicts, mut skipped) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut backups = Vec::new();
+    let mut selected = vec![false; selection.map_or(0, <[_]>::len)];
     for repo in changes {
         let repo_path = repo.get("repoPath").and_then(Value::as_str).unwrap_or("").to_string();
         let repo_key = repo.get("repoKey").and_then(Value::as_str).unwrap_or("").to_string();
         let files = repo.get("files").and_then(Value::as_array).cloned().unwrap_or_default();
-        for file in files {
-            if let Some(relative_path) = file.as_str() {
-                let content = git_show_file(&repo_path, &release_ref, relative_path)?;
-                let absolute_path = PathBuf::from(&repo_path).join(relative_path);
-                if let Some(parent) = absolute_path.parent() {
-                    fs::create_dir_all(parent)
-                        .map_err(|e| format!("Failed to create config parent directory: {}", e))?;
+        let git = GitRepo::open(&repo_path).ok();
+        let base_ref = git
+            .as_ref()
+            .and_then(|git| git.merge_base(&stable_ref, &release_ref).ok())
+            .unwrap_or_else(|| stable_ref.clone());
+        let show_file = |git_ref: &str, file: &str| git.as_ref().and_then(|git| git.read_file(git_ref, file).ok());
+        for file in files.iter().filter_map(Value::as_str) {
+            let keys = match selection {
+                None => None,
+                Some(selection) => match selection.iter().position(|entry| entry.repo_key == repo_key && entry.file == file) {
+                    Some(index) => {
+                        selected[index] = true;
+                        selection[index].keys.as_deref()
+                    }
+                    None => continue,
+                },
+            };
+            if !is_plain_relative_path(file) || !is_plain_relative_path(&repo_key) {
+                skipped.push(json!({ "repoKey": repo_key, "file": file, "reason": "Path escapes the repository" }));
+                continue;
+            }
+            let absolute_path = Path::new(&repo_path).join(file);
+            let ours = fs::read_to_string(&absolute_path).ok();
+            let base = show_file(&base_ref, file);
+            let theirs = show_file(&release_ref, file);
+            match merge_config_file(file, base.as_deref(), ours.as_deref(), theirs.as_deref(), keys) {
+                Err(reason) | Ok(MergeOutcome::Skipped(reason)) => {
+                    skipped.push(json!({ "repoKey": repo_key, "file": file, "reason": reason }));
+                }
+                Ok(MergeOutcome::Unchanged) => unchanged.push(json!({ "repoKey": repo_key, "file": file })),
+                Ok(MergeOutcome::Conflict { reason, keys, diff3 }) => {
+                    let diff3_path = match diff3 {
+                        Some(diff3) => {
+                            let path = conflict_dir.join(&repo_key).join(file);
+                            write_config_file(&path, &diff3)?;
+                            Some(path.display().to_string())
+                        }
+                        None => None,
+                    };
+                    conflicts.push(json!({
+                        "repoKey": repo_key,
+                        "file": file,
+                        "reason": reason,
+                        "keys": keys,
+                        "diff3Path": diff3_path
+                    }));
+                }
+                Ok(MergeOutcome::Merged { content, keys, reformatted }) => {
+                    let backup_path = backup_dir.join(&repo_key).join(file);
+                    if let Some(ours) = &ours {
+                        write_config_file(&backup_path, ours)?;
+                    }
+                    backups.push(ConfigBackupEntry {
+                        repo_key: repo_key.clone(),
+                        file: file.to_string(),
+                        path: absolute_path.display().to_string(),
+                        backup_path: backup_path.display().to_string(),
+                        existed: ours.is_some(),
+                    });
+                    // Record the file in the manifest before touching it, so a failure later in the
+                    // loop still leaves everything written so far restorable.
+                    write_config_backup_manifest(&backup_dir, &backups)?;
+                    *written = true;
+                    write_config_file(&absolute_path, &content)?;
+                    applied.push(json!({
+                        "repoKey": repo_key,
+                        "file": file,
+                        "path": absolute_path.display().to_string(),
+                        "keys": keys,
+                        "reformatted": reformatted
+                    }));
                 }
-                fs::write(&absolute_path, content)
-                    .map_err(|e| format!("Failed to write config file {}: {}", absolute_path.display(), e))?;
-                applied.push(json!({
-                    "repoKey": repo_key,
-                    "path": absolute_path.display().to_string()
-                }));
             }
         }
     }
+    for (entry, _) in selection.unwrap_or_default().iter().zip(&selected).filter(|(_, selected)| !**selected) {
+        skipped.push(json!({
+            "repoKey": entry.repo_key,
+            "file": entry.file,
+            "reason": "Not among the collected config changes"
+        }));
+    }
 
-    Ok(json!({
-        "ok": true,
+    let mut artifacts = vec![json!({
         "stepId": "applyConfigChanges",
-        "artifacts": [{
+        "kind": "config-apply",
+        "title": "Applied config changes",
+        "count": applied.len()
+    })];
+    if !backups.is_empty() {
+        artifacts.push(json!({
             "stepId": "applyConfigChanges",
-            "kind": "config-apply",
-            "title": "Applied config changes",
-            "count": applied.len()
-        }],
+            "kind": "config-backup",
+            "title": "Config backup before apply",
+            "path": backup_dir.join("manifest.json").display().to_string(),
+            "backupId": backup_id,
+            "count": backups.len()
+        }));
+    }
+    let summary = if !conflicts.is_empty() {
+        format!(
+            "已合并 {} 个配置文件，{} 个文件存在冲突未写入，请处理后重试。",
+            applied.len(),
+            conflicts.len()
+        )
+    } else if applied.is_empty() {
+        "没有需要写入的配置变更。".to_string()
+    } else {
+        format!("已将 {} 个配置文件合并到工作区，原文件已备份（{}）。", applied.len(), backup_id)
+    };
+    Ok(json!({
+        "ok": conflicts.is_empty(),
+        "stepId": "applyConfigChanges",
+        "backupId": if backups.is_empty() { Value::Null } else { json!(backup_id) },
+        "artifacts": artifacts,
         "applied": applied,
-        "summary": if applied.is_empty() {
-            "没有需要写入的配置变更。".to_string()
-        } else {
-            format!("已将 {} 个配置文件同步到工作区。", applied.len())
+        "unchanged": unchanged,
+        "conflicts": conflicts,
+        "skipped": skipped,
+        "summary": summary
+    }))
+}
+
+#[tauri::command]
+pub async fn release_restore_config_backup(
+    session_id: String,
+    backup_id: Option<String>,
+    actor: Option<String>,
+) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id, "backupId": backup_id });
+    let mut result = restore_release_config_backup(&session_id, backup_id.as_deref());
+    audit_release_action(&session_id, "release_restore_config_backup", &release_actor(actor), inputs, &mut result);
+    result
+}
+
+/// Put back the files written by one config apply (the latest when `backup_id` is omitted);
+/// files the apply created are removed.
+fn restore_release_config_backup(session_id: &str, backup_id: Option<&str>) -> Result<Value, String> {
+    read_release_session(session_id)?;
+    let backups_dir = release_artifact_dir(session_id)?.join("config-backups");
+    let backup_id = match backup_id {
+        Some(id) if !id.is_empty() && id.chars().all(|ch| ch.is_ascii_digit()) => id.to_string(),
+        Some(id) => return Err(format!("Invalid config backup id: {}", id)),
+        None => fs::read_dir(&backups_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str().and_then(|name| name.parse::<u128>().ok()))
+            .max()
+            .map(|id| id.to_string())
+            .ok_or_else(|| "No config backup found for this session".to_string())?,
+    };
+    let manifest = backups_dir.join(&backup_id).join("manifest.json");
+    let content = fs::read_to_string(&manifest)
+        .map_err(|e| format!("Failed to read config backup {}: {}", manifest.display(), e))?;
+    let entries = serde_json::from_str::<Vec<ConfigBackupEntry>>(&content)
+        .map_err(|e| format!("Invalid config backup manifest {}: {}", manifest.display(), e))?;
+
+    let (mut restored, mut removed) = (Vec::new(), Vec::new());
+    for entry in entries {
+        if entry.existed {
+            let content = fs::read_to_string(&entry.backup_path)
+                .map_err(|e| format!("Failed to read backup {}: {}", entry.backup_path, e))?;
+            write_config_file(Path::new(&entry.path), &content)?;
+            restored.push(json!({ "repoKey": entry.repo_key, "file": entry.file, "path": entry.path }));
+        } else if Path::new(&entry.path).exists() {
+            fs::remove_file(&entry.path).map_err(|e| format!("Failed to remove {}: {}", entry.path, e))?;
+            removed.push(json!({ "repoKey": entry.repo_key, "file": entry.file, "path": entry.path }));
         }
+    }
+    Ok(json!({
+        "ok": true,
+        "backupId": backup_id,
+        "restored": restored,
+        "removed": removed,
+        "summary": format!("已从备份 {} 恢复 {} 个配置文件，删除 {} 个新增文件。", backup_id, restored.len(), removed.len())
     }))
 }
 
+fn write_config_backup_manifest(backup_dir: &Path, backups: &[ConfigBackupEntry]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(backups).map_err(|e| format!("Failed to serialize config backup: {}", e))?;
+    write_config_file(&backup_dir.join("manifest.json"), &content)
+}
+
+fn write_config_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config parent directory: {}", e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write config file {}: {}", path.display(), e))
+}
+
+/// A non-empty relative path that stays below its root.
+fn is_plain_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_) | std::path::Component::CurDir))
+}
+
 #[tauri::command]
 pub async fn release_collect_i18n_changes(
     session_id: String,
     version: String,
     repos: Vec<Value>,
 ) -> Result<Value, String> {
-    let release_ref = format!("origin/release/v{}", version.trim_start_matches('v'));
-    let mut entries = Vec::new();
+    let profile = release_profile_for(&read_release_session(&session_id)?)?;
+    let release_ref = profile.remote_release_ref(&version);
+    let stable_ref = profile.remote_stable_ref();
+    let source_locale = profile.source_locale.clone();
+    let (mut entries, mut coverage, mut warnings) = (Vec::new(), Vec::new(), Vec::new());
+    let mut missing_translations = 0;
+    let mut repo_paths = Vec::new();
 
     for repo in repos {
         let repo_path = repo_path(&repo);
         if repo_path.is_empty() {
             continue;
         }
-        let diff_files = git_diff_name_only(&repo_path, "origin/latest", &release_ref).unwrap_or_default();
-        for file in diff_files.into_iter().filter(|file| detect_i18n_file(file)) {
-            let release_content = git_show_file(&repo_path, &release_ref, &file).unwrap_or_default();
-            let latest_content = git_show_file(&repo_path, "origin/latest", &file).unwrap_or_default();
-
-            let mut rows = Vec::new();
-            if file.to_lowercase().ends_with(".json") {
-                let release_json = serde_json::from_str::<Value>(&release_content).unwrap_or_else(|_| json!({}));
-                let latest_json = serde_json::from_str::<Value>(&latest_content).unwrap_or_else(|_| json!({}));
-                let mut release_keys = Vec::new();
-                let mut latest_keys = Vec::new();
-                flatten_json_keys("", &release_json, &mut release_keys);
-                flatten_json_keys("", &latest_json, &mut latest_keys);
-
-                let latest_map = latest_keys.into_iter().collect::<std::collections::HashMap<_, _>>();
-                for (key, value) in release_keys {
-                    let change_type = match latest_map.get(&key) {
-                        None => "added",
-                        Some(existing) if existing != &value => "changed",
-                        _ => continue,
-                    };
-                    rows.push(json!({
-                        "repoKey": repo_key(&repo),
-                        "file": file,
-                        "key": key,
-                        "changeType": change_type
-                    }));
+        let repo_key = repo_key(&repo);
+        repo_paths.push(json!({ "repoKey": repo_key, "repoPath": repo_path }));
+        let Ok(git) = GitRepo::open(&repo_path) else { continue };
+        let diff_files = git.diff_names(&stable_ref, &release_ref).unwrap_or_default();
+        let mut added_by_namespace = BTreeMap::<String, Vec<String>>::new();
+        for locale_file in diff_files.iter().filter_map(|file| classify_locale_file(file)) {
+            let file = &locale_file.path;
+            let old_content = git.read_file(&stable_ref, file).ok();
+            let new_content = git.read_file(&release_ref, file).ok();
+            let parse = |content: &Option<String>| parse_messages(file, content.as_deref().unwrap_or(""));
+            let changes = match (parse(&old_content), parse(&new_content)) {
+                (Some(Ok(old)), Some(Ok(new))) => Some(diff_messages(&old, &new)),
+                (Some(Err(error)), _) | (_, Some(Err(error))) => {
+                    warnings.push(format!("{}/{}: {}", repo_key, file, error));
+                    None
                 }
-            }
-
-            if rows.is_empty() {
-                rows.push(json!({
-                    "repoKey": repo_key(&repo),
+                _ => None,
+            };
+            let Some(changes) = changes else {
+                // Files without a parser, or that fail to parse, are listed as one whole-file row.
+                let change_type = match (&old_content, &new_content) {
+                    (None, Some(_)) => "added",
+                    (Some(_), None) => "removed",
+                    _ => "changed",
+                };
+                entries.push(json!({
+                    "repoKey": repo_key,
                     "file": file,
+                    "locale": locale_file.locale,
+                    "namespace": locale_file.namespace,
                     "key": file,
-                    "changeType": "changed"
+                    "changeType": change_type,
+                    "wholeFile": true
+                }));
+                continue;
+            };
+            if locale_file.locale == source_locale {
+                added_by_namespace.entry(locale_file.namespace.clone()).or_default().extend(
+                    changes.iter().filter(|change| change.change_type == "added").map(|change| change.key.clone()),
+                );
+            }
+            for change in changes {
+                entries.push(json!({
+                    "repoKey": repo_key,
+                    "file": file,
+                    "locale": locale_file.locale,
+                    "namespace": locale_file.namespace,
+                    "key": change.key,
+                    "changeType": change.change_type,
+                    "oldValue": change.old_value,
+                    "newValue": change.new_value
                 }));
             }
+        }
+        if added_by_namespace.is_empty() {
+            continue;
+        }
 
-            entries.extend(rows);
+        // Coverage reads every locale of the touched namespaces at the release ref, so locales
+        // that were not edited in this release still show the keys they are missing.
+        let release_files = git.list_files(&release_ref).unwrap_or_default();
+        let locale_files = release_files.iter().filter_map(|file| classify_locale_file(file)).collect::<Vec<_>>();
+        let locales = locale_files.iter().map(|file| file.locale.clone()).collect::<BTreeSet<_>>();
+        for (namespace, added) in added_by_namespace {
+            let files = locale_files
+                .iter()
+                .filter(|file| file.namespace == namespace)
+                .filter_map(|file| {
+                    let content = git.read_file(&release_ref, &file.path).ok()?;
+                    match parse_messages(&file.path, &content)? {
+                        Ok(messages) => Some((file.clone(), messages)),
+                        Err(_) => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            if let Some(report) = namespace_coverage(&namespace, &source_locale, &files, &locales, &added) {
+                missing_translations += report.missing_added_count();
+                let mut report = serde_json::to_value(&report).map_err(|e| e.to_string())?;
+                report["repoKey"] = json!(repo_key);
+                coverage.push(report);
+            }
         }
     }
 
+    let count = |change_type: &str| {
+        entries
+            .iter()
+            .filter(|entry| entry.get("changeType").and_then(Value::as_str) == Some(change_type))
+            .count()
+    };
+    let (added, changed, removed) = (count("added"), count("changed"), count("removed"));
+    let mut summary = if entries.is_empty() {
+        "未发现 i18n 差异。".to_string()
+    } else {
+        format!(
+            "识别到 {} 条 i18n 变更（新增 {}，修改 {}，删除 {}）。",
+            entries.len(),
+            added,
+            changed,
+            removed
+        )
+    };
+    if missing_translations > 0 {
+        summary.push_str(&format!(
+            "{} 中新增的 key 在其他语言中共缺少 {} 条翻译。",
+            source_locale, missing_translations
+        ));
+    }
     Ok(json!({
         "ok": true,
         "stepId": "i18nChanges",
         "sessionId": session_id,
+        "sourceLocale": source_locale,
+        "releaseRef": release_ref,
+        "repos": repo_paths,
         "entries": entries,
-        "summary": if entries.is_empty() {
-            "未发现 i18n 差异。".to_string()
-        } else {
-            format!("识别到 {} 条 i18n 变更。", entries.len())
-        }
+        "added": added,
+        "changed": changed,
+        "removed": removed,
+        "coverage": coverage,
+        "missingTranslations": missing_translations,
+        "warnings": warnings,
+        "summary": summary
     }))
 }
 
 #[tauri::command]
 pub async fn release_generate_i18n_artifacts(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
-    let entries = session_step_result(&session, "i18nChanges")
-        .get("entries")
-        .and_then(Value::as_array)
-        .cloned()
-        .unwrap_or_default();
+    let session = read_release_session(&session_id)?;
+    session.ensure_step_can_start("i18nArtifacts")?;
+    let changes = session.step_result("i18nChanges");
+    let entries = changes.get("entries").and_then(Value::as_array).cloned().unwrap_or_default();
+    let source_locale = changes.get("sourceLocale").and_then(Value::as_str).unwrap_or("en");
     let artifact_dir = release_artifact_dir(&session_id)?;
     let file_path = artifact_dir.join("i18n-changes.csv");
 
-    let mut csv = String::from("repo,file,key,changeType\n");
+    let mut csv = csv::Writer::from_writer(Vec::new());
+    let columns = ["repoKey", "file", "locale", "key", "changeType", "oldValue", "newValue"];
+    csv.write_record(columns).map_err(|e| format!("Failed to write i18n CSV: {}", e))?;
     for entry in &entries {
-        let line = format!(
-            "\"{}\",\"{}\",\"{}\",\"{}\"\n",
-            entry.get("repoKey").and_then(Value::as_str).unwrap_or(""),
-            entry.get("file").and_then(Value::as_str).unwrap_or(""),
-            entry.get("key").and_then(Value::as_str).unwrap_or(""),
-            entry.get("changeType").and_then(Value::as_str).unwrap_or("")
-        );
-        csv.push_str(&line);
+        csv.write_record(columns.map(|column| entry.get(column).and_then(Value::as_str).unwrap_or("")))
+            .map_err(|e| format!("Failed to write i18n CSV: {}", e))?;
     }
+    let csv = csv.into_inner().map_err(|e| format!("Failed to write i18n CSV: {}", e))?;
     fs::write(&file_path, csv).map_err(|e| format!("Failed to write i18n CSV: {}", e))?;
-
-    Ok(json!({
-        "ok": true,
+    let mut artifacts = vec![json!({
         "stepId": "i18nArtifacts",
-        "artifacts": [{
+        "kind": "i18n-csv",
+        "path": file_path.display().to_string(),
+        "title": "i18n changes CSV"
+    })];
+
+    let (units, locales) = release_translation_units(&changes, source_locale);
+    if !units.is_empty() {
+        let xlsx_path = artifact_dir.join("i18n-translations.xlsx");
+        fs::write(&xlsx_path, units_xlsx(&units, source_locale, &locales)?)
+            .map_err(|e| format!("Failed to write i18n XLSX: {}", e))?;
+        artifacts.push(json!({
             "stepId": "i18nArtifacts",
-            "kind": "i18n-csv",
-            "path": file_path.display().to_string(),
-            "title": "i18n changes CSV"
-        }],
+            "kind": "i18n-xlsx",
+            "path": xlsx_path.display().to_string(),
+            "title": "Translations (XLSX)",
+            "count": units.len()
+        }));
+        for locale in &locales {
+            let xliff_path = artifact_dir.join(format!("i18n-translations.{}.xlf", locale));
+            fs::write(&xliff_path, units_xliff(&units, source_locale, locale))
+                .map_err(|e| format!("Failed to write i18n XLIFF: {}", e))?;
+            artifacts.push(json!({
+                "stepId": "i18nArtifacts",
+                "kind": "i18n-xliff",
+                "path": xliff_path.display().to_string(),
+                "title": format!("Translations {} → {} (XLIFF 2.0)", source_locale, locale),
+                "locale": locale
+            }));
+        }
+    }
+
+    let result = json!({
+        "ok": true,
+        "stepId": "i18nArtifacts",
+        "artifacts": artifacts,
+        "units": units.len(),
+        "locales": locales,
         "summary": if entries.is_empty() {
             "未发现 i18n 变更，已生成空白 CSV 占位产物。".to_string()
+        } else if units.is_empty() {
+            format!("已生成 i18n CSV 产物，共 {} 条变更，无需翻译的新文本。", entries.len())
+        } else {
+            format!(
+                "已生成 i18n 产物：{} 条变更，{} 条待翻译文本（XLSX 及 {} 个语言的 XLIFF 2.0）。",
+                entries.len(),
+                units.len(),
+                locales.len()
+            )
+        }
+    });
+    record_release_step_outcome(&session_id, "i18nArtifacts", &result)?;
+    Ok(result)
+}
+
+/// Translation units for every key added or changed in this release (removed keys need no
+/// translation), with the current text of each locale at the release ref. Also returns the
+/// target locales seen, for the export columns and XLIFF files.
+fn release_translation_units(changes: &Value, source_locale: &str) -> (Vec<TranslationUnit>, Vec<String>) {
+    let Some(release_ref) = changes.get("releaseRef").and_then(Value::as_str) else {
+        return (Vec::new(), Vec::new());
+    };
+    let repos = i18n_repo_paths(changes)
+        .into_iter()
+        .filter_map(|(repo_key, repo_path)| Some((repo_key, GitRepo::open(&repo_path).ok()?)))
+        .collect::<BTreeMap<_, _>>();
+    let mut keys = BTreeMap::<(String, String), BTreeMap<String, (String, Option<String>)>>::new();
+    for entry in changes.get("entries").and_then(Value::as_array).into_iter().flatten() {
+        let text = |field: &str| entry.get(field).and_then(Value::as_str).unwrap_or("").to_string();
+        let change_type = text("changeType");
+        if entry.get("wholeFile").and_then(Value::as_bool).unwrap_or(false) || change_type == "removed" {
+            continue;
+        }
+        let unit = keys
+            .entry((text("repoKey"), text("namespace")))
+            .or_default()
+            .entry(text("key"))
+            .or_insert_with(|| ("unchanged".to_string(), None));
+        if text("locale") == source_locale {
+            *unit = (change_type, entry.get("oldValue").and_then(Value::as_str).map(str::to_string));
+        }
+    }
+
+    let (mut units, mut locales) = (Vec::new(), BTreeSet::new());
+    let mut repo_files = BTreeMap::<String, Vec<LocaleFile>>::new();
+    for ((repo_key, namespace), keys) in keys {
+        let Some(git) = repos.get(&repo_key) else { continue };
+        let locale_files = repo_files.entry(repo_key.clone()).or_insert_with(|| {
+            git.list_files(release_ref)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|file| classify_locale_file(file))
+                .collect()
+        });
+        let files = locale_files
+            .iter()
+            .filter(|file| file.namespace == namespace)
+            .filter_map(|file| {
+                let content = git.read_file(release_ref, &file.path).ok()?;
+                let messages = parse_messages(&file.path, &content)?.ok()?;
+                Some((file.locale.clone(), (file.path.clone(), messages)))
+            })
+            .collect::<BTreeMap<_, _>>();
+        let source = files.get(source_locale);
+        for (key, (change_type, previous_source)) in keys {
+            // gettext catalogs are keyed by the source text itself.
+            let source_text = match source {
+                Some((_, messages)) => messages.get(&key).cloned(),
+                None if namespace.ends_with(".po") => Some(key.clone()),
+                None => None,
+            };
+            let Some(source_text) = source_text else { continue };
+            let targets = files
+                .iter()
+                .filter(|(locale, _)| locale.as_str() != source_locale && locale.as_str() != TEMPLATE_LOCALE)
+                .map(|(locale, (_, messages))| (locale.clone(), messages.get(&key).cloned().unwrap_or_default()))
+                .collect::<BTreeMap<_, _>>();
+            locales.extend(targets.keys().cloned());
+            units.push(TranslationUnit {
+                repo_key: repo_key.clone(),
+                namespace: namespace.clone(),
+                key,
+                change_type,
+                source: source_text,
+                previous_source,
+                source_file: source.map_or_else(|| namespace.clone(), |(path, _)| path.clone()),
+                targets,
+            });
+        }
+    }
+    (units, locales.into_iter().collect())
+}
+
+fn i18n_repo_paths(changes: &Value) -> BTreeMap<String, String> {
+    changes
+        .get("repos")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|repo| {
+            let field = |name: &str| repo.get(name).and_then(Value::as_str).map(str::to_string);
+            Some((field("repoKey")?, field("repoPath")?))
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn release_import_i18n_translations(
+    session_id: String,
+    file_path: String,
+    actor: Option<String>,
+) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id, "filePath": file_path });
+    let mut result = import_release_i18n_translations(&session_id, &file_path);
+    audit_release_action(&session_id, "release_import_i18n_translations", &release_actor(actor), inputs, &mut result);
+    result
+}
+
+/// Write translations from a vendor XLSX/XLIFF file into the workspace locale files of the
+/// repos the `i18nChanges` step scanned.
+fn import_release_i18n_translations(session_id: &str, file_path: &str) -> Result<Value, String> {
+    let session = read_release_session(session_id)?;
+    let changes = session.step_result("i18nChanges");
+    let source_locale = changes.get("sourceLocale").and_then(Value::as_str).unwrap_or("en");
+    let repo_paths = i18n_repo_paths(&changes);
+    if repo_paths.is_empty() {
+        return Err("Collect i18n changes for this session before importing translations".to_string());
+    }
+    let bytes = fs::read(file_path).map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    let translations = read_translations(Path::new(file_path), &bytes)?;
+
+    let mut groups = BTreeMap::<(String, String, String), Messages>::new();
+    for translation in translations {
+        if translation.locale != source_locale {
+            groups
+                .entry((translation.repo_key, translation.namespace, translation.locale))
+                .or_default()
+                .insert(translation.key, translation.text);
+        }
+    }
+    let (mut imported, mut skipped) = (Vec::new(), Vec::new());
+    for ((repo_key, namespace, locale), messages) in groups {
+        let skip = |reason: String| json!({ "repoKey": repo_key, "namespace": namespace, "locale": locale, "reason": reason });
+        let Some(repo_path) = repo_paths.get(&repo_key) else {
+            skipped.push(skip("Repo was not part of the i18n scan".to_string()));
+            continue;
+        };
+        if !namespace.contains(LOCALE_PLACEHOLDER) || !is_plain_relative_path(&namespace.replace(LOCALE_PLACEHOLDER, &locale)) {
+            skipped.push(skip("Namespace is not a locale file pattern".to_string()));
+            continue;
+        }
+        // Directories may spell the locale `zh_CN` or `zh-cn`; prefer the file that exists.
+        let variants = [locale.clone(), locale.replace('-', "_"), locale.to_lowercase(), locale.replace('-', "_").to_lowercase()];
+        let relative = variants
+            .iter()
+            .map(|variant| namespace.replace(LOCALE_PLACEHOLDER, variant))
+            .find(|relative| Path::new(repo_path).join(relative).exists())
+            .unwrap_or_else(|| namespace.replace(LOCALE_PLACEHOLDER, &locale));
+        let path = Path::new(repo_path).join(&relative);
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        match update_messages(&relative, &content, &messages) {
+            Ok((written, missing)) => {
+                if written != content {
+                    write_config_file(&path, &written)?;
+                }
+                imported.push(json!({
+                    "repoKey": repo_key,
+                    "file": relative,
+                    "path": path.display().to_string(),
+                    "locale": locale,
+                    "count": messages.len() - missing.len(),
+                    "missingKeys": missing
+                }));
+            }
+            Err(error) => skipped.push(skip(error)),
+        }
+    }
+
+    let count = imported
+        .iter()
+        .map(|entry| entry.get("count").and_then(Value::as_u64).unwrap_or(0))
+        .sum::<u64>();
+    Ok(json!({
+        "ok": true,
+        "imported": imported,
+        "skipped": skipped,
+        "summary": if skipped.is_empty() {
+            format!("已导入 {} 条翻译，写入 {} 个语言文件。", count, imported.len())
         } else {
-            format!("已生成 i18n CSV 产物，共 {} 条变更。", entries.len())
+            format!(
+                "已导入 {} 条翻译，写入 {} 个语言文件；{} 组翻译未能写入，请查看 skipped。",
+                count,
+                imported.len(),
+                skipped.len()
+            )
         }
     }))
 }
 
 #[tauri::command]
 pub async fn release_generate_readiness_report(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
+    let session = read_release_session(&session_id)?;
+    let profile = release_profile_for(&session)?;
     let required_steps = vec![
         "credentials",
         "jiraIssues",
@@ -1528,16 +2186,17 @@ pub async fn release_generate_readiness_report(session_id: String) -> Result<Val
     ];
     let blocked = required_steps
         .iter()
-        .filter(|step_id| session_step_status(&session, step_id) == "blocked")
+        .filter(|step_id| session.step_status(step_id) == StepStatus::Blocked)
         .map(|step_id| step_id.to_string())
         .collect::<Vec<_>>();
     let pending = required_steps
         .iter()
-        .filter(|step_id| !["done", "skipped"].contains(&session_step_status(&session, step_id).as_str()))
+        .filter(|step_id| !session.step_status(step_id).is_settled())
         .map(|step_id| step_id.to_string())
         .collect::<Vec<_>>();
 
-    let has_config_changes = session_step_result(&session, "configChanges")
+    let has_config_changes = session
+        .step_result("configChanges")
         .get("hasChanges")
         .and_then(Value::as_bool)
         .unwrap_or(false);
@@ -1553,11 +2212,11 @@ pub async fn release_generate_readiness_report(session_id: String) -> Result<Val
     pending_approvals.push(json!({
         "stepId": "mergeLatest",
         "action": "execute_release_merge",
-        "target": format!("release/v{} -> latest", session.get("version").and_then(Value::as_str).unwrap_or(""))
+        "target": format!("{} -> {}", profile.release_branch(&session.version), profile.stable_branch)
     }));
 
     let ok = blocked.is_empty() && pending.is_empty();
-    Ok(json!({
+    let result = json!({
         "ok": ok,
         "stepId": "readinessReport",
         "status": if ok { "ready" } else { "blocked" },
@@ -1569,264 +2228,1340 @@ pub async fn release_generate_readiness_report(session_id: String) -> Result<Val
         } else {
             "仍存在未完成或阻塞的检查步骤。"
         }
-    }))
+    });
+    record_release_step_outcome(&session_id, "readinessReport", &result)?;
+    Ok(result)
+}
+
+/// Run `work(key, path, progress)` for every repo with a path on a bounded worker pool, emitting
+/// per-repo progress. An `Err` fails that repo only, so one broken repo can't abort the others.
+fn run_release_repos(
+    session: &ReleaseSession,
+    step_id: &str,
+    parallelism: usize,
+    app: Option<tauri::AppHandle>,
+    work: impl Fn(&str, &str, &ReleaseProgress) -> Result<Value, String> + Sync,
+) -> (Vec<Value>, Vec<String>) {
+    let repos = session
+        .repos
+        .iter()
+        .map(|repo| (repo_key(repo), repo_path(repo)))
+        .filter(|(_, path)| !path.is_empty())
+        .collect::<Vec<_>>();
+    let progress = ReleaseProgress::new(app, &session.session_id, step_id, repos.len());
+    let results = run_bounded(repos, parallelism, |(key, path)| {
+        progress.started(&key);
+        let result = work(&key, &path, &progress)
+            .unwrap_or_else(|error| json!({ "repoKey": key, "ok": false, "detail": error }));
+        progress.finished(&key, &result);
+        result
+    });
+    let failed = results
+        .iter()
+        .filter(|result| !result.get("ok").and_then(Value::as_bool).unwrap_or(false))
+        .map(|result| result.get("repoKey").and_then(Value::as_str).unwrap_or("").to_string())
+        .collect();
+    (results, failed)
 }
 
 #[tauri::command]
-pub async fn release_execute_merge(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
-    let version = session.get("version").and_then(Value::as_str).unwrap_or("").trim().to_string();
-    let release_ref = format!("origin/release/v{}", version.trim_start_matches('v'));
-    let repos = session.get("repos").and_then(Value::as_array).cloned().unwrap_or_default();
-    let mut results = Vec::new();
-    let mut failed = Vec::new();
+pub async fn release_execute_merge(
+    app: tauri::AppHandle,
+    session_id: String,
+    parallelism: Option<usize>,
+    actor: Option<String>,
+) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id, "parallelism": parallelism });
+    let mut result = execute_release_merge(&session_id, parallelism, Some(app));
+    audit_release_action(&session_id, "release_execute_merge", &release_actor(actor), inputs, &mut result);
+    result
+}
 
-    for repo in repos {
-        let path = repo_path(&repo);
-        let key = repo_key(&repo);
-        if path.is_empty() {
-            continue;
-        }
-        if !git_status_clean(&path)? {
-            failed.push(key.clone());
-            results.push(json!({
-                "repoKey": key,
-                "ok": false,
-                "detail": "工作区不干净，拒绝自动合并。"
-            }));
-            continue;
-        }
+fn execute_release_merge(
+    session_id: &str,
+    parallelism: Option<usize>,
+    app: Option<tauri::AppHandle>,
+) -> Result<Value, String> {
+    let (session, approval_id) = match authorize_dangerous_step(session_id, "mergeLatest")? {
+        Ok(authorized) => authorized,
+        Err(refusal) => return Ok(refusal),
+    };
+    let outcome = merge_release_repos(&session, parallelism, app);
+    // Only repos that reached the merge itself record where the stable branch was.
+    settle_approval(session_id, &approval_id, any_repo_result(&outcome, |item| item.get("preMergeSha").is_some()))?;
+    let result = outcome?;
+    record_release_step_outcome(session_id, "mergeLatest", &result)?;
+    Ok(result)
+}
 
-        let _ = run_git_output(&path, &vec!["fetch".into(), "origin".into(), "--prune".into()]);
-        let _ = run_git_output(&path, &vec!["checkout".into(), "-B".into(), "latest".into(), "origin/latest".into()]);
-        let merge_output = run_git_output(&path, &vec![
-            "merge".into(),
-            "--no-ff".into(),
-            "--no-edit".into(),
-            release_ref.clone(),
-        ])?;
-        if merge_output.status.success() {
-            let sha = run_git_text(&path, &vec!["rev-parse".into(), "HEAD".into()]).unwrap_or_default();
-            results.push(json!({
-                "repoKey": key,
-                "ok": true,
-                "sha": sha
-            }));
-        } else {
-            failed.push(key.clone());
-            results.push(json!({
-                "repoKey": key,
-                "ok": false,
-                "detail": String::from_utf8_lossy(&merge_output.stderr).trim().to_string()
-            }));
-        }
-    }
+fn merge_release_repos(
+    session: &ReleaseSession,
+    parallelism: Option<usize>,
+    app: Option<tauri::AppHandle>,
+) -> Result<Value, String> {
+    let profile = release_profile_for(session)?;
+    let release_ref = profile.remote_release_ref(&session.version);
+    let parallelism = parallelism.unwrap_or(profile.parallelism);
+    let (results, failed) = run_release_repos(session, "mergeLatest", parallelism, app, |key, path, _| {
+        merge_release_repo(key, path, &profile, &release_ref)
+    });
 
     Ok(json!({
         "ok": failed.is_empty(),
         "stepId": "mergeLatest",
         "results": results,
         "summary": if failed.is_empty() {
-            "release -> latest 合并已完成。".to_string()
+            format!("{} -> {} 合并已完成。", profile.release_branch(&session.version), profile.stable_branch)
         } else {
             format!("以下仓库合并失败：{}", failed.join("、"))
         }
     }))
 }
 
-#[tauri::command]
-pub async fn release_execute_post_merge_build(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
-    let repos = session.get("repos").and_then(Value::as_array).cloned().unwrap_or_default();
-    let mut results = Vec::new();
-    let mut failed = Vec::new();
-
-    for repo in repos {
-        let path = repo_path(&repo);
-        let key = repo_key(&repo);
-        if path.is_empty() {
-            continue;
-        }
-        let build_result = run_pnpm_build(path.clone()).await?;
-        let ok = build_result.get("success").and_then(Value::as_bool).unwrap_or(false);
-        if !ok {
-            failed.push(key.clone());
-        }
-        results.push(json!({
+fn merge_release_repo(key: &str, path: &str, profile: &ReleaseProfile, release_ref: &str) -> Result<Value, String> {
+    let git = GitRepo::open(path)?;
+    if !git.is_clean()? {
+        return Ok(json!({
             "repoKey": key,
-            "ok": ok,
-            "elapsedMs": build_result.get("elapsedMs").cloned().unwrap_or_else(|| json!(0)),
-            "stdout": build_result.get("stdout").cloned().unwrap_or_else(|| json!("")),
-            "stderr": build_result.get("stderr").cloned().unwrap_or_else(|| json!(""))
+            "ok": false,
+            "detail": "工作区不干净，拒绝自动合并。"
         }));
     }
 
-    Ok(json!({
+    let stable_branch = profile.stable_branch.clone();
+    let failure = |command: &str, stderr: &[u8]| {
+        json!({
+            "repoKey": key,
+            "ok": false,
+            "branch": stable_branch,
+            "detail": format!("{} 失败：{}", command, String::from_utf8_lossy(stderr).trim())
+        })
+    };
+    let fetch = git_cli(path, &["fetch".into(), profile.remote.clone(), "--prune".into()])?;
+    if !fetch.status.success() {
+        return Ok(failure("git fetch", &fetch.stderr));
+    }
+    // Merging onto whatever branch happens to be checked out would record the wrong preMergeSha.
+    let checkout = git_cli(path, &["checkout".into(), "-B".into(), stable_branch.clone(), profile.remote_stable_ref()])?;
+    if !checkout.status.success() {
+        return Ok(failure("git checkout", &checkout.stderr));
+    }
+    // Recorded so release_rollback can put the stable branch back where it was.
+    let pre_merge_sha = git.resolve_commit(&stable_branch).unwrap_or_default();
+    let merge_output = git_cli(path, &["merge".into(), "--no-ff".into(), "--no-edit".into(), release_ref.to_string()])?;
+    if merge_output.status.success() {
+        let sha = git.resolve_commit("HEAD").unwrap_or_default();
+        Ok(json!({
+            "repoKey": key,
+            "ok": true,
+            "sha": sha,
+            "branch": stable_branch,
+            "preMergeSha": pre_merge_sha
+        }))
+    } else {
+        Ok(json!({
+            "repoKey": key,
+            "ok": false,
+            "branch": stable_branch,
+            "preMergeSha": pre_merge_sha,
+            "detail": String::from_utf8_lossy(&merge_output.stderr).trim().to_string()
+        }))
+    }
+}
+
+#[tauri::command]
+pub async fn release_execute_post_merge_build(
+    app: tauri::AppHandle,
+    session_id: String,
+    parallelism: Option<usize>,
+    actor: Option<String>,
+) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id, "parallelism": parallelism });
+    let mut result = execute_release_post_merge_build(&session_id, parallelism, Some(app)).await;
+    audit_release_action(&session_id, "release_execute_post_merge_build", &release_actor(actor), inputs, &mut result);
+    result
+}
+
+async fn execute_release_post_merge_build(
+    session_id: &str,
+    parallelism: Option<usize>,
+    app: Option<tauri::AppHandle>,
+) -> Result<Value, String> {
+    let session = read_release_session(session_id)?;
+    session.ensure_step_can_start("buildVerification")?;
+    let profile = release_profile_for(&session)?;
+    let parallelism = parallelism.unwrap_or(profile.parallelism);
+    let owned_session_id = session_id.to_string();
+    let (results, failed) = tauri::async_runtime::spawn_blocking(move || {
+        run_release_repos(&session, "buildVerification", parallelism, app.clone(), |key, path, progress| {
+            let request = repo_build_request(key, path, new_build_id(key), Some(&owned_session_id), &profile)?;
+            progress.update(key, json!({ "buildId": request.build_id, "logPath": request.log_path }));
+            let build_result = run_streaming_build(&request, app.clone())?;
+            Ok(json!({
+                "repoKey": key,
+                "ok": build_result.get("success").and_then(Value::as_bool).unwrap_or(false),
+                "buildId": request.build_id,
+                "buildCommand": build_result.get("buildCommand").cloned().unwrap_or(Value::Null),
+                "status": build_result.get("status").cloned().unwrap_or(Value::Null),
+                "elapsedMs": build_result.get("elapsedMs").cloned().unwrap_or_else(|| json!(0)),
+                "stdout": build_result.get("stdout").cloned().unwrap_or_else(|| json!("")),
+                "stderr": build_result.get("stderr").cloned().unwrap_or_else(|| json!("")),
+                "logPath": build_result.get("logPath").cloned().unwrap_or(Value::Null),
+                "detail": build_result.get("detail").cloned().unwrap_or(Value::Null)
+            }))
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?;
+
+    let artifacts = results
+        .iter()
+        .filter_map(|result| {
+            let path = result.get("logPath").and_then(Value::as_str)?;
+            Some(json!({
+                "stepId": "buildVerification",
+                "kind": "build-log",
+                "path": path,
+                "title": format!("{} build log", result.get("repoKey").and_then(Value::as_str).unwrap_or(""))
+            }))
+        })
+        .collect::<Vec<_>>();
+    let result = json!({
         "ok": failed.is_empty(),
         "stepId": "buildVerification",
         "results": results,
+        "artifacts": artifacts,
         "summary": if failed.is_empty() {
             "合并后的构建验证全部通过。".to_string()
         } else {
             format!("构建失败仓库：{}", failed.join("、"))
         }
-    }))
+    });
+    record_release_step_outcome(session_id, "buildVerification", &result)?;
+    Ok(result)
 }
 
 #[tauri::command]
-pub async fn release_create_tag(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
-    let version = session.get("version").and_then(Value::as_str).unwrap_or("").trim().to_string();
-    let tag_name = format!("release/v{}", version.trim_start_matches('v'));
-    let repos = session.get("repos").and_then(Value::as_array).cloned().unwrap_or_default();
+pub async fn release_create_tag(
+    app: tauri::AppHandle,
+    session_id: String,
+    parallelism: Option<usize>,
+    actor: Option<String>,
+) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id, "parallelism": parallelism });
+    let mut result = create_release_tag(&session_id, parallelism, Some(app));
+    audit_release_action(&session_id, "release_create_tag", &release_actor(actor), inputs, &mut result);
+    result
+}
+
+fn create_release_tag(
+    session_id: &str,
+    parallelism: Option<usize>,
+    app: Option<tauri::AppHandle>,
+) -> Result<Value, String> {
+    let (session, approval_id) = match authorize_dangerous_step(session_id, "tagRelease")? {
+        Ok(authorized) => authorized,
+        Err(refusal) => return Ok(refusal),
+    };
+    let outcome = tag_release_repos(&session, parallelism, app);
+    let created = any_repo_result(&outcome, |item| item.get("created").and_then(Value::as_bool) == Some(true));
+    settle_approval(session_id, &approval_id, created)?;
+    let result = outcome?;
+    record_release_step_outcome(session_id, "tagRelease", &result)?;
+    Ok(result)
+}
+
+fn tag_release_repos(
+    session: &ReleaseSession,
+    parallelism: Option<usize>,
+    app: Option<tauri::AppHandle>,
+) -> Result<Value, String> {
+    let profile = release_profile_for(session)?;
+    let tag_name = profile.tag_name(&session.version);
+    let parallelism = parallelism.unwrap_or(profile.parallelism);
+    let (results, failed) = run_release_repos(session, "tagRelease", parallelism, app, |key, path, _| {
+        tag_release_repo(key, path, &tag_name)
+    });
+
+    Ok(json!({
+        "ok": failed.is_empty(),
+        "stepId": "tagRelease",
+        "results": results,
+        "summary": if failed.is_empty() {
+            format!("已完成 Tag 创建：{}", tag_name)
+        } else {
+            format!("以下仓库创建 Tag 失败：{}", failed.join("、"))
+        }
+    }))
+}
+
+fn tag_release_repo(key: &str, path: &str, tag_name: &str) -> Result<Value, String> {
+    let git = GitRepo::open(path)?;
+    if let Some(sha) = git.tag_commit(tag_name) {
+        return Ok(json!({
+            "repoKey": key,
+            "ok": true,
+            "tag": tag_name,
+            "sha": sha,
+            "created": false,
+            "detail": "Tag 已存在"
+        }));
+    }
+
+    match git.create_tag(tag_name, "HEAD") {
+        Ok(sha) => Ok(json!({
+            "repoKey": key,
+            "ok": true,
+            "tag": tag_name,
+            "sha": sha,
+            "created": true
+        })),
+        Err(detail) => Ok(json!({
+            "repoKey": key,
+            "ok": false,
+            "detail": detail
+        })),
+    }
+}
+
+/// SHA `remote` currently holds for `refname`, or an empty string when the ref is absent.
+fn remote_ref_sha(path: &str, remote: &str, refname: &str) -> Result<String, String> {
+    let output = git_cli_text(path, &["ls-remote".into(), remote.into(), refname.into()])?;
+    Ok(output
+        .lines()
+        .find_map(|line| {
+            let mut parts = line.split_whitespace();
+            let sha = parts.next()?;
+            (parts.next()? == refname).then(|| sha.to_string())
+        })
+        .unwrap_or_default())
+}
+
+/// A branch may only move forward: the remote tip must already be in the local history.
+/// Tags are immutable, so the remote must not have the tag or must already agree.
+fn push_is_safe(git: &GitRepo, refname: &str, local_sha: &str, remote_sha: &str) -> bool {
+    if remote_sha.is_empty() || remote_sha == local_sha {
+        return true;
+    }
+    if refname.starts_with("refs/tags/") {
+        return false;
+    }
+    git.is_ancestor(remote_sha, local_sha).unwrap_or(false)
+}
+
+/// Push `refs` of one repo in a single `git push` (atomic when there are several) after the
+/// non-fast-forward pre-check, then read back what the remote holds.
+fn push_repo_refs(key: &str, path: &str, remote: &str, refs: &[String]) -> Value {
+    let failure = |detail: String| json!({ "repoKey": key, "ok": false, "detail": detail });
+    let _ = git_cli(path, &["fetch".into(), remote.into(), "--prune".into()]);
+    let git = match GitRepo::open(path) {
+        Ok(git) => git,
+        Err(error) => return failure(error),
+    };
+
+    let mut checked = Vec::new();
+    for refname in refs {
+        let local_sha = match git.resolve(refname) {
+            Ok(sha) => sha,
+            Err(_) => return failure(format!("本地不存在 {}", refname)),
+        };
+        let remote_sha = match remote_ref_sha(path, remote, refname) {
+            Ok(sha) => sha,
+            Err(error) => return failure(format!("读取远端 {} 失败：{}", refname, error)),
+        };
+        if !push_is_safe(&git, refname, &local_sha, &remote_sha) {
+            return failure(format!("{} 在远端已指向 {}，拒绝非 fast-forward 推送。", refname, remote_sha));
+        }
+        checked.push((refname.clone(), local_sha, remote_sha));
+    }
+
+    let mut args = vec!["push".to_string(), "--porcelain".to_string()];
+    if refs.len() > 1 {
+        args.push("--atomic".to_string());
+    }
+    args.push(remote.to_string());
+    args.extend(refs.iter().map(|refname| format!("{}:{}", refname, refname)));
+    match git_cli(path, &args) {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => return failure(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(error) => return failure(error),
+    }
+
+    let mut pushed = Vec::new();
+    let mut mismatched = Vec::new();
+    for (refname, local_sha, previous_remote_sha) in checked {
+        let remote_sha = remote_ref_sha(path, remote, &refname).unwrap_or_default();
+        if remote_sha != local_sha {
+            mismatched.push(refname.clone());
+        }
+        pushed.push(json!({
+            "ref": refname,
+            "sha": local_sha,
+            "remoteSha": remote_sha,
+            "previousRemoteSha": previous_remote_sha
+        }));
+    }
+    let sha = pushed
+        .last()
+        .and_then(|item| item.get("sha").cloned())
+        .unwrap_or(Value::Null);
+    json!({
+        "repoKey": key,
+        "ok": mismatched.is_empty(),
+        "sha": sha,
+        "remote": remote,
+        "refs": pushed,
+        "detail": if mismatched.is_empty() {
+            String::new()
+        } else {
+            format!("推送后远端 SHA 不一致：{}", mismatched.join("、"))
+        }
+    })
+}
+
+fn push_release_refs(
+    session_id: &str,
+    step_id: &str,
+    refs_for_repo: impl Fn(&ReleaseSession, &ReleaseProfile, &str) -> Vec<String>,
+) -> Result<Value, String> {
+    let (session, approval_id) = match authorize_dangerous_step(session_id, step_id)? {
+        Ok(authorized) => authorized,
+        Err(refusal) => return Ok(refusal),
+    };
+    let outcome = push_session_refs(&session, step_id, refs_for_repo);
+    // `refs` is only reported once `git push` itself succeeded.
+    settle_approval(session_id, &approval_id, any_repo_result(&outcome, |item| item.get("refs").is_some()))?;
+    let result = outcome?;
+    record_release_step_outcome(session_id, step_id, &result)?;
+    Ok(result)
+}
+
+fn push_session_refs(
+    session: &ReleaseSession,
+    step_id: &str,
+    refs_for_repo: impl Fn(&ReleaseSession, &ReleaseProfile, &str) -> Vec<String>,
+) -> Result<Value, String> {
+    let profile = release_profile_for(session)?;
     let mut results = Vec::new();
     let mut failed = Vec::new();
 
-    for repo in repos {
-        let path = repo_path(&repo);
-        let key = repo_key(&repo);
+    for repo in &session.repos {
+        let path = repo_path(repo);
+        let key = repo_key(repo);
         if path.is_empty() {
             continue;
         }
-        let exists = run_git_output(&path, &vec![
-            "rev-parse".into(),
-            "--verify".into(),
-            format!("refs/tags/{}", tag_name),
-        ])?;
-        if exists.status.success() {
-            let sha = run_git_text(&path, &vec!["rev-list".into(), "-n".into(), "1".into(), tag_name.clone()]).unwrap_or_default();
-            results.push(json!({
-                "repoKey": key,
-                "ok": true,
-                "tag": tag_name,
-                "sha": sha,
-                "detail": "Tag 已存在"
-            }));
+        let refs = refs_for_repo(session, &profile, &key);
+        if refs.is_empty() {
             continue;
         }
-
-        let output = run_git_output(&path, &vec!["tag".into(), tag_name.clone()])?;
-        if output.status.success() {
-            let sha = run_git_text(&path, &vec!["rev-parse".into(), "HEAD".into()]).unwrap_or_default();
-            results.push(json!({
-                "repoKey": key,
-                "ok": true,
-                "tag": tag_name,
-                "sha": sha
-            }));
-        } else {
+        let outcome = push_repo_refs(&key, &path, &profile.remote, &refs);
+        if !outcome.get("ok").and_then(Value::as_bool).unwrap_or(false) {
             failed.push(key.clone());
-            results.push(json!({
-                "repoKey": key,
-                "ok": false,
-                "detail": String::from_utf8_lossy(&output.stderr).trim().to_string()
-            }));
         }
+        results.push(outcome);
     }
 
     Ok(json!({
         "ok": failed.is_empty(),
-        "stepId": "tagRelease",
+        "stepId": step_id,
         "results": results,
         "summary": if failed.is_empty() {
-            format!("已完成 Tag 创建：{}", tag_name)
+            format!("已推送到远端，共 {} 个仓库。", results.len())
         } else {
-            format!("以下仓库创建 Tag 失败：{}", failed.join("、"))
+            format!("以下仓库推送失败：{}", failed.join("、"))
         }
     }))
 }
 
 #[tauri::command]
-pub async fn release_generate_confluence_draft(session_id: String) -> Result<Value, String> {
-    let session = read_release_session_value(&session_id)?;
-    let artifact_dir = release_artifact_dir(&session_id)?;
-    let draft_id = format!("draft-{}", timestamp_string());
-    let file_path = artifact_dir.join(format!("{}.md", draft_id));
-    let version = session.get("version").and_then(Value::as_str).unwrap_or("");
-    let status = session.get("status").and_then(Value::as_str).unwrap_or("");
-    let repos = session.get("repos").and_then(Value::as_array).cloned().unwrap_or_default();
-    let approvals = session.get("approvals").and_then(Value::as_array).cloned().unwrap_or_default();
-    let artifacts = session.get("artifacts").and_then(Value::as_array).cloned().unwrap_or_default();
-
-    let mut lines = vec![
-        format!("# Release {}", version),
-        String::new(),
-        format!("- Session: {}", session_id),
-        format!("- Status: {}", status),
-        format!("- GeneratedAt: {}", timestamp_string()),
-        String::new(),
-        "## Repositories".to_string(),
-    ];
-    for repo in repos {
-        lines.push(format!(
-            "- {}",
-            repo.get("repo").and_then(Value::as_str).unwrap_or_else(|| repo.get("key").and_then(Value::as_str).unwrap_or(""))
-        ));
+pub async fn release_push_latest(session_id: String, actor: Option<String>) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id });
+    let mut result = push_release_refs(&session_id, "pushLatest", |_, profile, _| {
+        vec![format!("refs/heads/{}", profile.stable_branch)]
+    });
+    audit_release_action(&session_id, "release_push_latest", &release_actor(actor), inputs, &mut result);
+    result
+}
+
+#[tauri::command]
+pub async fn release_push_tags(session_id: String, actor: Option<String>) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id });
+    let mut result = push_release_refs(&session_id, "pushTags", |session, _, key| {
+        session
+            .step_result("tagRelease")
+            .get("results")
+            .and_then(Value::as_array)
+            .map(|results| {
+                results
+                    .iter()
+                    .filter(|item| item.get("repoKey").and_then(Value::as_str) == Some(key))
+                    .filter(|item| item.get("ok").and_then(Value::as_bool).unwrap_or(false))
+                    .filter_map(|item| item.get("tag").and_then(Value::as_str))
+                    .map(|tag| format!("refs/tags/{}", tag))
+                    .collect()
+            })
+            .unwrap_or_default()
+    });
+    audit_release_action(&session_id, "release_push_tags", &release_actor(actor), inputs, &mut result);
+    result
+}
+
+/// Per-repo undo plan built from the recorded merge and tag results. Tags that already
+/// existed before `release_create_tag` ran are left alone.
+fn build_rollback_plan(session: &ReleaseSession, profile: &ReleaseProfile) -> Vec<Value> {
+    let recorded = |step_id: &str| {
+        session
+            .step_result(step_id)
+            .get("results")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let merge_results = recorded("mergeLatest");
+    let tag_results = recorded("tagRelease");
+
+    session
+        .repos
+        .iter()
+        .filter_map(|repo| {
+            let key = repo_key(repo);
+            let path = repo_path(repo);
+            if path.is_empty() {
+                return None;
+            }
+            let for_repo = |item: &&Value| item.get("repoKey").and_then(Value::as_str) == Some(key.as_str());
+            let merge_result = merge_results.iter().find(for_repo);
+            let pre_merge_sha = merge_result
+                .and_then(|item| item.get("preMergeSha").and_then(Value::as_str))
+                .filter(|sha| !sha.is_empty())
+                .map(str::to_string);
+            // Sessions merged before profiles existed did not record the branch.
+            let branch = merge_result
+                .and_then(|item| item.get("branch").and_then(Value::as_str))
+                .filter(|branch| !branch.is_empty())
+                .unwrap_or(&profile.stable_branch)
+                .to_string();
+            let tags = tag_results
+                .iter()
+                .filter(for_repo)
+                .filter(|item| item.get("created").and_then(Value::as_bool).unwrap_or(false))
+                .filter_map(|item| item.get("tag").and_then(Value::as_str).map(str::to_string))
+                .collect::<Vec<_>>();
+            if pre_merge_sha.is_none() && tags.is_empty() {
+                return None;
+            }
+            let current_sha = GitRepo::open(&path)
+                .and_then(|git| git.resolve_commit(&branch))
+                .unwrap_or_default();
+            Some(json!({
+                "repoKey": key,
+                "repoPath": path,
+                "branch": branch,
+                "currentLatestSha": current_sha,
+                "resetLatestTo": pre_merge_sha,
+                "deleteTags": tags
+            }))
+        })
+        .collect()
+}
+
+fn rollback_repo(step: &Value) -> Result<Value, String> {
+    let key = step.get("repoKey").and_then(Value::as_str).unwrap_or("").to_string();
+    let path = step.get("repoPath").and_then(Value::as_str).unwrap_or("").to_string();
+    let branch = step.get("branch").and_then(Value::as_str).unwrap_or("latest").to_string();
+    let mut deleted_tags = Vec::new();
+    let failure = |detail: String, reset_to: Option<&String>, deleted_tags: &Vec<String>| {
+        let mut failure = json!({ "repoKey": key, "ok": false, "deletedTags": deleted_tags, "detail": detail });
+        if let Some(sha) = reset_to {
+            failure["resetTo"] = json!(sha);
+        }
+        failure
+    };
+
+    let git = GitRepo::open(&path)?;
+    if git.merge_in_progress() {
+        git_cli_text(&path, &["merge".into(), "--abort".into()])?;
     }
-    lines.push(String::new());
-    lines.push("## Approvals".to_string());
-    for approval in approvals {
-        lines.push(format!(
-            "- {}: {}",
-            approval.get("stepId").and_then(Value::as_str).unwrap_or(""),
-            approval.get("decision").and_then(Value::as_str).unwrap_or("pending")
-        ));
+
+    let restored = step.get("resetLatestTo").and_then(Value::as_str).map(str::to_string);
+    if let Some(sha) = &restored {
+        if !git.is_clean()? {
+            return Ok(failure("工作区不干净，拒绝自动回滚。".to_string(), None, &deleted_tags));
+        }
+        let reset = git_cli(&path, &["checkout".into(), "-B".into(), branch, sha.clone()])?;
+        if !reset.status.success() {
+            return Ok(failure(String::from_utf8_lossy(&reset.stderr).trim().to_string(), None, &deleted_tags));
+        }
     }
-    lines.push(String::new());
-    lines.push("## Artifacts".to_string());
-    for artifact in artifacts {
-        lines.push(format!(
-            "- {} {}",
-            artifact.get("kind").and_then(Value::as_str).unwrap_or("artifact"),
-            artifact.get("path").and_then(Value::as_str).unwrap_or("")
-        ));
+
+    for tag in step.get("deleteTags").and_then(Value::as_array).cloned().unwrap_or_default() {
+        let Some(tag) = tag.as_str() else { continue };
+        if let Err(detail) = git.delete_tag(tag) {
+            return Ok(failure(detail, restored.as_ref(), &deleted_tags));
+        }
+        deleted_tags.push(tag.to_string());
     }
 
-    fs::write(&file_path, lines.join("\n"))
-        .map_err(|e| format!("Failed to write Confluence draft: {}", e))?;
+    Ok(json!({
+        "repoKey": key,
+        "ok": true,
+        "sha": restored.unwrap_or_default(),
+        "previousSha": step.get("currentLatestSha").cloned().unwrap_or(Value::Null),
+        "deletedTags": deleted_tags
+    }))
+}
 
+/// What each repo would deploy: the tagged commit once `tagRelease` ran, otherwise the tip of
+/// the remote release branch. Also returns the repos whose commit could not be resolved.
+fn release_deploy_refs(session: &ReleaseSession, profile: &ReleaseProfile) -> (Vec<DeployedRef>, Vec<String>) {
+    let tagged = session.step_result("tagRelease");
+    let tagged = tagged
+        .get("results")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|result| result.get("ok").and_then(Value::as_bool).unwrap_or(false))
+        .filter_map(|result| {
+            let field = |name: &str| result.get(name).and_then(Value::as_str).filter(|value| !value.is_empty());
+            Some((field("repoKey")?.to_string(), (field("sha")?.to_string(), field("tag").map(str::to_string))))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let release_ref = profile.remote_release_ref(&session.version);
+    let (mut refs, mut unresolved) = (Vec::new(), Vec::new());
+    for repo in &session.repos {
+        let (key, path) = (repo_key(repo), repo_path(repo));
+        if path.is_empty() {
+            continue;
+        }
+        let resolved = match tagged.get(&key) {
+            Some((sha, tag)) => Some((sha.clone(), tag.clone())),
+            None => GitRepo::open(&path)
+                .and_then(|git| git.resolve_commit(&release_ref))
+                .ok()
+                .map(|sha| (sha, None)),
+        };
+        match resolved {
+            Some((sha, tag)) => refs.push(DeployedRef { repo_key: key, sha, tag }),
+            None => unresolved.push(key),
+        }
+    }
+    (refs, unresolved)
+}
+
+fn promotion_refusal_response(environment: &str, refusal: &PromotionRefusal) -> Value {
+    let mut response = json!({
+        "ok": false,
+        "blocked": true,
+        "stepId": promotion_step_id(environment),
+        "environment": environment,
+        "summary": format!("无法晋级到 {}：{}", environment, refusal.describe())
+    });
+    if let (Value::Object(map), Ok(Value::Object(detail))) = (&mut response, serde_json::to_value(refusal)) {
+        map.extend(detail);
+    }
+    response
+}
+
+#[tauri::command]
+pub async fn release_promote(session_id: String, environment: String, actor: Option<String>) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id, "environment": environment });
+    let actor = release_actor(actor);
+    let mut result = promote_release(&session_id, &environment, &actor);
+    audit_release_action(&session_id, "release_promote", &actor, inputs, &mut result);
+    result
+}
+
+/// Record that the release's commits were deployed to `environment`. Refused unless the
+/// previous environment on the profile's path verified exactly these SHAs; environments that
+/// require approval consume one for `promote-<environment>`.
+fn promote_release(session_id: &str, environment: &str, actor: &str) -> Result<Value, String> {
+    let session = read_release_session(session_id)?;
+    let profile = release_profile_for(&session)?;
+    let step_id = promotion_step_id(environment);
+    let (refs, unresolved) = release_deploy_refs(&session, &profile);
+    if !unresolved.is_empty() {
+        return Ok(json!({
+            "ok": false,
+            "stepId": step_id,
+            "environment": environment,
+            "unresolved": unresolved,
+            "summary": format!("无法解析以下仓库要部署的提交：{}", unresolved.join("、"))
+        }));
+    }
+
+    let now = timestamp_string();
+    let (_, outcome) = update_release_session(session_id, |session| {
+        session.ensure_step_can_start(&step_id)?;
+        if let Err(refusal) = promotion_gate(&profile.environments, &session.promotions, environment, &refs) {
+            return Ok(Err(promotion_refusal_response(environment, &refusal)));
+        }
+        let requires_approval = profile
+            .environments
+            .iter()
+            .any(|target| target.name == environment && target.requires_approval);
+        let approval_id = if requires_approval {
+            match session.consume_approval(&step_id, &now) {
+                Ok(approval) => Some(approval.approval_id),
+                Err(refusal) => return Ok(Err(approval_required_response(&step_id, refusal))),
+            }
+        } else {
+            None
+        };
+        let deployment = Deployment {
+            deployment_id: format!("deploy-{}-{}", environment, now),
+            environment: environment.to_string(),
+            refs: refs.clone(),
+            checks: Vec::new(),
+            promoted_by: actor.to_string(),
+            promoted_at: now.clone(),
+            approval_id,
+        };
+        session.promotions.push(deployment.clone());
+        session.environment = environment.to_string();
+        session.updated_at = now.clone();
+        Ok(Ok(deployment))
+    })?;
+    let deployment = match outcome {
+        Ok(deployment) => deployment,
+        Err(refusal) => return Ok(refusal),
+    };
+
+    let checks = profile
+        .environments
+        .iter()
+        .find(|target| target.name == environment)
+        .map(|target| target.checks.clone())
+        .unwrap_or_default();
+    Ok(json!({
+        "ok": true,
+        "stepId": step_id,
+        "environment": environment,
+        "deployment": deployment,
+        "results": deployment.refs.iter().map(|deployed| json!({
+            "repoKey": deployed.repo_key,
+            "ok": true,
+            "sha": deployed.sha,
+            "tag": deployed.tag
+        })).collect::<Vec<_>>(),
+        "pendingChecks": checks,
+        "summary": if checks.is_empty() {
+            format!("已晋级到 {}，共 {} 个仓库。", environment, deployment.refs.len())
+        } else {
+            format!(
+                "已晋级到 {}，共 {} 个仓库；晋级下一环境前需通过检查：{}。",
+                environment,
+                deployment.refs.len(),
+                checks.join("、")
+            )
+        }
+    }))
+}
+
+#[tauri::command]
+pub async fn release_record_environment_check(
+    session_id: String,
+    environment: String,
+    check: String,
+    passed: bool,
+    detail: Option<String>,
+    actor: Option<String>,
+) -> Result<Value, String> {
+    let inputs = json!({ "sessionId": session_id, "environment": environment, "check": check, "passed": passed });
+    let actor = release_actor(actor);
+    let mut result = record_environment_check(&session_id, &environment, &check, passed, detail, &actor);
+    audit_release_action(&session_id, "release_record_environment_check", &actor, inputs, &mut result);
+    result
+}
+
+/// Attach a check result to the latest deployment in `environment`.
+fn record_environment_check(
+    session_id: &str,
+    environment: &str,
+    check: &str,
+    passed: bool,
+    detail: Option<String>,
+    actor: &str,
+) -> Result<Value, String> {
+    let profile = release_profile_for(&read_release_session(session_id)?)?;
+    let target = profile
+        .environments
+        .iter()
+        .find(|target| target.name == environment)
+        .ok_or_else(|| format!("Unknown release environment: {}", environment))?;
+    if !target.checks.iter().any(|name| name == check) {
+        return Err(format!(
+            "Release environment {} has no check {} (configured: {})",
+            environment,
+            check,
+            target.checks.join(", ")
+        ));
+    }
+    let (_, deployment) = update_release_session(session_id, |session| {
+        let deployment = session
+            .promotions
+            .iter_mut()
+            .rev()
+            .find(|deployment| deployment.environment == environment)
+            .ok_or_else(|| format!("Release has not been promoted to {} yet", environment))?;
+        deployment.checks.push(EnvironmentCheck {
+            name: check.to_string(),
+            passed,
+            detail: detail.unwrap_or_default(),
+            recorded_by: actor.to_string(),
+            recorded_at: timestamp_string(),
+        });
+        let deployment = deployment.clone();
+        session.updated_at = timestamp_string();
+        Ok(deployment)
+    })?;
+    let pending = deployment.unpassed_checks(target);
     Ok(json!({
+        "ok": true,
+        "environment": environment,
+        "deploymentId": deployment.deployment_id,
+        "check": check,
+        "passed": passed,
+        "verified": pending.is_empty(),
+        "pendingChecks": pending,
+        "summary": match (passed, pending.is_empty()) {
+            (false, _) => format!("{} 的检查 {} 未通过。", environment, check),
+            (true, true) => format!("{} 的检查已全部通过，可以晋级到下一环境。", environment),
+            (true, false) => format!("{} 的检查 {} 已通过，仍待：{}。", environment, check, pending.join("、")),
+        }
+    }))
+}
+
+#[tauri::command]
+pub async fn release_promotion_status(session_id: String) -> Result<Value, String> {
+    let session = read_release_session(&session_id)?;
+    let profile = release_profile_for(&session)?;
+    let (refs, unresolved) = release_deploy_refs(&session, &profile);
+    let environments = profile
+        .environments
+        .iter()
+        .map(|environment| {
+            let deployment = latest_deployment(&session.promotions, &environment.name);
+            let gate = if unresolved.is_empty() {
+                promotion_gate(&profile.environments, &session.promotions, &environment.name, &refs)
+            } else {
+                Err(PromotionRefusal::NothingToDeploy)
+            };
+            json!({
+                "name": environment.name,
+                "checks": environment.checks,
+                "requiresApproval": environment.requires_approval,
+                "deployment": deployment,
+                "pendingChecks": deployment.map(|deployment| deployment.unpassed_checks(environment)),
+                "canPromote": gate.is_ok(),
+                "blockedReason": gate.err().map(|refusal| refusal.describe())
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(json!({
+        "ok": true,
+        "sessionId": session_id,
+        "currentEnvironment": session.promotions.last().map(|deployment| deployment.environment.clone()),
+        "refs": refs,
+        "unresolved": unresolved,
+        "environments": environments,
+        "promotions": session.promotions
+    }))
+}
+
+#[tauri::command]
+pub async fn release_rollback(
+    session_id: String,
+    dry_run: Option<bool>,
+    actor: Option<String>,
+) -> Result<Value, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let inputs = json!({ "sessionId": session_id, "dryRun": dry_run });
+    let mut result = rollback_release(&session_id, dry_run);
+    audit_release_action(&session_id, "release_rollback", &release_actor(actor), inputs, &mut result);
+    result
+}
+
+fn rollback_release(session_id: &str, dry_run: bool) -> Result<Value, String> {
+    let session = read_release_session(session_id)?;
+    if session.status == SessionStatus::Published {
+        return Err(format!("Release session {} is already published and cannot be rolled back", session_id));
+    }
+    let plan = build_rollback_plan(&session, &release_profile_for(&session)?);
+    // Rollback only rewinds local refs; anything already pushed has to be handled on the remote.
+    let remote_pushed = ["pushLatest", "pushTags"]
+        .iter()
+        .any(|step_id| session.step_status(step_id) == StepStatus::Done);
+    if plan.is_empty() {
+        return Ok(json!({
+            "ok": false,
+            "stepId": "rollback",
+            "dryRun": dry_run,
+            "plan": plan,
+            "summary": "没有可回滚的合并或 Tag 记录。"
+        }));
+    }
+    if dry_run {
+        return Ok(json!({
+            "ok": true,
+            "stepId": "rollback",
+            "dryRun": true,
+            "plan": plan,
+            "remotePushed": remote_pushed,
+            "summary": if remote_pushed {
+                format!("回滚预览：{} 个仓库的本地稳定分支和 Tag 将被还原，远端已推送的内容不会自动撤销；执行前需要人工审批。", plan.len())
+            } else {
+                format!("回滚预览：{} 个仓库将被还原，执行前需要人工审批。", plan.len())
+            }
+        }));
+    }
+    let approval_id = match authorize_dangerous_step(session_id, "rollback")? {
+        Ok((_, approval_id)) => approval_id,
+        Err(refusal) => return Ok(refusal),
+    };
+
+    let mut results = Vec::new();
+    let mut failed = Vec::new();
+    for step in &plan {
+        let outcome = rollback_repo(step).unwrap_or_else(|detail| {
+            json!({ "repoKey": step.get("repoKey").cloned().unwrap_or(Value::Null), "ok": false, "detail": detail })
+        });
+        if !outcome.get("ok").and_then(Value::as_bool).unwrap_or(false) {
+            failed.push(outcome.get("repoKey").and_then(Value::as_str).unwrap_or("").to_string());
+        }
+        results.push(outcome);
+    }
+
+    let summary = if failed.is_empty() && remote_pushed {
+        "已回滚本地稳定分支并删除本次创建的 Tag；远端已推送的内容需要手动处理。".to_string()
+    } else if failed.is_empty() {
+        "已回滚稳定分支并删除本次创建的 Tag。".to_string()
+    } else {
+        format!("以下仓库回滚失败：{}", failed.join("、"))
+    };
+    let touched = results.iter().any(|item| {
+        item.get("ok").and_then(Value::as_bool) == Some(true)
+            || item.get("resetTo").is_some()
+            || item.get("deletedTags").and_then(Value::as_array).is_some_and(|tags| !tags.is_empty())
+    });
+    settle_approval(session_id, &approval_id, touched)?;
+    let result = json!({
+        "ok": failed.is_empty(),
+        "stepId": "rollback",
+        "results": results,
+        "summary": summary
+    });
+    update_release_session(session_id, |session| {
+        session.record_rollback(failed.is_empty(), &summary, result.clone(), &timestamp_string());
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+/// Where release notes read Jira issues and merged PRs from; a missing credential skips that source.
+#[derive(Default)]
+struct ReleaseNotesSources {
+    domain: Option<String>,
+    email: Option<String>,
+    api_token: Option<String>,
+    project: Option<String>,
+    github_token: Option<String>,
+}
+
+/// GitHub API root of the repo's PR remote, so GitHub Enterprise checkouts are not sent to api.github.com.
+fn github_api_base_for_repo(path: &str) -> Option<String> {
+    let remotes = GitRepo::open(path).ok()?.remotes().ok()?;
+    let names = remotes.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    let pr_remote = default_pr_remote(&names)?;
+    let (_, url) = remotes.iter().find(|(name, _)| name == pr_remote)?;
+    parse_remote_url(url).filter(|remote| remote.forge == Forge::GitHub)?.api_base_url()
+}
+
+/// Join the session's Jira fix-version issues with the PRs merged into each repo's release
+/// branch. Sources that fail are recorded as warnings instead of failing the document.
+async fn collect_release_notes(session: &ReleaseSession, profile: &ReleaseProfile, sources: ReleaseNotesSources) -> ReleaseNotes {
+    let mut notes = ReleaseNotes::from_session(session, &timestamp_string());
+    let present = |value: Option<String>| value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+
+    match (present(sources.domain), present(sources.email), present(sources.api_token), present(sources.project)) {
+        (Some(domain), Some(email), Some(api_token), Some(project)) => {
+            match jira_get_version_issues(domain.clone(), email, api_token, project, session.version.clone()).await {
+                Ok(response) if response.status == 200 => {
+                    let body = serde_json::from_str::<Value>(&response.body).unwrap_or(Value::Null);
+                    notes.issues = body
+                        .get("issues")
+                        .and_then(Value::as_array)
+                        .map(|issues| issues.iter().filter_map(|issue| issue_from_jira(issue, &domain)).collect())
+                        .unwrap_or_default();
+                }
+                Ok(response) => notes.warnings.push(format!("Jira issues: HTTP {}", response.status)),
+                Err(error) => notes.warnings.push(format!("Jira issues: {}", error)),
+            }
+        }
+        _ => notes.warnings.push("Jira credentials not provided; issues are omitted.".to_string()),
+    }
+
+    let release_branch = profile.release_branch(&session.version);
+    match present(sources.github_token) {
+        Some(token) => {
+            for repo in &session.repos {
+                let full_name = repo.get("repo").and_then(Value::as_str).unwrap_or("");
+                let Some((owner, name)) = full_name.split_once('/') else { continue };
+                let api_base_url = github_api_base_for_repo(&repo_path(repo));
+                match github_list_merged_prs(owner.to_string(), name.to_string(), release_branch.clone(), token.clone(), api_base_url).await {
+                    Ok(response) if response.status == 200 => {
+                        let prs = serde_json::from_str::<Vec<Value>>(&response.body).unwrap_or_default();
+                        notes.prs.extend(prs.iter().filter_map(|pr| pr_from_github(full_name, pr)));
+                    }
+                    Ok(response) => notes.warnings.push(format!("{} pull requests: HTTP {}", full_name, response.status)),
+                    Err(error) => notes.warnings.push(format!("{} pull requests: {}", full_name, error)),
+                }
+            }
+        }
+        None => notes.warnings.push("GitHub token not provided; pull requests are omitted.".to_string()),
+    }
+    notes
+}
+
+fn template_error_response(step_id: &str, template: &ReleaseTemplate, errors: &[TemplateError]) -> Value {
+    json!({
+        "ok": false,
+        "stepId": step_id,
+        "template": template.name,
+        "errors": errors,
+        "summary": format!(
+            "发布文档模板 {} 渲染失败：{}",
+            template.name,
+            errors.first().map(|error| error.message.as_str()).unwrap_or("")
+        )
+    })
+}
+
+/// Render the release notes through the workspace's document template and write them as
+/// Markdown, HTML and Confluence storage format.
+///
+/// Jira issues come from the session's fix version and PRs from each repo's release branch;
+/// a source without credentials (or that fails) is skipped and listed under warnings.
+#[tauri::command]
+pub async fn release_generate_confluence_draft(
+    session_id: String,
+    domain: Option<String>,
+    email: Option<String>,
+    api_token: Option<String>,
+    project: Option<String>,
+    github_token: Option<String>,
+) -> Result<Value, String> {
+    let session = read_release_session(&session_id)?;
+    let profile = release_profile_for(&session)?;
+    let template = ReleaseTemplate::load(Some(&session.workspace_path), &profile.doc_template)?;
+    let artifact_dir = release_artifact_dir(&session_id)?;
+    let sources = ReleaseNotesSources { domain, email, api_token, project, github_token };
+    let notes = collect_release_notes(&session, &profile, sources).await;
+
+    let markdown = match notes.render_markdown(&template) {
+        Ok(markdown) => markdown,
+        Err(error) => {
+            let result = template_error_response("confluenceDraft", &template, &[error]);
+            record_release_step_outcome(&session_id, "confluenceDraft", &result)?;
+            return Ok(result);
+        }
+    };
+    let draft_id = format!("draft-{}", timestamp_string());
+    let markdown_path = artifact_dir.join(format!("{}.md", draft_id));
+    let html_path = artifact_dir.join(format!("{}.html", draft_id));
+    let storage_path = artifact_dir.join(format!("{}.storage.xml", draft_id));
+    for (path, content) in [
+        (&markdown_path, markdown.clone()),
+        (&html_path, markdown_html_document(&notes.title(), &markdown)),
+        (&storage_path, markdown_to_storage(&markdown)),
+    ] {
+        fs::write(path, content).map_err(|e| format!("Failed to write release notes {}: {}", path.display(), e))?;
+    }
+
+    let result = json!({
         "ok": true,
         "stepId": "confluenceDraft",
         "draftId": draft_id,
+        "template": template.name,
+        "issueCount": notes.issues.len(),
+        "prCount": notes.prs.len(),
+        "warnings": notes.warnings,
+        "formats": {
+            "markdown": markdown_path.display().to_string(),
+            "html": html_path.display().to_string(),
+            "storage": storage_path.display().to_string()
+        },
         "artifacts": [{
             "stepId": "confluenceDraft",
             "kind": "confluence-draft",
-            "path": file_path.display().to_string(),
-            "title": format!("Release {} draft", version)
+            "path": markdown_path.display().to_string(),
+            "title": format!("{} draft", notes.title()),
+            "htmlPath": html_path.display().to_string(),
+            "storagePath": storage_path.display().to_string()
         }],
-        "summary": "已生成运维发布文档草稿。"
-    }))
+        "summary": if notes.warnings.is_empty() {
+            format!("已生成发布说明草稿：{} 个 Issue，{} 个 PR。", notes.issues.len(), notes.prs.len())
+        } else {
+            format!(
+                "已生成发布说明草稿：{} 个 Issue，{} 个 PR；{} 项数据未能获取，详见草稿中的 Warnings。",
+                notes.issues.len(),
+                notes.prs.len(),
+                notes.warnings.len()
+            )
+        }
+    });
+    record_release_step_outcome(&session_id, "confluenceDraft", &result)?;
+    Ok(result)
+}
+
+/// Render the release document for a session without writing an artifact or touching the
+/// session; `template_source` previews unsaved edits, otherwise the profile's template is used.
+#[tauri::command]
+pub async fn release_preview_release_doc(
+    session_id: String,
+    template_source: Option<String>,
+    domain: Option<String>,
+    email: Option<String>,
+    api_token: Option<String>,
+    project: Option<String>,
+    github_token: Option<String>,
+) -> Result<Value, String> {
+    let session = read_release_session(&session_id)?;
+    let profile = release_profile_for(&session)?;
+    let template = match template_source {
+        Some(source) => ReleaseTemplate::inline(source),
+        None => ReleaseTemplate::load(Some(&session.workspace_path), &profile.doc_template)?,
+    };
+    let sources = ReleaseNotesSources { domain, email, api_token, project, github_token };
+    let notes = collect_release_notes(&session, &profile, sources).await;
+    match notes.render_markdown(&template) {
+        Ok(markdown) => Ok(json!({
+            "ok": true,
+            "template": template.name,
+            "title": notes.title(),
+            "markdown": markdown,
+            "html": markdown_to_html(&markdown),
+            "warnings": notes.warnings,
+            "summary": "已生成发布文档预览（未写入产物）。"
+        })),
+        Err(error) => Ok(template_error_response("confluenceDraft", &template, &[error])),
+    }
 }
 
 #[tauri::command]
 pub async fn release_publish_confluence_doc(
     session_id: String,
     draft_id: Option<String>,
+    email: Option<String>,
+    api_token: Option<String>,
+    actor: Option<String>,
 ) -> Result<Value, String> {
-    let artifact_dir = release_artifact_dir(&session_id)?;
-    let draft_name = draft_id.unwrap_or_else(|| "latest-draft".to_string());
-    let source = artifact_dir.join(format!("{}.md", draft_name));
-    let target = artifact_dir.join(format!("published-{}.md", timestamp_string()));
+    let inputs = json!({ "sessionId": session_id, "draftId": draft_id, "email": email });
+    let mut result = publish_release_confluence_doc(&session_id, draft_id, email, api_token).await;
+    audit_release_action(&session_id, "release_publish_confluence_doc", &release_actor(actor), inputs, &mut result);
+    result
+}
 
-    if source.exists() {
-        fs::copy(&source, &target)
-            .map_err(|e| format!("Failed to publish Confluence draft locally: {}", e))?;
-    } else {
-        fs::write(&target, "# Published Release Document\n")
-            .map_err(|e| format!("Failed to create published document: {}", e))?;
+/// Draft to publish: the requested one, otherwise the newest `confluence-draft` artifact.
+fn confluence_draft_path(session: &ReleaseSession, artifact_dir: &Path, draft_id: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(draft_id) = draft_id.map(str::trim).filter(|id| !id.is_empty()) {
+        if draft_id.contains(['/', '\\']) || draft_id.contains("..") {
+            return Err(format!("Invalid Confluence draft id: {}", draft_id));
+        }
+        let path = artifact_dir.join(format!("{}.md", draft_id));
+        return if path.exists() {
+            Ok(path)
+        } else {
+            Err(format!("Confluence draft not found: {}", draft_id))
+        };
     }
+    session
+        .artifacts
+        .iter()
+        .rev()
+        .filter(|artifact| artifact.kind == "confluence-draft")
+        .find_map(|artifact| artifact.path.as_deref().map(PathBuf::from).filter(|path| path.exists()))
+        .ok_or_else(|| "No Confluence draft in this session; generate one first".to_string())
+}
 
-    Ok(json!({
-        "ok": true,
-        "stepId": "confluencePublish",
-        "artifacts": [{
+async fn publish_release_confluence_doc(
+    session_id: &str,
+    draft_id: Option<String>,
+    email: Option<String>,
+    api_token: Option<String>,
+) -> Result<Value, String> {
+    let session = read_release_session(session_id)?;
+    session.ensure_step_can_start("confluencePublish")?;
+    let Some(target) = release_profile_for(&session)?.confluence.filter(|target| target.is_configured()) else {
+        return Ok(json!({
+            "ok": false,
+            "blocked": true,
             "stepId": "confluencePublish",
-            "kind": "confluence-published",
-            "path": target.display().to_string(),
-            "title": "Published release document"
-        }],
-        "url": format!("file://{}", target.display()),
-        "summary": "已发布运维发布文档。"
-    }))
+            "reason": "confluenceNotConfigured",
+            "summary": "发布配置未设置 Confluence 空间，请先在 release profile 中配置 confluence.baseUrl 与 spaceKey。"
+        }));
+    };
+    let artifact_dir = release_artifact_dir(session_id)?;
+    let draft_path = confluence_draft_path(&session, &artifact_dir, draft_id.as_deref())?;
+    let markdown = fs::read_to_string(&draft_path)
+        .map_err(|e| format!("Failed to read Confluence draft {}: {}", draft_path.display(), e))?;
+    let storage = markdown_to_storage(&markdown);
+    let title = target.page_title(&session.version);
+    // Re-publishing the same session updates the page created last time, even if it was renamed.
+    let previous = session.step_result("confluencePublish");
+    let known_page_id = previous.get("pageId").and_then(Value::as_str);
+
+    let client = ConfluenceClient::new(&target.base_url, email.as_deref(), api_token.as_deref());
+    let result = match client.publish(&target, &title, &storage, known_page_id).await {
+        Ok((page, created)) => {
+            let snapshot = artifact_dir.join(format!("published-{}.storage.xml", timestamp_string()));
+            fs::write(&snapshot, &storage)
+                .map_err(|e| format!("Failed to write published document snapshot: {}", e))?;
+            json!({
+                "ok": true,
+                "stepId": "confluencePublish",
+                "pageId": page.id,
+                "version": page.version,
+                "url": page.url,
+                "created": created,
+                "title": page.title,
+                "artifacts": [{
+                    "stepId": "confluencePublish",
+                    "kind": "confluence-page",
+                    "path": snapshot.display().to_string(),
+                    "title": page.title,
+                    "url": page.url,
+                    "pageId": page.id,
+                    "version": page.version
+                }],
+                "summary": format!(
+                    "已{}Confluence 页面：{}（版本 {}）",
+                    if created { "创建" } else { "更新" },
+                    page.url,
+                    page.version
+                )
+            })
+        }
+        Err(error) => json!({
+            "ok": false,
+            "stepId": "confluencePublish",
+            "title": title,
+            "error": error,
+            "summary": format!("发布到 Confluence 失败：{}", error)
+        }),
+    };
+    record_release_step_outcome(session_id, "confluencePublish", &result)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod release_push_tests {
+    use super::{merge_release_repo, push_repo_refs, run_release_repos, tag_release_repo, ReleaseProfile, ReleaseSession};
+    use serde_json::json;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = Command::new("git")
+            .args(["-c", "user.name=FlowDesk", "-c", "user.email=flowdesk@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("git should run");
+        assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    }
+
+    fn commit(dir: &Path, name: &str) {
+        fs::write(dir.join(name), name).expect("write file");
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-m", name]);
+    }
+
+    fn create_remote_with_clones(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("flow-desk-release-push-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("create temp root");
+        let remote = root.join("remote.git");
+        git(&root, &["init", "--bare", "-b", "latest", remote.to_str().unwrap()]);
+        let (ours, theirs) = (root.join("ours"), root.join("theirs"));
+        git(&root, &["clone", remote.to_str().unwrap(), ours.to_str().unwrap()]);
+        git(&ours, &["checkout", "-b", "latest"]);
+        commit(&ours, "base.txt");
+        git(&ours, &["push", "origin", "latest"]);
+        git(&root, &["clone", "-b", "latest", remote.to_str().unwrap(), theirs.to_str().unwrap()]);
+        (root, ours, theirs)
+    }
+
+    #[test]
+    fn pushes_fast_forward_and_refuses_diverged_latest() {
+        let (_root, ours, theirs) = create_remote_with_clones("latest");
+        let path = ours.to_str().unwrap();
+
+        commit(&ours, "release.txt");
+        let pushed = push_repo_refs("web", path, "origin", &["refs/heads/latest".to_string()]);
+        assert_eq!(pushed["ok"], true, "{}", pushed);
+        assert_eq!(pushed["refs"][0]["remoteSha"], pushed["sha"]);
+
+        git(&theirs, &["pull", "origin", "latest"]);
+        commit(&theirs, "hotfix.txt");
+        git(&theirs, &["push", "origin", "latest"]);
+        commit(&ours, "second.txt");
+        let refused = push_repo_refs("web", path, "origin", &["refs/heads/latest".to_string()]);
+        assert_eq!(refused["ok"], false);
+        assert!(refused["detail"].as_str().unwrap().contains("fast-forward"));
+    }
+
+    #[test]
+    fn pushes_several_tags_atomically_and_refuses_moved_tags() {
+        let (_root, ours, theirs) = create_remote_with_clones("tags");
+        let path = ours.to_str().unwrap();
+        git(&ours, &["tag", "release/v1.0.0"]);
+        git(&ours, &["tag", "release/v1.0.0-web"]);
+
+        let refs = vec!["refs/tags/release/v1.0.0".to_string(), "refs/tags/release/v1.0.0-web".to_string()];
+        let pushed = push_repo_refs("web", path, "origin", &refs);
+        assert_eq!(pushed["ok"], true, "{}", pushed);
+        assert_eq!(pushed["refs"].as_array().unwrap().len(), 2);
+
+        commit(&theirs, "other.txt");
+        git(&theirs, &["tag", "release/v2.0.0"]);
+        git(&theirs, &["push", "origin", "refs/tags/release/v2.0.0"]);
+        git(&ours, &["tag", "release/v2.0.0"]);
+        let refused = push_repo_refs("web", path, "origin", &["refs/tags/release/v2.0.0".to_string()]);
+        assert_eq!(refused["ok"], false);
+    }
+    #[test]
+    fn merge_stops_before_recording_pre_merge_sha_when_checkout_fails() {
+        let (_root, ours, _theirs) = create_remote_with_clones("merge-checkout");
+        git(&ours, &["checkout", "-b", "feature"]);
+        commit(&ours, "feature.txt");
+        let profile = ReleaseProfile { stable_branch: "stable".to_string(), ..ReleaseProfile::default() };
+
+        let merged = merge_release_repo("web", ours.to_str().unwrap(), &profile, "origin/latest").expect("merge result");
+        assert_eq!(merged["ok"], false, "{}", merged);
+        assert!(merged.get("preMergeSha").is_none());
+        assert!(merged["detail"].as_str().unwrap().contains("git checkout"));
+    }
+
+    #[test]
+    fn runs_repos_on_the_pool_and_isolates_failures() {
+        let (root, ours, theirs) = create_remote_with_clones("pool");
+        let mut session = ReleaseSession::new("release-session-pool".into(), "1.0.0".into(), "production".into(), "0".into());
+        session.repos = vec![
+            json!({ "key": "web", "path": ours.to_str().unwrap() }),
+            json!({ "key": "broken", "path": root.join("missing").to_str().unwrap() }),
+            json!({ "key": "skipped", "path": "" }),
+            json!({ "key": "admin", "path": theirs.to_str().unwrap() }),
+        ];
+
+        let (results, failed) = run_release_repos(&session, "tagRelease", 2, None, |key, path, _| {
+            tag_release_repo(key, path, "release/v1.0.0")
+        });
+        let keys = results.iter().map(|result| result["repoKey"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(keys, ["web", "broken", "admin"]);
+        assert_eq!(failed, ["broken"]);
+        assert_eq!(results[0]["created"], true);
+        assert_eq!(results[2]["created"], true);
+    }
 }
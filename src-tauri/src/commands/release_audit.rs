@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::release_store::{write_atomic, ReleaseStore};
+
+/// `prevHash` of the first entry in every log.
+pub const AUDIT_GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One line of `audit/<sessionId>.jsonl`.
+///
+/// `hash` is the SHA-256 of the entry serialized with an empty `hash`, and `prevHash`
+/// links it to the entry before, so editing or dropping any line breaks the chain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub ts: String,
+    pub session_id: String,
+    pub action: String,
+    pub actor: String,
+    pub inputs: Value,
+    pub outcome: String,
+    pub summary: String,
+    pub shas: Vec<Value>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> Result<String, String> {
+        let mut unsigned = self.clone();
+        unsigned.hash.clear();
+        let payload = serde_json::to_string(&unsigned)
+            .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+        Ok(Sha256::digest(payload.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+}
+
+/// Latest `seq`/`hash`, kept next to the log so a truncated tail is detectable too.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditHead {
+    seq: u64,
+    hash: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    pub ok: bool,
+    pub entries: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken_at: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl AuditVerification {
+    fn broken(entries: usize, line: usize, reason: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            entries,
+            broken_at: Some(line),
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+fn head_path(log_path: &Path) -> PathBuf {
+    log_path.with_extension("head.json")
+}
+
+fn read_log_lines(path: &Path) -> Result<Vec<String>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Chain `entry` onto the session's log under the store lock and return it as written.
+pub fn append_audit_entry(store: &ReleaseStore, mut entry: AuditEntry) -> Result<AuditEntry, String> {
+    let path = store.audit_log_path(&entry.session_id)?;
+    store.locked(|| {
+        let previous = match read_log_lines(&path)?.last() {
+            Some(line) => Some(
+                serde_json::from_str::<AuditEntry>(line)
+                    .map_err(|e| format!("Audit log is corrupted, refusing to append: {}", e))?,
+            ),
+            None => None,
+        };
+        entry.seq = previous.as_ref().map_or(1, |last| last.seq + 1);
+        entry.prev_hash = previous
+            .map(|last| last.hash)
+            .unwrap_or_else(|| AUDIT_GENESIS_HASH.to_string());
+        entry.hash = entry.compute_hash()?;
+
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        writeln!(file, "{}", line)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| format!("Failed to append to {}: {}", path.display(), e))?;
+
+        let head = AuditHead {
+            seq: entry.seq,
+            hash: entry.hash.clone(),
+        };
+        write_atomic(&head_path(&path), &json!(head).to_string())?;
+        Ok(entry)
+    })
+}
+
+pub fn read_audit_log(store: &ReleaseStore, session_id: &str) -> Result<Vec<AuditEntry>, String> {
+    read_log_lines(&store.audit_log_path(session_id)?)?
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            serde_json::from_str::<AuditEntry>(line)
+                .map_err(|e| format!("Invalid audit entry at line {}: {}", index + 1, e))
+        })
+        .collect()
+}
+
+/// Walk the chain from the genesis hash and report the first line that does not check out.
+pub fn verify_audit_log(store: &ReleaseStore, session_id: &str) -> Result<AuditVerification, String> {
+    let path = store.audit_log_path(session_id)?;
+    let lines = read_log_lines(&path)?;
+    let mut expected_prev = AUDIT_GENESIS_HASH.to_string();
+
+    for (index, line) in lines.iter().enumerate() {
+        let line_no = index + 1;
+        let entry = match serde_json::from_str::<AuditEntry>(line) {
+            Ok(entry) => entry,
+            Err(_) => return Ok(AuditVerification::broken(lines.len(), line_no, "无法解析审计记录")),
+        };
+        if entry.seq != line_no as u64 {
+            return Ok(AuditVerification::broken(
+                lines.len(),
+                line_no,
+                format!("序号不连续：期望 {}，实际 {}", line_no, entry.seq),
+            ));
+        }
+        if entry.prev_hash != expected_prev {
+            return Ok(AuditVerification::broken(lines.len(), line_no, "prevHash 与上一条记录不匹配"));
+        }
+        if entry.compute_hash()? != entry.hash {
+            return Ok(AuditVerification::broken(lines.len(), line_no, "记录内容与 hash 不匹配"));
+        }
+        expected_prev = entry.hash;
+    }
+
+    let head_file = head_path(&path);
+    if head_file.exists() {
+        let head = fs::read_to_string(&head_file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<AuditHead>(&content).ok())
+            .unwrap_or_default();
+        if head.seq != lines.len() as u64 || head.hash != expected_prev {
+            return Ok(AuditVerification::broken(
+                lines.len(),
+                lines.len() + 1,
+                format!("日志尾部与记录的 head 不一致（head seq {}）", head.seq),
+            ));
+        }
+    } else if !lines.is_empty() {
+        return Ok(AuditVerification::broken(lines.len(), lines.len(), "缺少审计日志 head 文件"));
+    }
+
+    Ok(AuditVerification {
+        ok: true,
+        entries: lines.len(),
+        broken_at: None,
+        reason: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_temp_store(name: &str) -> ReleaseStore {
+        let root = std::env::temp_dir().join(format!("flow-desk-release-audit-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        ReleaseStore::open(root).expect("store should open")
+    }
+
+    fn event(action: &str) -> AuditEntry {
+        AuditEntry {
+            ts: "1700000000000".to_string(),
+            session_id: "release-session-1".to_string(),
+            action: action.to_string(),
+            actor: "alice".to_string(),
+            inputs: json!({ "sessionId": "release-session-1" }),
+            outcome: "ok".to_string(),
+            shas: vec![json!({ "repoKey": "web", "sha": "abc123" })],
+            ..AuditEntry::default()
+        }
+    }
+
+    #[test]
+    fn chains_entries_and_detects_tampering() {
+        let store = create_temp_store("audit-chain");
+        for action in ["release_approval_decide", "release_execute_merge", "release_create_tag"] {
+            append_audit_entry(&store, event(action)).expect("append should succeed");
+        }
+
+        let entries = read_audit_log(&store, "release-session-1").expect("log should read");
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].prev_hash, AUDIT_GENESIS_HASH);
+        assert_eq!(entries[2].prev_hash, entries[1].hash);
+        assert!(verify_audit_log(&store, "release-session-1").expect("verify").ok);
+
+        let path = store.audit_log_path("release-session-1").expect("path");
+        let original = fs::read_to_string(&path).expect("log content");
+        fs::write(&path, original.replacen("\"outcome\":\"ok\"", "\"outcome\":\"failed\"", 1)).expect("tamper");
+        let tampered = verify_audit_log(&store, "release-session-1").expect("verify");
+        assert!(!tampered.ok);
+        assert_eq!(tampered.broken_at, Some(1));
+
+        let truncated = original.lines().take(2).collect::<Vec<_>>().join("\n");
+        fs::write(&path, truncated).expect("truncate");
+        let truncated = verify_audit_log(&store, "release-session-1").expect("verify");
+        assert!(!truncated.ok);
+        assert_eq!(truncated.entries, 2);
+    }
+}
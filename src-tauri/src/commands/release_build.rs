@@ -0,0 +1,572 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tauri::Emitter;
+
+/// Characters of stdout/stderr kept in the build result; the log file has everything.
+const LOG_TAIL_CHARS: usize = 4000;
+/// Time a cancelled or timed-out build gets between the polite and the forced kill.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Cancel flags of running builds, so `release_cancel_build` can reach them by id.
+static RUNNING_BUILDS: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceLock::new();
+static BUILD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn running_builds() -> MutexGuard<'static, HashMap<String, Arc<AtomicBool>>> {
+    RUNNING_BUILDS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Wall-clock and no-output limits for one build; `None` disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BuildTimeouts {
+    pub total: Option<Duration>,
+    pub idle: Option<Duration>,
+}
+
+impl BuildTimeouts {
+    /// `0` means "no limit", matching the release profile fields.
+    pub fn from_secs(total: u64, idle: u64) -> Self {
+        let limit = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+        Self {
+            total: limit(total),
+            idle: limit(idle),
+        }
+    }
+}
+
+pub struct BuildRequest {
+    pub build_id: String,
+    pub project_path: String,
+    pub program: String,
+    pub args: Vec<String>,
+    pub log_path: PathBuf,
+    pub timeouts: BuildTimeouts,
+    /// How `program`/`args` were chosen, echoed in the result as `buildCommand`.
+    pub resolved: Option<ResolvedBuildCommand>,
+}
+
+impl BuildRequest {
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BuildStatus {
+    Succeeded,
+    Failed,
+    Cancelled,
+    TimedOut,
+}
+
+impl BuildStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::TimedOut => "timedOut",
+        }
+    }
+}
+
+/// A fresh id usable in file names and event names, e.g. `build-web-1700000000000-3`.
+pub fn new_build_id(label: &str) -> String {
+    let label = label
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' { ch } else { '-' })
+        .collect::<String>();
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let sequence = BUILD_COUNTER.fetch_add(1, Ordering::SeqCst);
+    if label.is_empty() {
+        format!("build-{}-{}", millis, sequence)
+    } else {
+        format!("build-{}-{}-{}", label, millis, sequence)
+    }
+}
+
+/// Ask a running build to stop. Returns false when no build with that id is running.
+pub fn cancel_build(build_id: &str) -> bool {
+    match running_builds().get(build_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Package.json scripts tried in order when neither the profile nor the repo says otherwise.
+const BUILD_SCRIPT_CANDIDATES: [&str; 3] = ["build", "build:prod", "build:production"];
+
+/// Per-repo build settings from the release profile. Any field left out is detected;
+/// `program` replaces detection entirely.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BuildOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_manager: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+/// The build command picked for one repo and why, reported back with the build result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedBuildCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub command: String,
+    /// `pnpm`, `npm`, `yarn`, `bun`, `cargo`, or `custom` for an explicit program.
+    pub tool: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    /// `override`, `packageManagerField`, `lockfile`, `manifest` (a Cargo.toml) or `default`.
+    pub source: String,
+}
+
+impl ResolvedBuildCommand {
+    fn new(program: &str, args: Vec<String>, tool: &str, script: Option<String>, source: &str) -> Self {
+        let command = std::iter::once(program.to_string())
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            program: program.to_string(),
+            args,
+            command,
+            tool: tool.to_string(),
+            script,
+            source: source.to_string(),
+        }
+    }
+}
+
+/// Work out how to build `project_path`: an explicit override wins, then package.json
+/// (manager from its `packageManager` field or the lockfile, script from `scripts`),
+/// then `Cargo.toml`.
+pub fn resolve_build_command(project_path: &Path, build_override: Option<&BuildOverride>) -> Result<ResolvedBuildCommand, String> {
+    let build_override = build_override.cloned().unwrap_or_default();
+    if let Some(program) = build_override.program.as_deref().filter(|program| !program.trim().is_empty()) {
+        return Ok(ResolvedBuildCommand::new(program, build_override.args.clone(), "custom", None, "override"));
+    }
+
+    let package_json = project_path.join("package.json");
+    if package_json.exists() {
+        let content = fs::read_to_string(&package_json)
+            .map_err(|e| format!("Failed to read {}: {}", package_json.display(), e))?;
+        let manifest = serde_json::from_str::<Value>(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", package_json.display(), e))?;
+
+        let (manager, mut source) = match build_override.package_manager.as_deref() {
+            Some(manager) => (manager.to_string(), "override"),
+            None => detect_package_manager(project_path, &manifest),
+        };
+        if !["pnpm", "npm", "yarn", "bun"].contains(&manager.as_str()) {
+            return Err(format!("Unsupported package manager for {}: {}", project_path.display(), manager));
+        }
+
+        let scripts = manifest.get("scripts").and_then(Value::as_object);
+        let script = match build_override.script {
+            Some(script) => {
+                source = "override";
+                script
+            }
+            None => BUILD_SCRIPT_CANDIDATES
+                .iter()
+                .find(|candidate| scripts.is_some_and(|scripts| scripts.contains_key(**candidate)))
+                .map(|candidate| candidate.to_string())
+                .ok_or_else(|| {
+                    format!(
+                        "{} has no build script (tried {}); set buildOverrides in the release profile",
+                        package_json.display(),
+                        BUILD_SCRIPT_CANDIDATES.join(", ")
+                    )
+                })?,
+        };
+        let args = vec!["run".to_string(), script.clone()];
+        return Ok(ResolvedBuildCommand::new(&manager, args, &manager, Some(script), source));
+    }
+
+    if project_path.join("Cargo.toml").exists() {
+        return Ok(ResolvedBuildCommand::new("cargo", vec!["build".to_string()], "cargo", None, "manifest"));
+    }
+
+    Err(format!(
+        "Cannot determine how to build {}: no package.json or Cargo.toml; set buildOverrides in the release profile",
+        project_path.display()
+    ))
+}
+
+/// `packageManager` (corepack) beats lockfiles; with neither we keep the historical pnpm default.
+fn detect_package_manager(project_path: &Path, manifest: &Value) -> (String, &'static str) {
+    if let Some(field) = manifest.get("packageManager").and_then(Value::as_str) {
+        let name = field.split('@').next().unwrap_or("").trim();
+        if !name.is_empty() {
+            return (name.to_string(), "packageManagerField");
+        }
+    }
+    let lockfiles = [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+        ("yarn.lock", "yarn"),
+        ("package-lock.json", "npm"),
+        ("npm-shrinkwrap.json", "npm"),
+    ];
+    lockfiles
+        .iter()
+        .find(|(file, _)| project_path.join(file).exists())
+        .map(|(_, manager)| (manager.to_string(), "lockfile"))
+        .unwrap_or_else(|| ("pnpm".to_string(), "default"))
+}
+
+/// Shared by the stdout and stderr readers of one build.
+struct BuildOutput {
+    build_id: String,
+    app: Option<tauri::AppHandle>,
+    log: Mutex<File>,
+    last_output: Mutex<Instant>,
+}
+
+impl BuildOutput {
+    fn record(&self, stream: &str, line: &str) {
+        {
+            let mut log = self.log.lock().unwrap_or_else(PoisonError::into_inner);
+            let _ = match stream {
+                "stderr" => writeln!(log, "[stderr] {}", line),
+                _ => writeln!(log, "{}", line),
+            };
+        }
+        *self.last_output.lock().unwrap_or_else(PoisonError::into_inner) = Instant::now();
+        self.emit(json!({ "buildId": self.build_id, "stream": stream, "line": line }));
+    }
+
+    fn note(&self, message: &str) {
+        let mut log = self.log.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = writeln!(log, "[flow-desk] {}", message);
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_output.lock().unwrap_or_else(PoisonError::into_inner).elapsed()
+    }
+
+    fn emit(&self, payload: Value) {
+        if let Some(app) = &self.app {
+            let _ = app.emit(&format!("release-build-log-{}", self.build_id), payload);
+        }
+    }
+}
+
+/// Run a build to completion, streaming every output line as a `release-build-log-{buildId}`
+/// event and into `log_path`. The build runs in its own process group so cancellation and
+/// timeouts take down the whole tree, not just the package-manager wrapper.
+pub fn run_streaming_build(request: &BuildRequest, app: Option<tauri::AppHandle>) -> Result<Value, String> {
+    let mut log = File::create(&request.log_path)
+        .map_err(|e| format!("Failed to create {}: {}", request.log_path.display(), e))?;
+    let _ = writeln!(log, "$ {}  (in {})", request.command_line(), request.project_path);
+
+    let mut command = Command::new(&request.program);
+    command
+        .args(&request.args)
+        .current_dir(&request.project_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    isolate_process_group(&mut command);
+
+    let start = Instant::now();
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", request.command_line(), e))?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    running_builds().insert(request.build_id.clone(), cancel.clone());
+
+    let output = Arc::new(BuildOutput {
+        build_id: request.build_id.clone(),
+        app,
+        log: Mutex::new(log),
+        last_output: Mutex::new(Instant::now()),
+    });
+    let stdout = child.stdout.take().map(|stream| spawn_reader(stream, "stdout", output.clone()));
+    let stderr = child.stderr.take().map(|stream| spawn_reader(stream, "stderr", output.clone()));
+
+    let waited = wait_for_build(&mut child, &cancel, &output, request.timeouts, start);
+    running_builds().remove(&request.build_id);
+    let (exit_status, status, detail) = waited?;
+
+    // Background processes left by the build would keep the pipes open forever.
+    let readers_done = || [&stdout, &stderr].iter().all(|reader| reader.as_ref().is_none_or(JoinHandle::is_finished));
+    let drained_by = Instant::now() + KILL_GRACE;
+    while !readers_done() && Instant::now() < drained_by {
+        thread::sleep(POLL_INTERVAL);
+    }
+    if !readers_done() {
+        terminate_tree(&child, true);
+    }
+    let join = |reader: Option<JoinHandle<String>>| reader.and_then(|handle| handle.join().ok()).unwrap_or_default();
+    let (stdout_tail, stderr_tail) = (join(stdout), join(stderr));
+
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+    output.note(&format!("{} after {} ms{}", status.as_str(), elapsed_ms, detail.as_deref().map(|d| format!(": {}", d)).unwrap_or_default()));
+    output.emit(json!({ "buildId": request.build_id, "done": true, "status": status.as_str() }));
+
+    Ok(json!({
+        "buildId": request.build_id,
+        "success": status == BuildStatus::Succeeded,
+        "status": status.as_str(),
+        "exitCode": exit_status.and_then(|status| status.code()).unwrap_or(-1),
+        "stdout": stdout_tail,
+        "stderr": stderr_tail,
+        "elapsedMs": elapsed_ms,
+        "command": request.command_line(),
+        "buildCommand": request.resolved,
+        "logPath": request.log_path.display().to_string(),
+        "detail": detail
+    }))
+}
+
+fn spawn_reader(stream: impl Read + Send + 'static, name: &'static str, output: Arc<BuildOutput>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buffer = Vec::new();
+        let mut tail = String::new();
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\r', '\n']);
+            output.record(name, line);
+            tail.push_str(line);
+            tail.push('\n');
+            if tail.len() > LOG_TAIL_CHARS * 8 {
+                tail = keep_tail(&tail, LOG_TAIL_CHARS);
+            }
+        }
+        keep_tail(&tail, LOG_TAIL_CHARS)
+    })
+}
+
+fn keep_tail(text: &str, max_chars: usize) -> String {
+    let skip = text.chars().count().saturating_sub(max_chars);
+    text.chars().skip(skip).collect()
+}
+
+/// Poll the child until it exits, stopping it on cancel or timeout: first politely, then
+/// forcefully once `KILL_GRACE` has passed.
+fn wait_for_build(
+    child: &mut Child,
+    cancel: &AtomicBool,
+    output: &BuildOutput,
+    timeouts: BuildTimeouts,
+    start: Instant,
+) -> Result<(Option<ExitStatus>, BuildStatus, Option<String>), String> {
+    let mut stopping: Option<(BuildStatus, String, Instant)> = None;
+    let mut forced = false;
+    loop {
+        if let Some(exit) = child.try_wait().map_err(|e| format!("Failed to wait for build: {}", e))? {
+            return Ok(match stopping {
+                Some((status, detail, _)) => (Some(exit), status, Some(detail)),
+                None if exit.success() => (Some(exit), BuildStatus::Succeeded, None),
+                None => (Some(exit), BuildStatus::Failed, None),
+            });
+        }
+        match &stopping {
+            None => {
+                let over_total = timeouts.total.filter(|limit| start.elapsed() >= *limit);
+                let over_idle = timeouts.idle.filter(|limit| output.idle_for() >= *limit);
+                let reason = match (cancel.load(Ordering::SeqCst), over_total, over_idle) {
+                    (true, _, _) => Some((BuildStatus::Cancelled, "构建已被取消".to_string())),
+                    (_, Some(limit), _) => Some((BuildStatus::TimedOut, format!("构建超过 {} 秒未完成", limit.as_secs()))),
+                    (_, _, Some(limit)) => Some((BuildStatus::TimedOut, format!("构建 {} 秒没有任何输出", limit.as_secs()))),
+                    _ => None,
+                };
+                if let Some((status, detail)) = reason {
+                    output.note(&detail);
+                    terminate_tree(child, false);
+                    stopping = Some((status, detail, Instant::now()));
+                }
+            }
+            Some((_, _, since)) if !forced && since.elapsed() >= KILL_GRACE => {
+                terminate_tree(child, true);
+                let _ = child.kill();
+                forced = true;
+            }
+            Some(_) => {}
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(unix)]
+fn isolate_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn isolate_process_group(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn isolate_process_group(_command: &mut Command) {}
+
+/// Signal the build's whole process group (its pid is the group id, see `isolate_process_group`).
+#[cfg(unix)]
+fn terminate_tree(child: &Child, force: bool) {
+    let signal = if force { "-KILL" } else { "-TERM" };
+    let _ = Command::new("kill")
+        .args([signal, "--", &format!("-{}", child.id())])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// `taskkill /T` walks the child tree; Windows has no polite variant for console builds.
+#[cfg(windows)]
+fn terminate_tree(child: &Child, _force: bool) {
+    let _ = Command::new("taskkill")
+        .args(["/PID", &child.id().to_string(), "/T", "/F"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(not(any(unix, windows)))]
+fn terminate_tree(_child: &Child, _force: bool) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("flow-desk-build-detect-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create project dir");
+        for (file, content) in files {
+            fs::write(dir.join(file), content).expect("write project file");
+        }
+        dir
+    }
+
+    #[test]
+    fn detects_manager_and_script_from_the_repo() {
+        let yarn = project("yarn", &[("package.json", r#"{"scripts":{"build:prod":"vite build"}}"#), ("yarn.lock", "")]);
+        let resolved = resolve_build_command(&yarn, None).expect("yarn repo");
+        assert_eq!(resolved.command, "yarn run build:prod");
+        assert_eq!(resolved.source, "lockfile");
+
+        let corepack = project(
+            "corepack",
+            &[("package.json", r#"{"packageManager":"bun@1.1.0","scripts":{"build":"x"}}"#), ("package-lock.json", "{}")],
+        );
+        assert_eq!(resolve_build_command(&corepack, None).unwrap().command, "bun run build");
+
+        let cargo = project("cargo", &[("Cargo.toml", "[package]")]);
+        let resolved = resolve_build_command(&cargo, None).unwrap();
+        assert_eq!((resolved.command.as_str(), resolved.source.as_str()), ("cargo build", "manifest"));
+
+        let no_script = project("no-script", &[("package.json", r#"{"scripts":{"dev":"vite"}}"#)]);
+        assert!(resolve_build_command(&no_script, None).is_err());
+    }
+
+    #[test]
+    fn profile_override_wins_over_detection() {
+        let repo = project("override", &[("package.json", r#"{"scripts":{"build":"x"}}"#), ("pnpm-lock.yaml", "")]);
+        let script_only = BuildOverride {
+            script: Some("build:staging".to_string()),
+            ..BuildOverride::default()
+        };
+        let resolved = resolve_build_command(&repo, Some(&script_only)).unwrap();
+        assert_eq!(resolved.command, "pnpm run build:staging");
+        assert_eq!(resolved.source, "override");
+
+        let custom = BuildOverride {
+            program: Some("make".to_string()),
+            args: vec!["dist".to_string()],
+            ..BuildOverride::default()
+        };
+        assert_eq!(resolve_build_command(&repo, Some(&custom)).unwrap().command, "make dist");
+    }
+
+    #[cfg(unix)]
+    fn request(name: &str, script: &str, timeouts: BuildTimeouts) -> BuildRequest {
+        let dir = std::env::temp_dir().join(format!("flow-desk-release-build-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create build dir");
+        BuildRequest {
+            build_id: new_build_id(name),
+            project_path: dir.display().to_string(),
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            log_path: dir.join("build.log"),
+            timeouts,
+            resolved: None,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_full_log_and_reports_failure() {
+        let request = request("log", "for i in $(seq 1 2000); do echo line-$i; done; echo broken >&2; exit 3", BuildTimeouts::default());
+        let result = run_streaming_build(&request, None).expect("build should run");
+
+        assert_eq!(result["status"], "failed");
+        assert_eq!(result["exitCode"], 3);
+        assert!(result["stdout"].as_str().unwrap().ends_with("line-2000\n"));
+        assert!(result["stdout"].as_str().unwrap().chars().count() <= LOG_TAIL_CHARS);
+        let log = fs::read_to_string(&request.log_path).expect("log file");
+        assert!(log.contains("line-1\n"));
+        assert!(log.contains("[stderr] broken"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn times_out_and_cancels_the_process_tree() {
+        let timed_out = request("timeout", "sleep 30 & wait", BuildTimeouts::from_secs(1, 0));
+        let result = run_streaming_build(&timed_out, None).expect("build should run");
+        assert_eq!(result["status"], "timedOut");
+        assert!(result["elapsedMs"].as_u64().unwrap() < 10_000);
+
+        let cancelled = request("cancel", "echo started; sleep 30 & wait", BuildTimeouts::default());
+        let build_id = cancelled.build_id.clone();
+        let canceller = thread::spawn(move || {
+            while !cancel_build(&build_id) {
+                thread::sleep(Duration::from_millis(20));
+            }
+        });
+        let result = run_streaming_build(&cancelled, None).expect("build should run");
+        canceller.join().unwrap();
+        assert_eq!(result["status"], "cancelled");
+        assert!(!cancel_build(&cancelled.build_id));
+    }
+}
@@ -0,0 +1,599 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::confluence::markdown_to_html;
+use super::release_session::ReleaseSession;
+use super::release_template::{ReleaseTemplate, TemplateError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteIssue {
+    pub key: String,
+    pub summary: String,
+    pub issue_type: String,
+    pub status: String,
+    /// Epic or parent issue as `(key, summary)`.
+    pub parent: Option<(String, String)>,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotePr {
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    pub author: String,
+    pub merged_at: String,
+    pub issue_keys: Vec<String>,
+}
+
+/// Everything the release document says about one session. Templates render it to Markdown,
+/// which is converted to HTML or Confluence storage format.
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseNotes {
+    pub version: String,
+    pub session_id: String,
+    pub environment: String,
+    pub status: String,
+    pub generated_at: String,
+    pub repos: Vec<String>,
+    pub issues: Vec<NoteIssue>,
+    pub prs: Vec<NotePr>,
+    /// `(repoKey, file, ["key (change)", ..])` rows from the `configChanges` step; values are
+    /// left out so secrets never reach the document.
+    pub config_changes: Vec<(String, String, Vec<String>)>,
+    /// `(repoKey, file, key, changeType)` rows from the `i18nChanges` step.
+    pub i18n_changes: Vec<(String, String, String, String)>,
+    /// `(repoKey, status, command, elapsedMs)` rows from the `buildVerification` step.
+    pub builds: Vec<(String, String, String, u64)>,
+    pub approvals: Vec<(String, String)>,
+    /// Sources that could not be read; listed in the document so gaps are visible.
+    pub warnings: Vec<String>,
+}
+
+/// Build a note issue from one entry of a Jira search response.
+pub fn issue_from_jira(issue: &Value, jira_domain: &str) -> Option<NoteIssue> {
+    let key = issue.get("key").and_then(Value::as_str)?.to_string();
+    let fields = issue.get("fields").cloned().unwrap_or(Value::Null);
+    let text = |value: &Value, pointer: &str| value.pointer(pointer).and_then(Value::as_str).unwrap_or("").to_string();
+    let parent = fields
+        .get("parent")
+        .and_then(|parent| parent.get("key").and_then(Value::as_str).map(|key| (key.to_string(), text(parent, "/fields/summary"))));
+    Some(NoteIssue {
+        url: if jira_domain.trim().is_empty() {
+            String::new()
+        } else {
+            format!("https://{}/browse/{}", jira_domain.trim().trim_end_matches('/'), key)
+        },
+        summary: text(&fields, "/summary"),
+        issue_type: text(&fields, "/issuetype/name"),
+        status: text(&fields, "/status/name"),
+        parent,
+        key,
+    })
+}
+
+/// Build a note PR from a GitHub pull request; closed-but-unmerged PRs are skipped.
+pub fn pr_from_github(repo: &str, pr: &Value) -> Option<NotePr> {
+    let merged_at = pr.get("merged_at").and_then(Value::as_str)?.to_string();
+    let title = pr.get("title").and_then(Value::as_str).unwrap_or("").to_string();
+    let head = pr.pointer("/head/ref").and_then(Value::as_str).unwrap_or("");
+    let mut issue_keys = extract_issue_keys(&title);
+    for key in extract_issue_keys(head) {
+        if !issue_keys.contains(&key) {
+            issue_keys.push(key);
+        }
+    }
+    Some(NotePr {
+        repo: repo.to_string(),
+        number: pr.get("number").and_then(Value::as_u64).unwrap_or(0),
+        url: pr.get("html_url").and_then(Value::as_str).unwrap_or("").to_string(),
+        author: pr.pointer("/user/login").and_then(Value::as_str).unwrap_or("").to_string(),
+        merged_at,
+        issue_keys,
+        title,
+    })
+}
+
+/// Jira keys (`ABC-123`) mentioned in a PR title or branch name, in order of appearance.
+pub fn extract_issue_keys(text: &str) -> Vec<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut keys: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let starts_word = index == 0 || !chars[index - 1].is_ascii_alphanumeric();
+        if !(starts_word && chars[index].is_ascii_uppercase()) {
+            index += 1;
+            continue;
+        }
+        let mut end = index + 1;
+        while end < chars.len() && (chars[end].is_ascii_uppercase() || chars[end].is_ascii_digit()) {
+            end += 1;
+        }
+        let digits_start = end + 1;
+        let mut digits_end = digits_start;
+        while digits_end < chars.len() && chars[digits_end].is_ascii_digit() {
+            digits_end += 1;
+        }
+        let ends_word = digits_end == chars.len() || !chars[digits_end].is_ascii_alphanumeric();
+        if end - index >= 2 && chars.get(end) == Some(&'-') && digits_end > digits_start && ends_word {
+            let key = chars[index..digits_end].iter().collect::<String>();
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+            index = digits_end;
+        } else {
+            index = end;
+        }
+    }
+    keys
+}
+
+impl ReleaseNotes {
+    /// Notes with the session facts and the config/i18n/build results already recorded on it.
+    /// Issues and PRs come from Jira and GitHub and are filled in by the caller.
+    pub fn from_session(session: &ReleaseSession, generated_at: &str) -> Self {
+        let rows = |step_id: &str, field: &str| {
+            session
+                .step_result(step_id)
+                .get(field)
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let text = |value: &Value, field: &str| value.get(field).and_then(Value::as_str).unwrap_or("").to_string();
+
+        let config_changes = rows("configChanges", "changes")
+            .iter()
+            .flat_map(|change| {
+                let repo = text(change, "repoKey");
+                let diffs = change.get("diffs").and_then(Value::as_array).cloned().unwrap_or_default();
+                change
+                    .get("files")
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(move |file| {
+                        let file = file.as_str()?.to_string();
+                        let keys = diffs
+                            .iter()
+                            .filter(|diff| diff.get("file").and_then(Value::as_str) == Some(file.as_str()))
+                            .flat_map(|diff| diff.get("changes").and_then(Value::as_array).cloned().unwrap_or_default())
+                            .map(|key| format!("{} ({})", text(&key, "key"), text(&key, "change")))
+                            .collect();
+                        Some((repo.clone(), file, keys))
+                    })
+            })
+            .collect();
+        let i18n_changes = rows("i18nChanges", "entries")
+            .iter()
+            .map(|entry| (text(entry, "repoKey"), text(entry, "file"), text(entry, "key"), text(entry, "changeType")))
+            .collect();
+        let builds = rows("buildVerification", "results")
+            .iter()
+            .map(|build| {
+                let status = match text(build, "status") {
+                    status if !status.is_empty() => status,
+                    _ if build.get("ok").and_then(Value::as_bool) == Some(true) => "succeeded".to_string(),
+                    _ => "failed".to_string(),
+                };
+                (
+                    text(build, "repoKey"),
+                    status,
+                    build.pointer("/buildCommand/command").and_then(Value::as_str).unwrap_or("").to_string(),
+                    build.get("elapsedMs").and_then(Value::as_u64).unwrap_or(0),
+                )
+            })
+            .collect();
+
+        Self {
+            version: session.version.clone(),
+            session_id: session.session_id.clone(),
+            environment: session.environment.clone(),
+            status: session.status.as_str().to_string(),
+            generated_at: generated_at.to_string(),
+            repos: session
+                .repos
+                .iter()
+                .map(|repo| {
+                    let name = text(repo, "repo");
+                    if name.is_empty() { text(repo, "key") } else { name }
+                })
+                .filter(|name| !name.is_empty())
+                .collect(),
+            approvals: session
+                .approvals
+                .iter()
+                .map(|approval| (approval.step_id.clone(), approval.decision.as_str().to_string()))
+                .collect(),
+            config_changes,
+            i18n_changes,
+            builds,
+            ..Self::default()
+        }
+    }
+
+    pub fn title(&self) -> String {
+        format!("Release {}", self.version)
+    }
+
+    /// The typed view templates render against; field names are camelCase in templates.
+    pub fn context(&self) -> ReleaseDocContext {
+        let pr_context = |pr: &NotePr| PrContext {
+            repo: pr.repo.clone(),
+            name: pr.repo.rsplit('/').next().unwrap_or(&pr.repo).to_string(),
+            number: pr.number,
+            title: pr.title.clone(),
+            link: if pr.url.is_empty() {
+                format!("{}#{}", pr.repo.rsplit('/').next().unwrap_or(&pr.repo), pr.number)
+            } else {
+                format!("[{}#{}]({})", pr.repo.rsplit('/').next().unwrap_or(&pr.repo), pr.number, pr.url)
+            },
+            url: pr.url.clone(),
+            author: pr.author.clone(),
+            merged_at: pr.merged_at.clone(),
+            merged_date: pr.merged_at.chars().take(10).collect(),
+            issue_keys: pr.issue_keys.clone(),
+        };
+        let issue_context = |issue: &NoteIssue| IssueContext {
+            key: issue.key.clone(),
+            summary: issue.summary.clone(),
+            issue_type: issue.issue_type.clone(),
+            status: issue.status.clone(),
+            link: if issue.url.is_empty() {
+                issue.key.clone()
+            } else {
+                format!("[{}]({})", issue.key, issue.url)
+            },
+            url: issue.url.clone(),
+            parent_key: issue.parent.as_ref().map(|(key, _)| key.clone()).unwrap_or_default(),
+            parent_summary: issue.parent.as_ref().map(|(_, summary)| summary.clone()).unwrap_or_default(),
+            prs: self
+                .prs
+                .iter()
+                .filter(|pr| pr.issue_keys.contains(&issue.key))
+                .map(pr_context)
+                .collect(),
+        };
+
+        ReleaseDocContext {
+            title: self.title(),
+            version: self.version.clone(),
+            session_id: self.session_id.clone(),
+            environment: self.environment.clone(),
+            status: self.status.clone(),
+            generated_at: self.generated_at.clone(),
+            repos: self.repos.clone(),
+            issue_groups: self
+                .issues_by_type()
+                .into_iter()
+                .map(|(issue_type, issues)| IssueGroupContext {
+                    issue_type,
+                    count: issues.len(),
+                    parents: group_by_parent(&issues)
+                        .into_iter()
+                        .map(|(parent, issues)| ParentContext {
+                            key: parent.as_ref().map(|(key, _)| key.clone()).unwrap_or_default(),
+                            summary: parent.map(|(_, summary)| summary).unwrap_or_default(),
+                            issues: issues.into_iter().map(issue_context).collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            issues: self.issues.iter().map(issue_context).collect(),
+            prs: self.prs.iter().map(pr_context).collect(),
+            config_changes: self
+                .config_changes
+                .iter()
+                .map(|(repo, file, keys)| ConfigChangeContext { repo: repo.clone(), file: file.clone(), keys: keys.clone() })
+                .collect(),
+            i18n_changes: self
+                .i18n_changes
+                .iter()
+                .map(|(repo, file, key, change)| I18nChangeContext {
+                    repo: repo.clone(),
+                    file: file.clone(),
+                    key: key.clone(),
+                    change: change.clone(),
+                })
+                .collect(),
+            builds: self
+                .builds
+                .iter()
+                .map(|(repo, status, command, elapsed_ms)| BuildContext {
+                    repo: repo.clone(),
+                    status: status.clone(),
+                    command: command.clone(),
+                    elapsed_ms: *elapsed_ms,
+                })
+                .collect(),
+            approvals: self
+                .approvals
+                .iter()
+                .map(|(step_id, decision)| ApprovalContext { step_id: step_id.clone(), decision: decision.clone() })
+                .collect(),
+            warnings: self.warnings.clone(),
+        }
+    }
+
+    pub fn render_markdown(&self, template: &ReleaseTemplate) -> Result<String, TemplateError> {
+        template.render(&self.context())
+    }
+
+    /// Notes with one of everything, used to validate templates before a real release.
+    pub fn sample() -> Self {
+        Self {
+            version: "1.0.0".to_string(),
+            session_id: "release-session-sample".to_string(),
+            environment: "prod".to_string(),
+            status: "ready".to_string(),
+            generated_at: "0".to_string(),
+            repos: vec!["acme/web".to_string()],
+            issues: vec![NoteIssue {
+                key: "DEMO-1".to_string(),
+                summary: "Sample story".to_string(),
+                issue_type: "Story".to_string(),
+                status: "Done".to_string(),
+                parent: Some(("DEMO-100".to_string(), "Sample epic".to_string())),
+                url: "https://example.atlassian.net/browse/DEMO-1".to_string(),
+            }],
+            prs: vec![NotePr {
+                repo: "acme/web".to_string(),
+                number: 1,
+                title: "DEMO-1 sample change".to_string(),
+                url: "https://github.com/acme/web/pull/1".to_string(),
+                author: "octocat".to_string(),
+                merged_at: "2026-01-01T00:00:00Z".to_string(),
+                issue_keys: vec!["DEMO-1".to_string()],
+            }],
+            config_changes: vec![("web".to_string(), ".env.production".to_string(), vec!["API_URL (changed)".to_string()])],
+            i18n_changes: vec![("web".to_string(), "locales/en.json".to_string(), "app.title".to_string(), "added".to_string())],
+            builds: vec![("web".to_string(), "succeeded".to_string(), "pnpm run build".to_string(), 1000)],
+            approvals: vec![("merge".to_string(), "approved".to_string())],
+            warnings: vec!["Sample warning".to_string()],
+        }
+    }
+
+    /// Issue type groups, features before improvements, bugs and tasks.
+    fn issues_by_type(&self) -> Vec<(String, Vec<&NoteIssue>)> {
+        let mut groups: BTreeMap<(u8, String), Vec<&NoteIssue>> = BTreeMap::new();
+        for issue in &self.issues {
+            let issue_type = if issue.issue_type.is_empty() { "Other".to_string() } else { issue.issue_type.clone() };
+            groups.entry((type_rank(&issue_type), issue_type)).or_default().push(issue);
+        }
+        groups.into_iter().map(|((_, issue_type), issues)| (issue_type, issues)).collect()
+    }
+}
+
+/// A standalone HTML page for sharing outside Confluence.
+pub fn markdown_html_document(title: &str, markdown: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>\
+         body{{font-family:sans-serif;max-width:960px;margin:2em auto;line-height:1.5}}\
+         table{{border-collapse:collapse}}th,td{{border:1px solid #ccc;padding:4px 8px;text-align:left}}\
+         </style></head><body>{}</body></html>\n",
+        title.replace('&', "&amp;").replace('<', "&lt;"),
+        markdown_to_html(markdown)
+    )
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseDocContext {
+    pub title: String,
+    pub version: String,
+    pub session_id: String,
+    pub environment: String,
+    pub status: String,
+    pub generated_at: String,
+    pub repos: Vec<String>,
+    /// Issues by type (features first), then by epic/parent, issues without one last.
+    pub issue_groups: Vec<IssueGroupContext>,
+    pub issues: Vec<IssueContext>,
+    pub prs: Vec<PrContext>,
+    pub config_changes: Vec<ConfigChangeContext>,
+    pub i18n_changes: Vec<I18nChangeContext>,
+    pub builds: Vec<BuildContext>,
+    pub approvals: Vec<ApprovalContext>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueGroupContext {
+    pub issue_type: String,
+    pub count: usize,
+    pub parents: Vec<ParentContext>,
+}
+
+/// `key` is empty for the group of issues without an epic/parent.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParentContext {
+    pub key: String,
+    pub summary: String,
+    pub issues: Vec<IssueContext>,
+}
+
+/// `link` is the Markdown link to the issue (the bare key when the Jira site is unknown).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueContext {
+    pub key: String,
+    pub summary: String,
+    pub issue_type: String,
+    pub status: String,
+    pub url: String,
+    pub link: String,
+    pub parent_key: String,
+    pub parent_summary: String,
+    pub prs: Vec<PrContext>,
+}
+
+/// `name` is the repo name without owner; `link` renders as `[name#number](url)`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrContext {
+    pub repo: String,
+    pub name: String,
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    pub link: String,
+    pub author: String,
+    pub merged_at: String,
+    pub merged_date: String,
+    pub issue_keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChangeContext {
+    pub repo: String,
+    pub file: String,
+    /// `key (added|removed|changed)` entries, without values.
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct I18nChangeContext {
+    pub repo: String,
+    pub file: String,
+    pub key: String,
+    pub change: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildContext {
+    pub repo: String,
+    pub status: String,
+    pub command: String,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalContext {
+    pub step_id: String,
+    pub decision: String,
+}
+
+type ParentGroup<'a> = (Option<(String, String)>, Vec<&'a NoteIssue>);
+
+/// Parents in first-seen order, issues without a parent last.
+fn group_by_parent<'a>(issues: &[&'a NoteIssue]) -> Vec<ParentGroup<'a>> {
+    let mut groups: Vec<ParentGroup<'a>> = Vec::new();
+    for issue in issues {
+        match groups.iter_mut().find(|(parent, _)| parent == &issue.parent) {
+            Some((_, members)) => members.push(issue),
+            None => groups.push((issue.parent.clone(), vec![issue])),
+        }
+    }
+    groups.sort_by_key(|(parent, _)| parent.is_none());
+    groups
+}
+
+fn type_rank(issue_type: &str) -> u8 {
+    let lower = issue_type.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| lower.contains(word));
+    if has(&["feature", "story", "epic", "需求", "故事"]) {
+        0
+    } else if has(&["improvement", "改进", "优化"]) {
+        1
+    } else if has(&["bug", "defect", "缺陷", "故障"]) {
+        2
+    } else if has(&["task", "任务"]) {
+        3
+    } else {
+        4
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::confluence::markdown_to_storage;
+    use crate::commands::release_session::ReleaseStep;
+    use serde_json::json;
+
+    #[test]
+    fn extracts_issue_keys_from_titles_and_branches() {
+        assert_eq!(extract_issue_keys("CRM-12 fix login (CRM-7, ab-3)"), vec!["CRM-12", "CRM-7"]);
+        assert_eq!(extract_issue_keys("feature/CRMCN-4521-export"), vec!["CRMCN-4521"]);
+        assert_eq!(extract_issue_keys("UTF-8 in xCRM-1 and A-1"), vec!["UTF-8"]);
+    }
+
+    #[test]
+    fn groups_issues_by_type_and_parent_and_joins_prs() {
+        let mut session = ReleaseSession::new(
+            "release-session-1".to_string(),
+            "v3.8.2".to_string(),
+            "prod".to_string(),
+            "1".to_string(),
+        );
+        session.repos = vec![json!({ "key": "web", "repo": "acme/web", "path": "/tmp/web" })];
+        session.steps.insert(
+            "configChanges".to_string(),
+            ReleaseStep {
+                result: json!({ "changes": [{ "repoKey": "web", "files": [".env.production"],
+                    "diffs": [{ "file": ".env.production", "changes": [{ "key": "API_URL", "change": "changed", "masked": false }] }] }] }),
+                ..ReleaseStep::default()
+            },
+        );
+        session.steps.insert(
+            "buildVerification".to_string(),
+            ReleaseStep {
+                result: json!({ "results": [{ "repoKey": "web", "ok": true, "status": "succeeded", "elapsedMs": 83000,
+                    "buildCommand": { "program": "pnpm", "args": ["run", "build"], "command": "pnpm run build",
+                        "tool": "pnpm", "script": "build", "source": "lockfile" } }] }),
+                ..ReleaseStep::default()
+            },
+        );
+
+        let mut notes = ReleaseNotes::from_session(&session, "2");
+        notes.issues = [
+            json!({ "key": "CRM-2", "fields": { "summary": "Login crash", "issuetype": { "name": "Bug" }, "status": { "name": "Done" } } }),
+            json!({ "key": "CRM-1", "fields": { "summary": "Export | CSV", "issuetype": { "name": "Story" }, "status": { "name": "Done" },
+                "parent": { "key": "CRM-100", "fields": { "summary": "Reporting" } } } }),
+        ]
+        .iter()
+        .filter_map(|issue| issue_from_jira(issue, "acme.atlassian.net"))
+        .collect();
+        notes.prs = [
+            json!({ "number": 12, "title": "CRM-1 export", "html_url": "https://github.com/acme/web/pull/12",
+                "user": { "login": "alice" }, "merged_at": "2026-10-01T10:00:00Z", "head": { "ref": "feature/CRM-1" } }),
+            json!({ "number": 13, "title": "closed without merge", "merged_at": null }),
+        ]
+        .iter()
+        .filter_map(|pr| pr_from_github("acme/web", pr))
+        .collect();
+
+        let markdown = notes.render_markdown(&ReleaseTemplate::builtin()).expect("builtin template renders");
+        let story = markdown.find("### Story (1)").expect("story group");
+        let bug = markdown.find("### Bug (1)").expect("bug group");
+        assert!(story < bug);
+        assert!(markdown.contains("**CRM-100 Reporting**"));
+        assert!(markdown.contains(
+            "- [CRM-1](https://acme.atlassian.net/browse/CRM-1) Export | CSV (Done) — [web#12](https://github.com/acme/web/pull/12) @alice"
+        ));
+        assert!(markdown.contains("| acme/web | [web#12](https://github.com/acme/web/pull/12) | CRM-1 export | @alice | 2026-10-01 | CRM-1 |"));
+        assert!(markdown.contains("| web | succeeded | `pnpm run build` | 1m 23s |"));
+        assert_eq!(notes.prs.len(), 1);
+
+        assert!(markdown.contains("## Approvals\n\nNo approvals recorded.\n"));
+        assert!(!markdown.contains("\n\n\n"));
+
+        let storage = markdown_to_storage(&markdown);
+        assert!(storage.contains("<td>web</td><td><code>.env.production</code></td><td>API_URL (changed)</td>"));
+        let html = markdown_html_document(&notes.title(), &markdown);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h3>Story (1)</h3>"));
+    }
+
+    #[test]
+    fn builtin_template_validates_against_the_sample() {
+        assert!(ReleaseTemplate::builtin().validate(&ReleaseNotes::sample().context()).is_empty());
+    }
+}
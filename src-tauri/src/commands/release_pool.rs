@@ -0,0 +1,128 @@
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+use tauri::Emitter;
+
+pub const DEFAULT_RELEASE_PARALLELISM: usize = 4;
+pub const MAX_RELEASE_PARALLELISM: usize = 16;
+
+/// Run `work` over `items` on at most `parallelism` threads and return the outputs in input order.
+pub fn run_bounded<T, R, F>(items: Vec<T>, parallelism: usize, work: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let total = items.len();
+    let workers = parallelism.clamp(1, MAX_RELEASE_PARALLELISM).min(total.max(1));
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let outputs = Mutex::new((0..total).map(|_| None).collect::<Vec<Option<R>>>());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap_or_else(PoisonError::into_inner).next();
+                let Some((index, item)) = next else { break };
+                let output = work(item);
+                outputs.lock().unwrap_or_else(PoisonError::into_inner)[index] = Some(output);
+            });
+        }
+    });
+
+    outputs
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner)
+        .into_iter()
+        .map(|output| output.expect("every queued item is processed before the scope ends"))
+        .collect()
+}
+
+/// Per-repo progress for one release step, emitted as `release-progress-{sessionId}`.
+///
+/// Without an app handle (tests, internal callers) progress is only counted.
+pub struct ReleaseProgress {
+    app: Option<tauri::AppHandle>,
+    session_id: String,
+    step_id: String,
+    total: usize,
+    completed: AtomicUsize,
+}
+
+impl ReleaseProgress {
+    pub fn new(app: Option<tauri::AppHandle>, session_id: &str, step_id: &str, total: usize) -> Self {
+        Self {
+            app,
+            session_id: session_id.to_string(),
+            step_id: step_id.to_string(),
+            total,
+            completed: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn started(&self, repo_key: &str) {
+        self.emit(repo_key, "running", self.completed.load(Ordering::SeqCst), Value::Null);
+    }
+
+    /// Attach step-specific detail (such as a build id) to a repo that is still running.
+    pub fn update(&self, repo_key: &str, detail: Value) {
+        self.emit(repo_key, "running", self.completed.load(Ordering::SeqCst), detail);
+    }
+
+    /// Report a finished repo; `result` is the same per-repo entry that ends up in `results`.
+    pub fn finished(&self, repo_key: &str, result: &Value) {
+        let completed = self.completed.fetch_add(1, Ordering::SeqCst) + 1;
+        let ok = result.get("ok").and_then(Value::as_bool).unwrap_or(false);
+        self.emit(repo_key, if ok { "done" } else { "failed" }, completed, result.clone());
+    }
+
+    fn emit(&self, repo_key: &str, status: &str, completed: usize, result: Value) {
+        let Some(app) = &self.app else { return };
+        let _ = app.emit(
+            &format!("release-progress-{}", self.session_id),
+            json!({
+                "sessionId": self.session_id,
+                "stepId": self.step_id,
+                "repoKey": repo_key,
+                "status": status,
+                "completed": completed,
+                "total": self.total,
+                "result": result
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn keeps_input_order_and_respects_the_bound() {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let outputs = run_bounded((0..12).collect(), 3, |item: u64| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20 - item));
+            running.fetch_sub(1, Ordering::SeqCst);
+            item * 2
+        });
+
+        assert_eq!(outputs, (0..12).map(|item| item * 2).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert!(peak.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn counts_progress_without_an_app_handle() {
+        let progress = ReleaseProgress::new(None, "release-session-1", "buildVerification", 2);
+        run_bounded(vec!["web", "admin"], 2, |key| {
+            progress.started(key);
+            progress.finished(key, &json!({ "repoKey": key, "ok": true }));
+        });
+        assert_eq!(progress.completed.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::confluence::ConfluenceTarget;
+use super::i18n_diff::normalize_locale;
+use super::release_build::{BuildOverride, BuildTimeouts};
+use super::release_pool::{DEFAULT_RELEASE_PARALLELISM, MAX_RELEASE_PARALLELISM};
+use super::release_promotion::{default_environments, validate_environments, PromotionEnvironment};
+use super::release_store::write_atomic;
+use super::release_template::{validate_template_name, BUILTIN_TEMPLATE_NAME};
+
+pub const RELEASE_PROFILE_FILE: &str = "release-profile.json";
+
+/// Per-workspace branch and tag conventions, read from `<workspace>/.flow-desk/release-profile.json`.
+///
+/// Templates use `{version}`, which expands to the release version without a leading `v`.
+/// A workspace without a profile gets the historical `latest` / `release/v{version}` layout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReleaseProfile {
+    pub remote: String,
+    pub stable_branch: String,
+    pub release_branch_template: String,
+    pub tag_template: String,
+    /// How many repos merge, build and tag steps work on at once.
+    pub parallelism: usize,
+    /// Wall-clock limit for one repo build in seconds; `0` disables it.
+    pub build_timeout_secs: u64,
+    /// A build with no output for this many seconds is treated as hung; `0` disables it.
+    pub build_idle_timeout_secs: u64,
+    /// Build command settings by repo key, for repos detection gets wrong.
+    pub build_overrides: BTreeMap<String, BuildOverride>,
+    /// Where `release_publish_confluence_doc` puts the release page; unset means publishing is off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confluence: Option<ConfluenceTarget>,
+    /// Release document template in `.flow-desk/templates/`; empty uses the built-in layout.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub doc_template: String,
+    /// Locale new message keys are written in first; i18n coverage compares the others to it.
+    pub source_locale: String,
+    /// Ordered promotion path; `release_promote` moves a release through it one step at a time.
+    pub environments: Vec<PromotionEnvironment>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for ReleaseProfile {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+            stable_branch: "latest".to_string(),
+            release_branch_template: "release/v{version}".to_string(),
+            tag_template: "release/v{version}".to_string(),
+            parallelism: DEFAULT_RELEASE_PARALLELISM,
+            build_timeout_secs: 30 * 60,
+            build_idle_timeout_secs: 10 * 60,
+            build_overrides: BTreeMap::new(),
+            confluence: None,
+            doc_template: String::new(),
+            source_locale: "en".to_string(),
+            environments: default_environments(),
+            extra: Map::new(),
+        }
+    }
+}
+
+impl ReleaseProfile {
+    pub fn path(workspace: &Path) -> PathBuf {
+        workspace.join(".flow-desk").join(RELEASE_PROFILE_FILE)
+    }
+
+    /// Load the profile for `workspace_path`, falling back to the defaults when no workspace
+    /// is set or it has no profile file.
+    pub fn load(workspace_path: Option<&str>) -> Result<Self, String> {
+        let Some(workspace) = workspace_path.map(str::trim).filter(|path| !path.is_empty()) else {
+            return Ok(Self::default());
+        };
+        let path = Self::path(Path::new(workspace));
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let profile = serde_json::from_str::<Self>(&content)
+            .map_err(|e| format!("Invalid release profile {}: {}", path.display(), e))?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    pub fn save(&self, workspace: &Path) -> Result<PathBuf, String> {
+        self.validate()?;
+        let path = Self::path(workspace);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize release profile: {}", e))?;
+        write_atomic(&path, &content)?;
+        Ok(path)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_valid_ref_name(&self.remote) || self.remote.contains('/') {
+            return Err(format!("Invalid remote name in release profile: {:?}", self.remote));
+        }
+        if !is_valid_ref_name(&self.stable_branch) {
+            return Err(format!("Invalid stable branch in release profile: {:?}", self.stable_branch));
+        }
+        if !(1..=MAX_RELEASE_PARALLELISM).contains(&self.parallelism) {
+            return Err(format!(
+                "Release profile parallelism must be between 1 and {}: {}",
+                MAX_RELEASE_PARALLELISM, self.parallelism
+            ));
+        }
+        for (field, template) in [
+            ("releaseBranchTemplate", &self.release_branch_template),
+            ("tagTemplate", &self.tag_template),
+        ] {
+            if !template.contains("{version}") {
+                return Err(format!("Release profile {} must contain {{version}}: {:?}", field, template));
+            }
+            if !is_valid_ref_name(&render(template, "1.0.0")) {
+                return Err(format!("Release profile {} does not produce a valid ref: {:?}", field, template));
+            }
+        }
+        if !self.doc_template.is_empty() && self.doc_template != BUILTIN_TEMPLATE_NAME {
+            validate_template_name(&self.doc_template)?;
+        }
+        if normalize_locale(&self.source_locale).as_deref() != Some(self.source_locale.as_str()) {
+            return Err(format!(
+                "Release profile sourceLocale must be a locale code such as en or zh-CN: {:?}",
+                self.source_locale
+            ));
+        }
+        validate_environments(&self.environments)?;
+        if let Some(confluence) = &self.confluence {
+            let base_url = confluence.base_url.trim();
+            if !base_url.starts_with("https://") && !base_url.starts_with("http://") {
+                return Err(format!("Release profile confluence.baseUrl must be an http(s) URL: {:?}", base_url));
+            }
+            if confluence.space_key.trim().is_empty() {
+                return Err("Release profile confluence.spaceKey is required".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn release_branch(&self, version: &str) -> String {
+        render(&self.release_branch_template, version)
+    }
+
+    pub fn tag_name(&self, version: &str) -> String {
+        render(&self.tag_template, version)
+    }
+
+    pub fn build_timeouts(&self) -> BuildTimeouts {
+        BuildTimeouts::from_secs(self.build_timeout_secs, self.build_idle_timeout_secs)
+    }
+
+    pub fn remote_stable_ref(&self) -> String {
+        format!("{}/{}", self.remote, self.stable_branch)
+    }
+
+    pub fn remote_release_ref(&self, version: &str) -> String {
+        format!("{}/{}", self.remote, self.release_branch(version))
+    }
+
+    /// Concrete ref names for one version, as shown to the frontend.
+    pub fn resolve(&self, version: &str) -> Value {
+        json!({
+            "remote": self.remote,
+            "stableBranch": self.stable_branch,
+            "releaseBranch": self.release_branch(version),
+            "tagName": self.tag_name(version),
+            "remoteStableRef": self.remote_stable_ref(),
+            "remoteReleaseRef": self.remote_release_ref(version)
+        })
+    }
+}
+
+fn render(template: &str, version: &str) -> String {
+    template.replace("{version}", version.trim().trim_start_matches('v'))
+}
+
+/// Conservative subset of `git check-ref-format` so a bad profile fails before any git call.
+fn is_valid_ref_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(['-', '/', '.'])
+        && !name.ends_with(['/', '.'])
+        && !name.ends_with(".lock")
+        && !name.contains("..")
+        && !name.contains("//")
+        && !name.contains("@{")
+        && !name
+            .chars()
+            .any(|ch| ch.is_whitespace() || ch.is_control() || "~^:?*[\\".contains(ch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReleaseProfile;
+    use std::fs;
+
+    #[test]
+    fn loads_workspace_profile_and_resolves_refs() {
+        let workspace = std::env::temp_dir().join(format!("flow-desk-release-profile-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&workspace);
+        fs::create_dir_all(&workspace).expect("create workspace");
+        let workspace_path = workspace.to_str().unwrap();
+
+        let defaults = ReleaseProfile::load(Some(workspace_path)).expect("defaults");
+        assert_eq!(defaults.remote_release_ref("v3.8.2"), "origin/release/v3.8.2");
+        assert_eq!(defaults.tag_name("3.8.2"), "release/v3.8.2");
+
+        let profile = ReleaseProfile {
+            remote: "upstream".to_string(),
+            stable_branch: "main".to_string(),
+            release_branch_template: "rel-{version}".to_string(),
+            tag_template: "v{version}".to_string(),
+            ..ReleaseProfile::default()
+        };
+        profile.save(&workspace).expect("save profile");
+
+        let loaded = ReleaseProfile::load(Some(workspace_path)).expect("load profile");
+        assert_eq!(loaded, profile);
+        assert_eq!(loaded.remote_stable_ref(), "upstream/main");
+        assert_eq!(loaded.remote_release_ref("v3.8.2"), "upstream/rel-3.8.2");
+        assert_eq!(loaded.resolve("3.8.2")["tagName"], "v3.8.2");
+    }
+
+    #[test]
+    fn rejects_templates_without_version_or_with_invalid_refs() {
+        let missing_version = ReleaseProfile {
+            release_branch_template: "release".to_string(),
+            ..ReleaseProfile::default()
+        };
+        assert!(missing_version.validate().is_err());
+
+        let invalid_ref = ReleaseProfile {
+            tag_template: "release v{version}".to_string(),
+            ..ReleaseProfile::default()
+        };
+        assert!(invalid_ref.validate().is_err());
+
+        let remote_with_slash = ReleaseProfile {
+            remote: "origin/main".to_string(),
+            ..ReleaseProfile::default()
+        };
+        assert!(remote_with_slash.validate().is_err());
+
+        let no_workers = ReleaseProfile {
+            parallelism: 0,
+            ..ReleaseProfile::default()
+        };
+        assert!(no_workers.validate().is_err());
+    }
+}
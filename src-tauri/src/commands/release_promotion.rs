@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One stage of the promotion path. A release reaches a stage only after the stage before it
+/// ran every one of its `checks` on the same SHAs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PromotionEnvironment {
+    pub name: String,
+    /// Checks that must pass here before the release moves to the next environment.
+    pub checks: Vec<String>,
+    /// Promoting into this environment consumes an approval for `promote-<name>`.
+    pub requires_approval: bool,
+}
+
+pub fn promotion_step_id(environment: &str) -> String {
+    format!("promote-{}", environment)
+}
+
+/// qa → uat → prod, with prod behind an approval.
+pub fn default_environments() -> Vec<PromotionEnvironment> {
+    let environment = |name: &str, checks: &[&str], requires_approval: bool| PromotionEnvironment {
+        name: name.to_string(),
+        checks: checks.iter().map(|check| check.to_string()).collect(),
+        requires_approval,
+    };
+    vec![
+        environment("qa", &["smoke"], false),
+        environment("uat", &["acceptance"], false),
+        environment("prod", &[], true),
+    ]
+}
+
+pub fn validate_environments(environments: &[PromotionEnvironment]) -> Result<(), String> {
+    if environments.is_empty() {
+        return Err("Release profile environments must list at least one environment".to_string());
+    }
+    let valid_name = |name: &str| {
+        !name.is_empty() && name.chars().all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_')
+    };
+    for (index, environment) in environments.iter().enumerate() {
+        if !valid_name(&environment.name) {
+            return Err(format!(
+                "Invalid release environment name {:?}: use lowercase letters, digits, '-' or '_'",
+                environment.name
+            ));
+        }
+        if environments[..index].iter().any(|other| other.name == environment.name) {
+            return Err(format!("Release environment {} is listed twice", environment.name));
+        }
+        if environment.checks.iter().any(|check| check.trim().is_empty()) {
+            return Err(format!("Release environment {} has an empty check name", environment.name));
+        }
+    }
+    Ok(())
+}
+
+/// What one repo had deployed: the commit and, when the release was tagged, the tag on it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeployedRef {
+    pub repo_key: String,
+    pub sha: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EnvironmentCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    pub recorded_by: String,
+    pub recorded_at: String,
+}
+
+/// One promotion into an environment. Checks are recorded against the deployment, so a
+/// redeploy with new SHAs starts them over.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Deployment {
+    pub deployment_id: String,
+    pub environment: String,
+    pub refs: Vec<DeployedRef>,
+    pub checks: Vec<EnvironmentCheck>,
+    pub promoted_by: String,
+    pub promoted_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_id: Option<String>,
+}
+
+impl Deployment {
+    /// Latest result of each check, by name.
+    pub fn check_results(&self) -> BTreeMap<&str, &EnvironmentCheck> {
+        self.checks.iter().map(|check| (check.name.as_str(), check)).collect()
+    }
+
+    /// Checks of `environment` that have not passed yet on this deployment.
+    pub fn unpassed_checks(&self, environment: &PromotionEnvironment) -> Vec<String> {
+        let results = self.check_results();
+        environment
+            .checks
+            .iter()
+            .filter(|name| !results.get(name.as_str()).is_some_and(|check| check.passed))
+            .cloned()
+            .collect()
+    }
+}
+
+pub fn latest_deployment<'a>(deployments: &'a [Deployment], environment: &str) -> Option<&'a Deployment> {
+    deployments.iter().rev().find(|deployment| deployment.environment == environment)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "reason")]
+pub enum PromotionRefusal {
+    UnknownEnvironment { environment: String },
+    NothingToDeploy,
+    PreviousNotDeployed { previous: String },
+    PreviousNotVerified { previous: String, checks: Vec<String> },
+    ShaMismatch { previous: String, repos: Vec<String> },
+}
+
+impl PromotionRefusal {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::UnknownEnvironment { environment } => format!("发布配置中没有环境 {}。", environment),
+            Self::NothingToDeploy => "没有可部署的仓库 SHA，请先确认 release 分支或 Tag。".to_string(),
+            Self::PreviousNotDeployed { previous } => format!("尚未晋级到上一环境 {}。", previous),
+            Self::PreviousNotVerified { previous, checks } => {
+                format!("上一环境 {} 的检查尚未通过：{}。", previous, checks.join("、"))
+            }
+            Self::ShaMismatch { previous, repos } => format!(
+                "以下仓库的 SHA 与 {} 中验证通过的版本不一致：{}。",
+                previous,
+                repos.join("、")
+            ),
+        }
+    }
+}
+
+/// Whether `refs` may be promoted into `target`: the first environment is always open; any
+/// later one needs the previous environment's latest deployment to carry exactly these SHAs
+/// and to have passed all of its checks.
+pub fn promotion_gate(
+    environments: &[PromotionEnvironment],
+    deployments: &[Deployment],
+    target: &str,
+    refs: &[DeployedRef],
+) -> Result<(), PromotionRefusal> {
+    let index = environments
+        .iter()
+        .position(|environment| environment.name == target)
+        .ok_or_else(|| PromotionRefusal::UnknownEnvironment { environment: target.to_string() })?;
+    if refs.is_empty() {
+        return Err(PromotionRefusal::NothingToDeploy);
+    }
+    let Some(previous) = index.checked_sub(1).map(|index| &environments[index]) else {
+        return Ok(());
+    };
+    let deployed = latest_deployment(deployments, &previous.name)
+        .ok_or_else(|| PromotionRefusal::PreviousNotDeployed { previous: previous.name.clone() })?;
+    let mismatched = refs
+        .iter()
+        .filter(|candidate| {
+            !deployed
+                .refs
+                .iter()
+                .any(|verified| verified.repo_key == candidate.repo_key && verified.sha == candidate.sha)
+        })
+        .map(|candidate| candidate.repo_key.clone())
+        .collect::<Vec<_>>();
+    if !mismatched.is_empty() {
+        return Err(PromotionRefusal::ShaMismatch { previous: previous.name.clone(), repos: mismatched });
+    }
+    let checks = deployed.unpassed_checks(previous);
+    if !checks.is_empty() {
+        return Err(PromotionRefusal::PreviousNotVerified { previous: previous.name.clone(), checks });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs(sha: &str) -> Vec<DeployedRef> {
+        vec![DeployedRef { repo_key: "web".to_string(), sha: sha.to_string(), tag: Some("release/v1.0.0".to_string()) }]
+    }
+
+    fn deployment(environment: &str, sha: &str, checks: &[(&str, bool)]) -> Deployment {
+        Deployment {
+            environment: environment.to_string(),
+            refs: refs(sha),
+            checks: checks
+                .iter()
+                .map(|(name, passed)| EnvironmentCheck { name: name.to_string(), passed: *passed, ..EnvironmentCheck::default() })
+                .collect(),
+            ..Deployment::default()
+        }
+    }
+
+    #[test]
+    fn promotes_only_what_passed_the_previous_environment() {
+        let environments = default_environments();
+        assert_eq!(promotion_gate(&environments, &[], "qa", &refs("aaa")), Ok(()));
+        assert_eq!(
+            promotion_gate(&environments, &[], "uat", &refs("aaa")),
+            Err(PromotionRefusal::PreviousNotDeployed { previous: "qa".to_string() })
+        );
+        assert!(matches!(
+            promotion_gate(&environments, &[], "staging", &refs("aaa")),
+            Err(PromotionRefusal::UnknownEnvironment { .. })
+        ));
+
+        let mut deployments = vec![deployment("qa", "aaa", &[("smoke", false)])];
+        assert_eq!(
+            promotion_gate(&environments, &deployments, "uat", &refs("aaa")),
+            Err(PromotionRefusal::PreviousNotVerified { previous: "qa".to_string(), checks: vec!["smoke".to_string()] })
+        );
+        deployments[0].checks.push(EnvironmentCheck { name: "smoke".to_string(), passed: true, ..EnvironmentCheck::default() });
+        assert_eq!(promotion_gate(&environments, &deployments, "uat", &refs("aaa")), Ok(()));
+        assert_eq!(
+            promotion_gate(&environments, &deployments, "uat", &refs("bbb")),
+            Err(PromotionRefusal::ShaMismatch { previous: "qa".to_string(), repos: vec!["web".to_string()] })
+        );
+
+        // A newer qa deployment without checks replaces the verified one.
+        deployments.push(deployment("qa", "bbb", &[]));
+        deployments.push(deployment("uat", "aaa", &[("acceptance", true)]));
+        assert!(promotion_gate(&environments, &deployments, "uat", &refs("bbb")).is_err());
+        assert_eq!(promotion_gate(&environments, &deployments, "prod", &refs("aaa")), Ok(()));
+        assert!(promotion_gate(&environments, &deployments, "prod", &refs("bbb")).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_environment_lists() {
+        assert!(validate_environments(&default_environments()).is_ok());
+        assert!(validate_environments(&[]).is_err());
+        let mut duplicated = default_environments();
+        duplicated[1].name = "qa".to_string();
+        assert!(validate_environments(&duplicated).is_err());
+        let mut invalid = default_environments();
+        invalid[0].name = "QA env".to_string();
+        assert!(validate_environments(&invalid).is_err());
+    }
+}
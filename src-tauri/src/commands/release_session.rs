@@ -0,0 +1,656 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::release_promotion::Deployment;
+
+/// Session-level lifecycle. The forward path is
+/// draft → checking → ready → merging → built → tagged → published,
+/// with `awaitingApproval` / `blocked` as hold states and `aborted` as an exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionStatus {
+    #[default]
+    Draft,
+    Checking,
+    AwaitingApproval,
+    Blocked,
+    Ready,
+    #[serde(alias = "executing")]
+    Merging,
+    Built,
+    Tagged,
+    #[serde(alias = "completed")]
+    Published,
+    #[serde(alias = "cancelled")]
+    Aborted,
+}
+
+impl SessionStatus {
+    fn is_terminal(self) -> bool {
+        matches!(self, Self::Published | Self::Aborted)
+    }
+
+    fn is_hold(self) -> bool {
+        matches!(self, Self::AwaitingApproval | Self::Blocked)
+    }
+
+    /// Milestones reachable in one move from `self` (hold states and `aborted` are handled separately).
+    fn next_milestones(self) -> &'static [SessionStatus] {
+        match self {
+            Self::Draft => &[Self::Checking, Self::Ready],
+            Self::Checking => &[Self::Ready],
+            Self::Ready => &[Self::Checking, Self::Merging],
+            Self::Merging => &[Self::Built],
+            Self::Built => &[Self::Tagged],
+            Self::Tagged => &[Self::Published],
+            _ => &[],
+        }
+    }
+
+    /// The step that must be `done` before the session may sit in this milestone.
+    fn required_step(self) -> Option<&'static str> {
+        match self {
+            Self::Ready => Some("readinessReport"),
+            Self::Merging => Some("mergeLatest"),
+            Self::Built => Some("buildVerification"),
+            Self::Tagged => Some("tagRelease"),
+            Self::Published => Some("confluencePublish"),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Checking => "checking",
+            Self::AwaitingApproval => "awaitingApproval",
+            Self::Blocked => "blocked",
+            Self::Ready => "ready",
+            Self::Merging => "merging",
+            Self::Built => "built",
+            Self::Tagged => "tagged",
+            Self::Published => "published",
+            Self::Aborted => "aborted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    #[default]
+    Pending,
+    WaitingInput,
+    AwaitingApproval,
+    Running,
+    Blocked,
+    Done,
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn is_settled(self) -> bool {
+        matches!(self, Self::Done | Self::Skipped)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalDecision {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ApprovalDecision {
+    pub fn parse(value: &str) -> Result<Self, ReleaseSessionError> {
+        match value {
+            "pending" => Ok(Self::Pending),
+            "approved" => Ok(Self::Approved),
+            "rejected" => Ok(Self::Rejected),
+            other => Err(ReleaseSessionError::InvalidSession(format!("Unknown approval decision: {}", other))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// How long an approved decision stays usable before it must be requested again.
+pub const APPROVAL_TTL_MS: u128 = 30 * 60 * 1000;
+
+/// Why a dangerous step was refused for lack of a usable approval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalRefusal {
+    Missing,
+    Pending,
+    Rejected,
+    Expired,
+    Consumed,
+}
+
+impl ApprovalRefusal {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Missing => "尚未申请审批",
+            Self::Pending => "审批仍在等待决定",
+            Self::Rejected => "审批已被拒绝",
+            Self::Expired => "审批已过期，请重新申请",
+            Self::Consumed => "审批已被使用，请重新申请",
+        }
+    }
+}
+
+/// Steps that may only start once every listed prerequisite is `done` or `skipped`.
+const STEP_PREREQUISITES: &[(&str, &[&str])] = &[
+    ("applyConfigChanges", &["configChanges"]),
+    ("i18nArtifacts", &["i18nChanges"]),
+    ("mergeLatest", &["readinessReport"]),
+    ("buildVerification", &["mergeLatest"]),
+    ("pushLatest", &["buildVerification"]),
+    ("tagRelease", &["buildVerification"]),
+    ("pushTags", &["tagRelease", "pushLatest"]),
+    ("confluencePublish", &["confluenceDraft"]),
+];
+
+/// Steps a successful rollback returns to `pending`, in the order they were executed.
+pub const ROLLED_BACK_STEPS: [&str; 5] = ["mergeLatest", "buildVerification", "pushLatest", "tagRelease", "pushTags"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReleaseStep {
+    pub id: String,
+    pub label: String,
+    pub tool_name: String,
+    pub phase: String,
+    pub requires_approval: bool,
+    pub dangerous: bool,
+    pub status: StepStatus,
+    pub summary: String,
+    pub detail: String,
+    pub updated_at: String,
+    pub result: Value,
+    pub approval_id: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Approval {
+    pub approval_id: String,
+    pub session_id: String,
+    pub step_id: String,
+    pub action: String,
+    pub target: String,
+    pub summary: String,
+    pub requested_by: String,
+    pub approved_by: String,
+    pub decision: ApprovalDecision,
+    pub ts: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consumed_at: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Artifact {
+    pub step_id: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReleaseSession {
+    pub session_id: String,
+    pub version: String,
+    /// Environment the release was last promoted to (the requested one until then).
+    pub environment: String,
+    /// Workspace whose release profile resolves branch and tag names; fixed at creation.
+    pub workspace_path: String,
+    pub status: SessionStatus,
+    /// Store-managed counter used to reject updates computed from a stale copy.
+    pub revision: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_step_id: Option<String>,
+    pub steps: BTreeMap<String, ReleaseStep>,
+    pub approvals: Vec<Approval>,
+    pub artifacts: Vec<Artifact>,
+    pub repos: Vec<Value>,
+    pub blocked_steps: Vec<String>,
+    pub pending_approvals: Vec<Value>,
+    pub current_gate: Option<Value>,
+    /// Promotions along the profile's environment path, oldest first.
+    pub promotions: Vec<Deployment>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseSessionError {
+    InvalidSession(String),
+    SessionClosed { status: SessionStatus },
+    SessionMismatch { expected: String, actual: String },
+    StaleRevision { expected: u64, actual: u64 },
+    IllegalStatusTransition { from: SessionStatus, to: SessionStatus },
+    MilestoneNotReached { status: SessionStatus, step_id: String },
+    StepGateBlocked { step_id: String, requires: String, requires_status: StepStatus },
+}
+
+impl fmt::Display for ReleaseSessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSession(detail) => write!(f, "Invalid release session: {}", detail),
+            Self::SessionClosed { status } => write!(f, "Release session is {}", status.as_str()),
+            Self::SessionMismatch { expected, actual } => {
+                write!(f, "Release session id mismatch: expected {}, got {}", expected, actual)
+            }
+            Self::StaleRevision { expected, actual } => write!(
+                f,
+                "Release session revision is stale: expected {}, got {}",
+                expected,
+                actual
+            ),
+            Self::IllegalStatusTransition { from, to } => {
+                write!(f, "Illegal release status transition: {} -> {}", from.as_str(), to.as_str())
+            }
+            Self::MilestoneNotReached { status, step_id } => {
+                write!(f, "Release cannot enter {} before step {} is done", status.as_str(), step_id)
+            }
+            Self::StepGateBlocked { step_id, requires, requires_status } => write!(
+                f,
+                "Step {} cannot run while {} is {:?}",
+                step_id,
+                requires,
+                requires_status
+            ),
+        }
+    }
+}
+
+impl From<ReleaseSessionError> for String {
+    fn from(error: ReleaseSessionError) -> Self {
+        error.to_string()
+    }
+}
+
+impl ReleaseSession {
+    pub fn new(session_id: String, version: String, environment: String, now: String) -> Self {
+        Self {
+            session_id,
+            version,
+            environment,
+            created_at: now.clone(),
+            updated_at: now,
+            ..Self::default()
+        }
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, ReleaseSessionError> {
+        let session: Self = serde_json::from_value(value)
+            .map_err(|e| ReleaseSessionError::InvalidSession(e.to_string()))?;
+        if session.session_id.trim().is_empty() {
+            return Err(ReleaseSessionError::InvalidSession("sessionId is required".to_string()));
+        }
+        Ok(session)
+    }
+
+    pub fn to_value(&self) -> Result<Value, String> {
+        serde_json::to_value(self).map_err(|e| format!("Failed to serialize release session: {}", e))
+    }
+
+    pub fn step_status(&self, step_id: &str) -> StepStatus {
+        self.steps.get(step_id).map(|step| step.status).unwrap_or_default()
+    }
+
+    pub fn step_result(&self, step_id: &str) -> Value {
+        self.steps.get(step_id).map(|step| step.result.clone()).unwrap_or(Value::Null)
+    }
+
+    /// Highest milestone whose driving step has completed, used to resume after a hold state.
+    pub fn reached_milestone(&self) -> SessionStatus {
+        const ORDER: [SessionStatus; 5] = [
+            SessionStatus::Published,
+            SessionStatus::Tagged,
+            SessionStatus::Built,
+            SessionStatus::Merging,
+            SessionStatus::Ready,
+        ];
+        for milestone in ORDER {
+            if let Some(step_id) = milestone.required_step() {
+                if self.step_status(step_id) == StepStatus::Done {
+                    return milestone;
+                }
+            }
+        }
+        if self.steps.values().any(|step| step.status == StepStatus::Done) {
+            SessionStatus::Checking
+        } else {
+            SessionStatus::Draft
+        }
+    }
+
+    /// Reject starting `step_id` while any of its prerequisites is unfinished.
+    pub fn ensure_step_can_start(&self, step_id: &str) -> Result<(), ReleaseSessionError> {
+        if self.status == SessionStatus::Aborted {
+            return Err(ReleaseSessionError::SessionClosed { status: self.status });
+        }
+        let prerequisites = STEP_PREREQUISITES
+            .iter()
+            .find(|(id, _)| *id == step_id)
+            .map(|(_, requires)| *requires)
+            .unwrap_or(&[]);
+        for requires in prerequisites {
+            let requires_status = self.step_status(requires);
+            if !requires_status.is_settled() {
+                return Err(ReleaseSessionError::StepGateBlocked {
+                    step_id: step_id.to_string(),
+                    requires: requires.to_string(),
+                    requires_status,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the latest approval for `step_id` if it is approved, unexpired and unused.
+    /// The approval is marked consumed so it cannot authorize a second run.
+    pub fn consume_approval(&mut self, step_id: &str, now: &str) -> Result<Approval, ApprovalRefusal> {
+        let now_ms = now.parse::<u128>().unwrap_or(0);
+        let approval = self
+            .approvals
+            .iter_mut()
+            .rev()
+            .find(|approval| approval.step_id == step_id)
+            .ok_or(ApprovalRefusal::Missing)?;
+        match approval.decision {
+            ApprovalDecision::Pending => return Err(ApprovalRefusal::Pending),
+            ApprovalDecision::Rejected => return Err(ApprovalRefusal::Rejected),
+            ApprovalDecision::Approved => {}
+        }
+        if approval.consumed_at.is_some() {
+            return Err(ApprovalRefusal::Consumed);
+        }
+        let expires_at = approval
+            .expires_at
+            .as_deref()
+            .and_then(|value| value.parse::<u128>().ok())
+            .unwrap_or(0);
+        if expires_at <= now_ms {
+            return Err(ApprovalRefusal::Expired);
+        }
+        approval.consumed_at = Some(now.to_string());
+        Ok(approval.clone())
+    }
+
+    /// Hand back approval `approval_id` after its operation failed before changing anything.
+    pub fn refund_approval(&mut self, approval_id: &str) {
+        if let Some(approval) = self.approvals.iter_mut().find(|approval| approval.approval_id == approval_id) {
+            approval.consumed_at = None;
+        }
+    }
+
+    /// Validate that `next` is a legal successor of `self`.
+    pub fn validate_transition(&self, next: &ReleaseSession) -> Result<(), ReleaseSessionError> {
+        if self.session_id != next.session_id {
+            return Err(ReleaseSessionError::SessionMismatch {
+                expected: self.session_id.clone(),
+                actual: next.session_id.clone(),
+            });
+        }
+        if self.revision != next.revision {
+            return Err(ReleaseSessionError::StaleRevision {
+                expected: self.revision,
+                actual: next.revision,
+            });
+        }
+
+        for (step_id, step) in &next.steps {
+            let entering_active = matches!(step.status, StepStatus::Running | StepStatus::Done);
+            if entering_active && self.step_status(step_id) != step.status {
+                next.ensure_step_can_start(step_id)?;
+            }
+        }
+
+        let (from, to) = (self.status, next.status);
+        if from == to {
+            return Ok(());
+        }
+        let illegal = ReleaseSessionError::IllegalStatusTransition { from, to };
+        if from.is_terminal() {
+            return Err(illegal);
+        }
+        if to == SessionStatus::Aborted || to.is_hold() {
+            return Ok(());
+        }
+
+        let effective_from = if from.is_hold() { self.reached_milestone() } else { from };
+        if effective_from != to && !effective_from.next_milestones().contains(&to) {
+            return Err(illegal);
+        }
+        if let Some(step_id) = to.required_step() {
+            if next.step_status(step_id) != StepStatus::Done {
+                return Err(ReleaseSessionError::MilestoneNotReached {
+                    status: to,
+                    step_id: step_id.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the outcome of a backend-executed step and advance the milestone it drives.
+    pub fn record_step_outcome(&mut self, step_id: &str, ok: bool, summary: &str, result: Value, now: &str) {
+        let step = self.steps.entry(step_id.to_string()).or_insert_with(|| ReleaseStep {
+            id: step_id.to_string(),
+            ..ReleaseStep::default()
+        });
+        step.status = if ok { StepStatus::Done } else { StepStatus::Blocked };
+        step.summary = summary.to_string();
+        step.result = result;
+        step.updated_at = now.to_string();
+
+        self.blocked_steps.retain(|id| id != step_id);
+        if ok {
+            let milestone = self.reached_milestone();
+            if milestone.required_step() == Some(step_id) {
+                self.status = milestone;
+            }
+        } else {
+            self.blocked_steps.push(step_id.to_string());
+            self.status = SessionStatus::Blocked;
+        }
+        self.updated_at = now.to_string();
+    }
+
+    /// Record a rollback. On success the merge/build/tag steps go back to `pending` and the
+    /// session falls back to the milestone still standing, normally `ready`.
+    pub fn record_rollback(&mut self, ok: bool, summary: &str, result: Value, now: &str) {
+        self.record_step_outcome("rollback", ok, summary, result, now);
+        if !ok {
+            return;
+        }
+        for step_id in ROLLED_BACK_STEPS {
+            if let Some(step) = self.steps.get_mut(step_id) {
+                step.status = StepStatus::Pending;
+                step.summary = "已回滚".to_string();
+                step.detail.clear();
+                step.result = Value::Null;
+                step.updated_at = now.to_string();
+            }
+            self.blocked_steps.retain(|id| id != step_id);
+        }
+        self.status = self.reached_milestone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApprovalRefusal, ReleaseSession, ReleaseSessionError, SessionStatus, StepStatus};
+    use serde_json::json;
+
+    fn session_with(status: &str, steps: serde_json::Value) -> ReleaseSession {
+        ReleaseSession::from_value(json!({
+            "sessionId": "release-session-1",
+            "version": "3.8.2",
+            "environment": "production",
+            "status": status,
+            "steps": steps,
+            "currentGate": null
+        }))
+        .expect("valid session")
+    }
+
+    #[test]
+    fn round_trips_frontend_session_shape() {
+        let session = ReleaseSession::from_value(json!({
+            "sessionId": "release-session-1",
+            "status": "executing",
+            "steps": { "mergeLatest": { "id": "mergeLatest", "status": "awaiting_approval", "custom": 1 } },
+            "approvals": [{ "approvalId": "a-1", "stepId": "mergeLatest", "decision": "approved" }],
+            "currentStepId": "mergeLatest"
+        }))
+        .expect("parse session");
+
+        assert_eq!(session.status, SessionStatus::Merging);
+        assert_eq!(session.step_status("mergeLatest"), StepStatus::AwaitingApproval);
+        let value = session.to_value().expect("serialize");
+        assert_eq!(value["status"].as_str(), Some("merging"));
+        assert_eq!(value["steps"]["mergeLatest"]["custom"].as_u64(), Some(1));
+        assert_eq!(value["approvals"][0]["decision"].as_str(), Some("approved"));
+    }
+
+    #[test]
+    fn rejects_merge_while_readiness_report_is_blocked() {
+        let previous = session_with("blocked", json!({ "readinessReport": { "status": "blocked" } }));
+        let next = session_with("merging", json!({
+            "readinessReport": { "status": "blocked" },
+            "mergeLatest": { "status": "done" }
+        }));
+
+        let error = previous.validate_transition(&next).expect_err("merge must be gated");
+        assert!(matches!(
+            error,
+            ReleaseSessionError::StepGateBlocked { ref step_id, ref requires, requires_status: StepStatus::Blocked }
+                if step_id == "mergeLatest" && requires == "readinessReport"
+        ));
+        assert!(previous.ensure_step_can_start("mergeLatest").is_err());
+    }
+
+    #[test]
+    fn follows_forward_milestones_and_resumes_after_hold_states() {
+        let ready = session_with("ready", json!({ "readinessReport": { "status": "done" } }));
+        let awaiting = session_with("awaitingApproval", json!({
+            "readinessReport": { "status": "done" },
+            "mergeLatest": { "status": "awaiting_approval" }
+        }));
+        let merging = session_with("merging", json!({
+            "readinessReport": { "status": "done" },
+            "mergeLatest": { "status": "done" }
+        }));
+        let tagged = session_with("tagged", json!({
+            "readinessReport": { "status": "done" },
+            "mergeLatest": { "status": "done" },
+            "tagRelease": { "status": "done" }
+        }));
+
+        assert!(ready.validate_transition(&awaiting).is_ok());
+        assert!(awaiting.validate_transition(&merging).is_ok());
+        assert_eq!(
+            merging.validate_transition(&ready),
+            Err(ReleaseSessionError::IllegalStatusTransition {
+                from: SessionStatus::Merging,
+                to: SessionStatus::Ready
+            })
+        );
+        assert!(merging.validate_transition(&tagged).is_err());
+
+        let mut stale = merging.clone();
+        stale.revision = ready.revision + 1;
+        assert_eq!(
+            ready.validate_transition(&stale),
+            Err(ReleaseSessionError::StaleRevision { expected: 0, actual: 1 })
+        );
+    }
+
+    #[test]
+    fn records_backend_step_outcomes() {
+        let mut session = session_with("ready", json!({ "readinessReport": { "status": "done" } }));
+
+        session.record_step_outcome("mergeLatest", true, "merged", json!({ "ok": true }), "2");
+        assert_eq!(session.status, SessionStatus::Merging);
+
+        session.record_step_outcome("buildVerification", false, "build failed", json!({ "ok": false }), "3");
+        assert_eq!(session.status, SessionStatus::Blocked);
+        assert_eq!(session.blocked_steps, vec!["buildVerification".to_string()]);
+        assert_eq!(session.reached_milestone(), SessionStatus::Merging);
+    }
+
+    #[test]
+    fn rollback_returns_session_to_ready() {
+        let mut session = session_with("tagged", json!({
+            "readinessReport": { "status": "done" },
+            "mergeLatest": { "status": "done", "result": { "ok": true } },
+            "buildVerification": { "status": "blocked" },
+            "tagRelease": { "status": "done" }
+        }));
+        session.blocked_steps = vec!["buildVerification".to_string()];
+
+        session.record_rollback(false, "reset failed", json!({ "ok": false }), "5");
+        assert_eq!(session.status, SessionStatus::Blocked);
+        assert_eq!(session.step_status("mergeLatest"), StepStatus::Done);
+
+        session.record_rollback(true, "rolled back", json!({ "ok": true }), "6");
+        assert_eq!(session.status, SessionStatus::Ready);
+        assert_eq!(session.step_status("rollback"), StepStatus::Done);
+        assert_eq!(session.step_status("mergeLatest"), StepStatus::Pending);
+        assert_eq!(session.step_result("mergeLatest"), serde_json::Value::Null);
+        assert!(session.blocked_steps.is_empty());
+    }
+
+    #[test]
+    fn approvals_are_single_use_and_expire() {
+        let mut session = ReleaseSession::from_value(json!({
+            "sessionId": "release-session-1",
+            "approvals": [
+                { "approvalId": "a-1", "stepId": "mergeLatest", "decision": "approved", "expiresAt": "1000" },
+                { "approvalId": "a-2", "stepId": "tagRelease", "decision": "approved", "expiresAt": "1000" },
+                { "approvalId": "a-3", "stepId": "applyConfigChanges", "decision": "pending" }
+            ]
+        }))
+        .expect("valid session");
+
+        assert_eq!(session.consume_approval("mergeLatest", "500").map(|a| a.approval_id), Ok("a-1".to_string()));
+        assert_eq!(session.consume_approval("mergeLatest", "600").err(), Some(ApprovalRefusal::Consumed));
+        assert_eq!(session.consume_approval("tagRelease", "1000").err(), Some(ApprovalRefusal::Expired));
+        assert_eq!(session.consume_approval("applyConfigChanges", "500").err(), Some(ApprovalRefusal::Pending));
+        assert_eq!(session.consume_approval("confluencePublish", "500").err(), Some(ApprovalRefusal::Missing));
+        assert_eq!(session.approvals[0].consumed_at.as_deref(), Some("500"));
+
+        session.refund_approval("a-1");
+        assert_eq!(session.consume_approval("mergeLatest", "700").map(|a| a.approval_id), Ok("a-1".to_string()));
+    }
+}
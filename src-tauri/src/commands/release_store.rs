@@ -0,0 +1,527 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+/// Bumped whenever the on-disk layout of a session record changes.
+pub const RELEASE_STORE_SCHEMA_VERSION: u32 = 1;
+
+const STORE_META_FILE: &str = "store-meta.json";
+const STORE_LOCK_FILE: &str = ".store.lock";
+
+/// Serializes store writers inside this process; the lock file covers other processes.
+static STORE_MUTEX: OnceLock<Mutex<()>> = OnceLock::new();
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionRecord {
+    schema_version: u32,
+    session: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct StoreMeta {
+    schema_version: u32,
+    #[serde(default)]
+    legacy_store_migrated: bool,
+    #[serde(default)]
+    migrated_at: String,
+    #[serde(default)]
+    migrated_sessions: usize,
+    /// Why the legacy import stopped early; the store is marked migrated anyway so it is not retried.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    migration_error: String,
+}
+
+/// Sessions from `list_sessions`, plus records that could not be read as `{ path, error }`.
+pub struct SessionListing {
+    pub sessions: Vec<Value>,
+    pub unreadable: Vec<Value>,
+}
+
+/// Durable release session store rooted at `~/.flow-desk/releases/`.
+///
+/// Layout:
+/// - `sessions/<sessionId>.json` — one versioned record per session
+/// - `artifacts/<sessionId>/` — generated CSVs, drafts and published docs; `builds/` holds full build logs
+/// - `builds/` — logs of builds started outside a release session
+/// - `audit/<sessionId>.jsonl` — append-only, hash-chained action log (see `release_audit`)
+/// - `store-meta.json` — schema version and legacy migration marker
+///
+/// Every write runs under an in-process mutex plus an advisory lock on `.store.lock`,
+/// and bumps the session's `revision` so callers can detect stale copies.
+pub struct ReleaseStore {
+    root: PathBuf,
+}
+
+impl ReleaseStore {
+    /// Open the per-user store and import the legacy temp-dir store once. A failed import is
+    /// logged rather than returned, so a broken legacy file cannot lock every release command out.
+    pub fn open_default() -> Result<Self, String> {
+        let store = Self::open(super::agent::resolve_flowdesk_home_dir().join("releases"))?;
+        if let Err(error) = store.migrate_legacy_store(&legacy_store_path(), &legacy_artifact_root()) {
+            eprintln!("[release_store] legacy store migration failed: {}", error);
+        }
+        Ok(store)
+    }
+
+    pub fn open(root: PathBuf) -> Result<Self, String> {
+        for dir in [root.join("sessions"), root.join("artifacts"), root.join("audit")] {
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        Ok(Self { root })
+    }
+
+    fn session_path(&self, session_id: &str) -> Result<PathBuf, String> {
+        validate_session_id(session_id)?;
+        Ok(self.root.join("sessions").join(format!("{}.json", session_id)))
+    }
+
+    pub fn artifact_dir(&self, session_id: &str) -> Result<PathBuf, String> {
+        validate_session_id(session_id)?;
+        let dir = self.root.join("artifacts").join(session_id);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create artifact directory: {}", e))?;
+        Ok(dir)
+    }
+
+    /// Where the full log of `build_id` goes: the session's artifacts, or `builds/` without a session.
+    pub fn build_log_path(&self, session_id: Option<&str>, build_id: &str) -> Result<PathBuf, String> {
+        validate_session_id(build_id).map_err(|_| format!("Invalid build id: {}", build_id))?;
+        let dir = match session_id {
+            Some(session_id) => self.artifact_dir(session_id)?.join("builds"),
+            None => self.root.join("builds"),
+        };
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        Ok(dir.join(format!("{}.log", build_id)))
+    }
+
+    pub(crate) fn audit_log_path(&self, session_id: &str) -> Result<PathBuf, String> {
+        validate_session_id(session_id)?;
+        Ok(self.root.join("audit").join(format!("{}.jsonl", session_id)))
+    }
+
+    pub fn read_session(&self, session_id: &str) -> Result<Value, String> {
+        let path = self.session_path(session_id)?;
+        if !path.exists() {
+            return Err(format!("Release Session not found: {}", session_id));
+        }
+        read_session_record(&path)
+    }
+
+    /// Persist a brand-new session at revision 1.
+    pub fn create_session(&self, session: &Value) -> Result<Value, String> {
+        self.locked(|| {
+            let session_id = session_id_of(session)?;
+            if self.session_path(session_id)?.exists() {
+                return Err(format!("Release Session already exists: {}", session_id));
+            }
+            let mut created = session.clone();
+            created["revision"] = json!(1);
+            self.write_session(&created)?;
+            Ok(created)
+        })
+    }
+
+    /// Read-modify-write a session under the store lock. The closure sees the latest
+    /// persisted copy; the revision is bumped only when the session actually changed.
+    pub fn update_session<T>(
+        &self,
+        session_id: &str,
+        mutate: impl FnOnce(&mut Value) -> Result<T, String>,
+    ) -> Result<(Value, T), String> {
+        self.locked(|| {
+            let current = self.read_session(session_id)?;
+            let mut next = current.clone();
+            let output = mutate(&mut next)?;
+            if next == current {
+                return Ok((current, output));
+            }
+            if session_id_of(&next)? != session_id {
+                return Err(format!("Release session id cannot change: {}", session_id));
+            }
+            next["revision"] = json!(revision_of(&current) + 1);
+            self.write_session(&next)?;
+            Ok((next, output))
+        })
+    }
+
+    pub(crate) fn locked<T>(&self, work: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+        let _guard = STORE_MUTEX
+            .get_or_init(|| Mutex::new(()))
+            .lock()
+            .map_err(|_| "Failed to lock release store".to_string())?;
+        let lock_path = self.root.join(STORE_LOCK_FILE);
+        let lock_file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| format!("Failed to open {}: {}", lock_path.display(), e))?;
+        lock_file
+            .lock()
+            .map_err(|e| format!("Failed to lock {}: {}", lock_path.display(), e))?;
+        let result = work();
+        let _ = lock_file.unlock();
+        result
+    }
+
+    fn write_session(&self, session: &Value) -> Result<(), String> {
+        let session_id = session_id_of(session)?;
+        let record = SessionRecord {
+            schema_version: RELEASE_STORE_SCHEMA_VERSION,
+            session: session.clone(),
+        };
+        let serialized = serde_json::to_string_pretty(&record)
+            .map_err(|e| format!("Failed to serialize release session: {}", e))?;
+        write_atomic(&self.session_path(session_id)?, &serialized)
+    }
+
+    /// All persisted sessions, oldest first. A corrupt record is reported instead of failing the list.
+    pub fn list_sessions(&self) -> Result<SessionListing, String> {
+        let dir = self.root.join("sessions");
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        let mut sessions = Vec::new();
+        let mut unreadable = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            match read_session_record(&path) {
+                Ok(session) => sessions.push(session),
+                Err(error) => unreadable.push(json!({ "path": path.display().to_string(), "error": error })),
+            }
+        }
+        sessions.sort_by_key(|session| {
+            session
+                .get("createdAt")
+                .and_then(Value::as_str)
+                .and_then(|value| value.parse::<u128>().ok())
+                .unwrap_or(0)
+        });
+        Ok(SessionListing { sessions, unreadable })
+    }
+
+    /// Import sessions from the pre-1.0 `flowdesk-release-store.json` in the OS temp dir.
+    /// Runs at most once per store; sessions already present are never overwritten. If the
+    /// import fails partway, the store is still marked migrated with the error recorded.
+    pub fn migrate_legacy_store(&self, legacy_path: &Path, legacy_artifacts: &Path) -> Result<usize, String> {
+        if read_store_meta(&self.root.join(STORE_META_FILE))?.legacy_store_migrated {
+            return Ok(0);
+        }
+        self.locked(|| self.migrate_legacy_store_locked(legacy_path, legacy_artifacts))
+    }
+
+    fn migrate_legacy_store_locked(&self, legacy_path: &Path, legacy_artifacts: &Path) -> Result<usize, String> {
+        let meta_path = self.root.join(STORE_META_FILE);
+        let mut meta = read_store_meta(&meta_path)?;
+        if meta.legacy_store_migrated {
+            return Ok(0);
+        }
+
+        let mut imported = 0;
+        let outcome = if legacy_path.exists() {
+            self.import_legacy_sessions(legacy_path, legacy_artifacts, &mut imported)
+        } else {
+            Ok(())
+        };
+
+        meta.schema_version = RELEASE_STORE_SCHEMA_VERSION;
+        meta.legacy_store_migrated = true;
+        meta.migrated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .to_string();
+        meta.migrated_sessions = imported;
+        meta.migration_error = outcome.as_ref().err().cloned().unwrap_or_default();
+        let serialized = serde_json::to_string_pretty(&meta)
+            .map_err(|e| format!("Failed to serialize release store meta: {}", e))?;
+        write_atomic(&meta_path, &serialized)?;
+        outcome.map(|_| imported)
+    }
+
+    fn import_legacy_sessions(&self, legacy_path: &Path, legacy_artifacts: &Path, imported: &mut usize) -> Result<(), String> {
+        let content = fs::read_to_string(legacy_path)
+            .map_err(|e| format!("Failed to read legacy release store: {}", e))?;
+        let legacy: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse legacy release store: {}", e))?;
+        let sessions = legacy.get("sessions").and_then(Value::as_array).cloned().unwrap_or_default();
+
+        for mut session in sessions {
+            let Some(session_id) = session.get("sessionId").and_then(Value::as_str).map(str::to_string) else {
+                continue;
+            };
+            if validate_session_id(&session_id).is_err() || self.session_path(&session_id)?.exists() {
+                continue;
+            }
+
+            let legacy_dir = legacy_artifacts.join(&session_id);
+            if legacy_dir.is_dir() {
+                let target_dir = self.artifact_dir(&session_id)?;
+                copy_artifact_files(&legacy_dir, &target_dir)?;
+                rewrite_artifact_paths(&mut session, &legacy_dir, &target_dir);
+            }
+
+            if session.get("revision").and_then(Value::as_u64).is_none() {
+                session["revision"] = json!(1);
+            }
+            self.write_session(&session)?;
+            *imported += 1;
+        }
+        Ok(())
+    }
+}
+
+fn legacy_store_path() -> PathBuf {
+    env::temp_dir().join("flowdesk-release-store.json")
+}
+
+fn legacy_artifact_root() -> PathBuf {
+    env::temp_dir().join("flowdesk-release-artifacts")
+}
+
+fn session_id_of(session: &Value) -> Result<&str, String> {
+    session
+        .get("sessionId")
+        .and_then(Value::as_str)
+        .ok_or_else(|| "session.sessionId is required".to_string())
+}
+
+fn revision_of(session: &Value) -> u64 {
+    session.get("revision").and_then(Value::as_u64).unwrap_or(0)
+}
+
+fn validate_session_id(session_id: &str) -> Result<(), String> {
+    let valid = !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.')
+        && !session_id.starts_with('.');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid release session id: {}", session_id))
+    }
+}
+
+fn read_session_record(path: &Path) -> Result<Value, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let record: SessionRecord = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    if record.schema_version > RELEASE_STORE_SCHEMA_VERSION {
+        return Err(format!(
+            "{} was written by a newer FlowDesk (schema v{}, supported v{})",
+            path.display(),
+            record.schema_version,
+            RELEASE_STORE_SCHEMA_VERSION
+        ));
+    }
+    Ok(record.session)
+}
+
+fn read_store_meta(path: &Path) -> Result<StoreMeta, String> {
+    if !path.exists() {
+        return Ok(StoreMeta::default());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Write to a sibling temp file and rename over the target so readers never see a torn file.
+pub(crate) fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid store path: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    fs::write(&temp_path, content)
+        .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+    fs::rename(&temp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("Failed to persist {}: {}", path.display(), e)
+    })
+}
+
+fn copy_artifact_files(source: &Path, target: &Path) -> Result<(), String> {
+    let entries = fs::read_dir(source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() {
+            let destination = target.join(entry.file_name());
+            if !destination.exists() {
+                fs::copy(&path, &destination)
+                    .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_artifact_paths(session: &mut Value, legacy_dir: &Path, target_dir: &Path) {
+    let legacy_prefix = legacy_dir.display().to_string();
+    let target_prefix = target_dir.display().to_string();
+    if let Some(artifacts) = session.get_mut("artifacts").and_then(Value::as_array_mut) {
+        for artifact in artifacts {
+            if let Some(path) = artifact.get("path").and_then(Value::as_str) {
+                if let Some(rest) = path.strip_prefix(&legacy_prefix) {
+                    artifact["path"] = Value::String(format!("{}{}", target_prefix, rest));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_store_meta, ReleaseStore, RELEASE_STORE_SCHEMA_VERSION, STORE_META_FILE};
+    use serde_json::json;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn create_temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir()
+            .join(format!("flow-desk-release-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("create temp root");
+        root
+    }
+
+    #[test]
+    fn saves_one_versioned_file_per_session() {
+        let root = create_temp_root("save");
+        let store = ReleaseStore::open(root.join("releases")).expect("open store");
+
+        store
+            .create_session(&json!({ "sessionId": "release-session-1", "status": "draft", "createdAt": "1" }))
+            .expect("create session");
+
+        let raw = fs::read_to_string(root.join("releases/sessions/release-session-1.json")).expect("read record");
+        let record: serde_json::Value = serde_json::from_str(&raw).expect("parse record");
+        assert_eq!(record["schemaVersion"].as_u64(), Some(RELEASE_STORE_SCHEMA_VERSION as u64));
+        assert_eq!(record["session"]["status"].as_str(), Some("draft"));
+        assert_eq!(record["session"]["revision"].as_u64(), Some(1));
+
+        let leftovers = fs::read_dir(root.join("releases/sessions"))
+            .expect("list sessions")
+            .filter(|entry| entry.as_ref().map(|e| e.file_name().to_string_lossy().ends_with(".tmp")).unwrap_or(false))
+            .count();
+        assert_eq!(leftovers, 0);
+        assert!(store.create_session(&json!({ "sessionId": "../escape" })).is_err());
+        assert!(store.create_session(&json!({ "sessionId": "release-session-1" })).is_err());
+    }
+
+    #[test]
+    fn migrates_legacy_temp_store_once() {
+        let root = create_temp_root("migrate");
+        let legacy_artifacts = root.join("legacy-artifacts");
+        fs::create_dir_all(legacy_artifacts.join("release-session-7")).expect("create legacy artifacts");
+        fs::write(legacy_artifacts.join("release-session-7/i18n-changes.csv"), "repo\n").expect("write artifact");
+        let legacy_store = root.join("flowdesk-release-store.json");
+        fs::write(
+            &legacy_store,
+            json!({
+                "sessions": [{
+                    "sessionId": "release-session-7",
+                    "createdAt": "7",
+                    "artifacts": [{
+                        "kind": "i18n-csv",
+                        "path": legacy_artifacts.join("release-session-7/i18n-changes.csv").display().to_string()
+                    }]
+                }]
+            })
+            .to_string(),
+        )
+        .expect("write legacy store");
+
+        let store = ReleaseStore::open(root.join("releases")).expect("open store");
+        assert_eq!(store.migrate_legacy_store(&legacy_store, &legacy_artifacts).expect("migrate"), 1);
+        assert_eq!(store.migrate_legacy_store(&legacy_store, &legacy_artifacts).expect("migrate again"), 0);
+
+        let session = store.read_session("release-session-7").expect("read migrated session");
+        let artifact_path = PathBuf::from(session["artifacts"][0]["path"].as_str().expect("artifact path"));
+        assert!(artifact_path.starts_with(root.join("releases/artifacts/release-session-7")));
+        assert!(artifact_path.exists());
+        assert_eq!(store.list_sessions().expect("list").sessions.len(), 1);
+
+        fs::write(root.join("releases/sessions/release-session-8.json"), "{\"schemaVersion\": 1, \"sess").expect("write corrupt record");
+        let listing = store.list_sessions().expect("list with a corrupt record");
+        assert_eq!((listing.sessions.len(), listing.unreadable.len()), (1, 1));
+    }
+
+    #[test]
+    fn marks_a_truncated_legacy_store_migrated_with_the_error() {
+        let root = create_temp_root("migrate-truncated");
+        let legacy_store = root.join("flowdesk-release-store.json");
+        fs::write(&legacy_store, "{\"sessions\": [{\"sessionId\": \"rel").expect("write legacy store");
+
+        let store = ReleaseStore::open(root.join("releases")).expect("open store");
+        assert!(store.migrate_legacy_store(&legacy_store, &root.join("legacy-artifacts")).is_err());
+        let meta = read_store_meta(&root.join("releases").join(STORE_META_FILE)).expect("read meta");
+        assert!(meta.legacy_store_migrated);
+        assert!(meta.migration_error.contains("Failed to parse legacy release store"));
+        assert_eq!(store.migrate_legacy_store(&legacy_store, &root.join("legacy-artifacts")), Ok(0));
+    }
+
+    #[test]
+    fn rejects_records_from_newer_schema() {
+        let root = create_temp_root("schema");
+        let store = ReleaseStore::open(root.join("releases")).expect("open store");
+        fs::write(
+            root.join("releases/sessions/release-session-9.json"),
+            json!({ "schemaVersion": RELEASE_STORE_SCHEMA_VERSION + 1, "session": { "sessionId": "release-session-9" } }).to_string(),
+        )
+        .expect("write future record");
+
+        let error = store.read_session("release-session-9").expect_err("newer schema should fail");
+        assert!(error.contains("newer FlowDesk"));
+    }
+
+    #[test]
+    fn serializes_concurrent_updates_and_bumps_revision() {
+        let root = create_temp_root("concurrent");
+        let releases = root.join("releases");
+        ReleaseStore::open(releases.clone())
+            .expect("open store")
+            .create_session(&json!({ "sessionId": "release-session-3", "counter": 0 }))
+            .expect("create session");
+
+        let workers = (0..8)
+            .map(|_| {
+                let releases = releases.clone();
+                std::thread::spawn(move || {
+                    let store = ReleaseStore::open(releases).expect("open store");
+                    for _ in 0..5 {
+                        store
+                            .update_session("release-session-3", |session| {
+                                let counter = session["counter"].as_u64().unwrap_or(0);
+                                session["counter"] = json!(counter + 1);
+                                Ok(())
+                            })
+                            .expect("update session");
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for worker in workers {
+            worker.join().expect("worker finished");
+        }
+
+        let store = ReleaseStore::open(releases).expect("open store");
+        let session = store.read_session("release-session-3").expect("read session");
+        assert_eq!(session["counter"].as_u64(), Some(40));
+        assert_eq!(session["revision"].as_u64(), Some(41));
+
+        let (unchanged, _) = store.update_session("release-session-3", |_| Ok(())).expect("no-op update");
+        assert_eq!(unchanged["revision"].as_u64(), Some(41));
+    }
+}
@@ -0,0 +1,243 @@
+use minijinja::{Environment, UndefinedBehavior};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::release_store::write_atomic;
+
+pub const BUILTIN_TEMPLATE_NAME: &str = "builtin";
+const BUILTIN_TEMPLATE: &str = include_str!("../../templates/release-notes.md.j2");
+const TEMPLATE_EXTENSION: &str = "md.j2";
+
+/// One problem found while compiling or rendering a template, as shown in the editor.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateError {
+    pub kind: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl TemplateError {
+    fn from_minijinja(error: &minijinja::Error) -> Self {
+        Self {
+            kind: format!("{:?}", error.kind()),
+            message: error.to_string(),
+            line: error.line(),
+            detail: error.detail().map(str::to_string),
+        }
+    }
+}
+
+/// A release document layout: Jinja-style syntax producing Markdown, rendered against
+/// [`super::release_notes::ReleaseDocContext`].
+///
+/// Workspace templates live in `<workspace>/.flow-desk/templates/<name>.md.j2`; the built-in
+/// layout is used when the profile names none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReleaseTemplate {
+    pub name: String,
+    pub source: String,
+    pub path: Option<PathBuf>,
+}
+
+impl ReleaseTemplate {
+    pub fn builtin() -> Self {
+        Self {
+            name: BUILTIN_TEMPLATE_NAME.to_string(),
+            source: BUILTIN_TEMPLATE.to_string(),
+            path: None,
+        }
+    }
+
+    pub fn inline(source: String) -> Self {
+        Self {
+            name: "preview".to_string(),
+            source,
+            path: None,
+        }
+    }
+
+    pub fn dir(workspace: &Path) -> PathBuf {
+        workspace.join(".flow-desk").join("templates")
+    }
+
+    pub fn path(workspace: &Path, name: &str) -> Result<PathBuf, String> {
+        validate_template_name(name)?;
+        Ok(Self::dir(workspace).join(format!("{}.{}", name, TEMPLATE_EXTENSION)))
+    }
+
+    /// Load `name` from the workspace; an empty name or `builtin` is the built-in layout.
+    pub fn load(workspace_path: Option<&str>, name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.is_empty() || name == BUILTIN_TEMPLATE_NAME {
+            return Ok(Self::builtin());
+        }
+        let workspace = workspace_path
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| format!("Release template {} needs a workspace", name))?;
+        let path = Self::path(Path::new(workspace), name)?;
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read release template {}: {}", path.display(), e))?;
+        Ok(Self {
+            name: name.to_string(),
+            source,
+            path: Some(path),
+        })
+    }
+
+    /// Template names available in the workspace, the built-in one first.
+    pub fn list(workspace_path: Option<&str>) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Some(workspace) = workspace_path.map(str::trim).filter(|path| !path.is_empty()) {
+            if let Ok(entries) = fs::read_dir(Self::dir(Path::new(workspace))) {
+                names = entries
+                    .flatten()
+                    .filter_map(|entry| {
+                        let file_name = entry.file_name().to_string_lossy().to_string();
+                        file_name
+                            .strip_suffix(&format!(".{}", TEMPLATE_EXTENSION))
+                            .filter(|name| validate_template_name(name).is_ok())
+                            .map(str::to_string)
+                    })
+                    .collect();
+            }
+        }
+        names.sort();
+        names.insert(0, BUILTIN_TEMPLATE_NAME.to_string());
+        names
+    }
+
+    pub fn save(&self, workspace: &Path) -> Result<PathBuf, String> {
+        let path = Self::path(workspace, &self.name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        write_atomic(&path, &self.source)?;
+        Ok(path)
+    }
+
+    pub fn render<C: Serialize>(&self, context: &C) -> Result<String, TemplateError> {
+        let mut env = template_environment();
+        env.add_template_owned(format!("{}.md", self.name), self.source.clone())
+            .map_err(|e| TemplateError::from_minijinja(&e))?;
+        let template = env
+            .get_template(&format!("{}.md", self.name))
+            .map_err(|e| TemplateError::from_minijinja(&e))?;
+        let mut rendered = template.render(context).map_err(|e| TemplateError::from_minijinja(&e))?;
+        if !rendered.ends_with('\n') {
+            rendered.push('\n');
+        }
+        Ok(rendered)
+    }
+
+    /// Compile the template and render it against `sample`, so syntax errors, unknown filters
+    /// and misspelled fields all surface before a release depends on the template.
+    pub fn validate<C: Serialize>(&self, sample: &C) -> Vec<TemplateError> {
+        match self.render(sample) {
+            Ok(_) => Vec::new(),
+            Err(error) => vec![error],
+        }
+    }
+}
+
+pub fn validate_template_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name != BUILTIN_TEMPLATE_NAME
+        && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid release template name {:?}: use letters, digits, '-' or '_' (and not {:?})",
+            name, BUILTIN_TEMPLATE_NAME
+        ))
+    }
+}
+
+/// Line-oriented settings (a tag alone on a line leaves no blank line) plus the Markdown
+/// filters the built-in layout uses. Printing an unknown field is an error, while
+/// `{% if field %}` on a missing one is still allowed.
+fn template_environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+    env.add_filter("inline", |text: String| inline_text(&text));
+    env.add_filter("cell", |text: String| {
+        let text = inline_text(&text);
+        if text.trim().is_empty() {
+            "-".to_string()
+        } else {
+            text.replace('|', "\\|")
+        }
+    });
+    env.add_filter("duration", |elapsed_ms: u64| {
+        let seconds = elapsed_ms / 1000;
+        match seconds {
+            0 => format!("{}ms", elapsed_ms),
+            1..=59 => format!("{}s", seconds),
+            _ => format!("{}m {}s", seconds / 60, seconds % 60),
+        }
+    });
+    env
+}
+
+/// One line of plain text that cannot open code spans in the Markdown source.
+fn inline_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").replace('`', "'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_syntax_and_unknown_field_errors_with_lines() {
+        let sample = json!({ "title": "Release 1.0.0", "issues": [] });
+
+        let ok = ReleaseTemplate::inline("# {{ title }}\n{% for issue in issues %}\n- {{ issue.key }}\n{% endfor %}".to_string());
+        assert!(ok.validate(&sample).is_empty());
+        assert_eq!(ok.render(&sample).unwrap(), "# Release 1.0.0\n");
+
+        let unclosed = ReleaseTemplate::inline("# {{ title }}\n{% for issue in issues %}\n- {{ issue.key }}\n".to_string());
+        let errors = unclosed.validate(&sample);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, "SyntaxError");
+
+        let misspelled = ReleaseTemplate::inline("# {{ title }}\n\n{{ verison }}".to_string());
+        let errors = misspelled.validate(&sample);
+        assert_eq!(errors[0].line, Some(3));
+
+        let unknown_filter = ReleaseTemplate::inline("{{ title|shout }}".to_string());
+        assert_eq!(unknown_filter.validate(&sample).len(), 1);
+    }
+
+    #[test]
+    fn saves_lists_and_loads_workspace_templates() {
+        let workspace = std::env::temp_dir().join(format!("flow-desk-release-template-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&workspace);
+        let workspace_path = workspace.to_str().unwrap();
+
+        assert_eq!(ReleaseTemplate::load(Some(workspace_path), "").unwrap(), ReleaseTemplate::builtin());
+        assert!(ReleaseTemplate::load(Some(workspace_path), "ops").is_err());
+        assert!(ReleaseTemplate::path(&workspace, "../escape").is_err());
+
+        let template = ReleaseTemplate {
+            name: "ops".to_string(),
+            source: "# {{ title }}".to_string(),
+            path: None,
+        };
+        let path = template.save(&workspace).expect("save template");
+        assert_eq!(ReleaseTemplate::list(Some(workspace_path)), vec!["builtin", "ops"]);
+        let loaded = ReleaseTemplate::load(Some(workspace_path), "ops").expect("load template");
+        assert_eq!(loaded.source, "# {{ title }}");
+        assert_eq!(loaded.path, Some(path));
+    }
+}
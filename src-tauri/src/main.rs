@@ -28,6 +28,7 @@ fn main() {
             commands::git::git_create_branch,
             commands::git::git_push_branch,
             commands::git::git_get_remote_info,
+            commands::git::git_list_remotes,
             commands::github::github_create_draft_pr,
             commands::github::github_list_open_prs,
             commands::github::github_get_current_user,
@@ -37,24 +38,40 @@ fn main() {
             commands::release::git_remote_branch_exists,
             commands::release::git_merge_conflict_check,
             commands::release::run_pnpm_build,
+            commands::release::release_cancel_build,
             commands::release::github_list_all_open_prs,
             commands::release::github_list_merged_prs,
             commands::release::release_session_create,
             commands::release::release_session_read,
             commands::release::release_session_update,
             commands::release::release_session_list,
+            commands::release::release_audit_log_read,
+            commands::release::release_profile_read,
+            commands::release::release_profile_save,
+            commands::release::release_template_read,
+            commands::release::release_template_validate,
+            commands::release::release_template_save,
             commands::release::release_approval_create,
             commands::release::release_approval_decide,
             commands::release::release_collect_config_changes,
             commands::release::release_preview_config_changes,
             commands::release::release_apply_config_changes,
+            commands::release::release_restore_config_backup,
             commands::release::release_collect_i18n_changes,
             commands::release::release_generate_i18n_artifacts,
+            commands::release::release_import_i18n_translations,
             commands::release::release_generate_readiness_report,
             commands::release::release_execute_merge,
             commands::release::release_execute_post_merge_build,
             commands::release::release_create_tag,
+            commands::release::release_push_latest,
+            commands::release::release_push_tags,
+            commands::release::release_rollback,
+            commands::release::release_promote,
+            commands::release::release_record_environment_check,
+            commands::release::release_promotion_status,
             commands::release::release_generate_confluence_draft,
+            commands::release::release_preview_release_doc,
             commands::release::release_publish_confluence_doc,
             commands::agent::agent_run_command,
             commands::agent::agent_read_file,
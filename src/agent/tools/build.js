@@ -1,6 +1,7 @@
 /**
  * Tool: run_build
- * Runs `pnpm run build` in each repo to verify build passes.
+ * Runs each repo's build (package manager and script detected from lockfiles/package.json,
+ * overridable per repo in the release profile); full logs land in the release store.
  */
 import { invoke } from '@tauri-apps/api/core'
 
@@ -8,7 +9,7 @@ export const schema = {
   type: 'function',
   function: {
     name: 'run_build',
-    description: '对指定仓库执行 pnpm run build 构建验证。任一仓库构建失败将阻塞发布。这是发布前的最后一道门禁。',
+    description: '对指定仓库执行构建验证：按锁文件与 package.json scripts 自动识别 pnpm/npm/yarn/bun 或 Cargo 构建命令，可在工作区发布配置中按仓库覆盖。任一仓库构建失败将阻塞发布。这是发布前的最后一道门禁。',
     parameters: {
       type: 'object',
       properties: {
@@ -29,18 +30,26 @@ export const schema = {
   }
 }
 
-export async function handler(args, _ctx) {
+export async function handler(args, ctx) {
   const repos = args.repos || []
   const results = []
 
   for (const repo of repos) {
     try {
-      const result = await invoke('run_pnpm_build', { projectPath: repo.path })
+      const result = await invoke('run_pnpm_build', {
+        projectPath: repo.path,
+        repoKey: repo.key || null,
+        workspacePath: ctx?.settings?.workspacePath || null
+      })
       results.push({
         repoKey: repo.key,
         ok: result.success,
+        status: result.status,
+        buildId: result.buildId,
+        command: result.command,
+        logPath: result.logPath,
         elapsedMs: result.elapsedMs,
-        stderr: result.stderr,
+        stderr: result.detail ? `${result.detail}\n${result.stderr}` : result.stderr,
         stdout: result.stdout
       })
     } catch (e) {
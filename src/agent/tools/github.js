@@ -3,6 +3,8 @@
  * Scans all configured repos for PRs related to the version's Jira issues.
  */
 import { invoke } from '@tauri-apps/api/core'
+import { resolveReleaseRefs } from './release-session.js'
+import { resolveGithubRemote } from '../../services/git-remotes.js'
 
 function extractIssueKeys(text) {
   return (text || '').match(/[A-Z][A-Z0-9]+-\d+/g) || []
@@ -33,19 +35,17 @@ export async function handler(args, ctx) {
   const version = args.version
   const brokerEntries = Object.entries(ctx.settings.brokerPaths || {})
   const allMatches = []
+  const { releaseBranch } = await resolveReleaseRefs(version, ctx)
 
   for (const [brokerKey, repoPath] of brokerEntries) {
     try {
-      const remoteInfo = await invoke('git_get_remote_info', { projectPath: repoPath }).catch(() => '')
-      if (!remoteInfo) continue
-      const parts = remoteInfo.replace(/\.git$/i, '').split('/').filter(Boolean)
-      if (parts.length < 2) continue
-      const owner = parts[0]
-      const repo = parts[1]
+      const remote = await resolveGithubRemote(repoPath).catch(() => null)
+      if (!remote) continue
+      const { owner, repo, apiBaseUrl } = remote
 
       const [openRes, mergedRes] = await Promise.all([
-        invoke('github_list_all_open_prs', { owner, repo, token: ctx.settings.githubToken }),
-        invoke('github_list_merged_prs', { owner, repo, baseBranch: `release/v${version}`, token: ctx.settings.githubToken })
+        invoke('github_list_all_open_prs', { owner, repo, token: ctx.settings.githubToken, apiBaseUrl }),
+        invoke('github_list_merged_prs', { owner, repo, baseBranch: releaseBranch, token: ctx.settings.githubToken, apiBaseUrl })
       ])
 
       const openPrs = openRes.status === 200 ? JSON.parse(openRes.body) : []
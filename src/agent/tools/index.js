@@ -59,6 +59,8 @@ import {
   getReleaseSessionHandler,
   listReleaseSessionsSchema,
   listReleaseSessionsHandler,
+  readReleaseAuditLogSchema,
+  readReleaseAuditLogHandler,
   requestStepApprovalSchema,
   requestStepApprovalHandler,
   recordStepDecisionSchema,
@@ -71,20 +73,42 @@ import {
   previewConfigChangesHandler,
   applyConfigChangesSchema,
   applyConfigChangesHandler,
+  restoreConfigBackupSchema,
+  restoreConfigBackupHandler,
   collectI18nChangesSchema,
   collectI18nChangesHandler,
   generateI18nArtifactsSchema,
   generateI18nArtifactsHandler,
+  importI18nTranslationsSchema,
+  importI18nTranslationsHandler,
   generateReleaseReadinessReportSchema,
   generateReleaseReadinessReportHandler,
   executeReleaseMergeSchema,
   executeReleaseMergeHandler,
   executePostMergeBuildSchema,
   executePostMergeBuildHandler,
+  cancelReleaseBuildSchema,
+  cancelReleaseBuildHandler,
   createReleaseTagSchema,
   createReleaseTagHandler,
+  pushReleaseLatestSchema,
+  pushReleaseLatestHandler,
+  pushReleaseTagsSchema,
+  pushReleaseTagsHandler,
+  previewReleaseRollbackSchema,
+  previewReleaseRollbackHandler,
+  rollbackReleaseSchema,
+  rollbackReleaseHandler,
+  getPromotionStatusSchema,
+  getPromotionStatusHandler,
+  promoteReleaseSchema,
+  promoteReleaseHandler,
+  recordEnvironmentCheckSchema,
+  recordEnvironmentCheckHandler,
   generateConfluenceDraftSchema,
   generateConfluenceDraftHandler,
+  previewReleaseDocSchema,
+  previewReleaseDocHandler,
   publishConfluenceReleaseDocSchema,
   publishConfluenceReleaseDocHandler
 } from './release-ops.js'
@@ -215,6 +239,11 @@ export const TOOL_CATALOG = [
     handler: listReleaseSessionsHandler,
     tags: ['release']
   },
+  {
+    schema: readReleaseAuditLogSchema,
+    handler: readReleaseAuditLogHandler,
+    tags: ['release']
+  },
   {
     schema: requestStepApprovalSchema,
     handler: requestStepApprovalHandler,
@@ -240,6 +269,11 @@ export const TOOL_CATALOG = [
     handler: applyConfigChangesHandler,
     tags: ['release', 'dangerous']
   },
+  {
+    schema: restoreConfigBackupSchema,
+    handler: restoreConfigBackupHandler,
+    tags: ['release', 'dangerous']
+  },
   {
     schema: collectI18nChangesSchema,
     handler: collectI18nChangesHandler,
@@ -250,6 +284,11 @@ export const TOOL_CATALOG = [
     handler: generateI18nArtifactsHandler,
     tags: ['release']
   },
+  {
+    schema: importI18nTranslationsSchema,
+    handler: importI18nTranslationsHandler,
+    tags: ['release']
+  },
   {
     schema: generateReleaseReadinessReportSchema,
     handler: generateReleaseReadinessReportHandler,
@@ -265,16 +304,61 @@ export const TOOL_CATALOG = [
     handler: executePostMergeBuildHandler,
     tags: ['release']
   },
+  {
+    schema: cancelReleaseBuildSchema,
+    handler: cancelReleaseBuildHandler,
+    tags: ['release']
+  },
   {
     schema: createReleaseTagSchema,
     handler: createReleaseTagHandler,
     tags: ['release', 'dangerous']
   },
+  {
+    schema: pushReleaseLatestSchema,
+    handler: pushReleaseLatestHandler,
+    tags: ['release', 'dangerous']
+  },
+  {
+    schema: pushReleaseTagsSchema,
+    handler: pushReleaseTagsHandler,
+    tags: ['release', 'dangerous']
+  },
+  {
+    schema: previewReleaseRollbackSchema,
+    handler: previewReleaseRollbackHandler,
+    tags: ['release']
+  },
+  {
+    schema: rollbackReleaseSchema,
+    handler: rollbackReleaseHandler,
+    tags: ['release', 'dangerous']
+  },
+  {
+    schema: getPromotionStatusSchema,
+    handler: getPromotionStatusHandler,
+    tags: ['release']
+  },
+  {
+    schema: promoteReleaseSchema,
+    handler: promoteReleaseHandler,
+    tags: ['release', 'dangerous']
+  },
+  {
+    schema: recordEnvironmentCheckSchema,
+    handler: recordEnvironmentCheckHandler,
+    tags: ['release']
+  },
   {
     schema: generateConfluenceDraftSchema,
     handler: generateConfluenceDraftHandler,
     tags: ['release']
   },
+  {
+    schema: previewReleaseDocSchema,
+    handler: previewReleaseDocHandler,
+    tags: ['release']
+  },
   {
     schema: publishConfluenceReleaseDocSchema,
     handler: publishConfluenceReleaseDocHandler,
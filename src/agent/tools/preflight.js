@@ -3,12 +3,13 @@
  * Runs pre-flight checks on release repos: branch existence, version match, merge conflicts.
  */
 import { invoke } from '@tauri-apps/api/core'
+import { resolveReleaseRefs } from './release-session.js'
 
 export const schema = {
   type: 'function',
   function: {
     name: 'run_preflight',
-    description: '对指定仓库执行发布预检：release 分支存在性、latest 分支存在性、package.json 版本号匹配、latest→release 合并冲突检查。任一失败将阻塞发布。',
+    description: '对指定仓库执行发布预检：release 分支存在性、稳定分支（默认 latest）存在性、package.json 版本号匹配、稳定分支→release 合并冲突检查；分支名取自工作区发布配置。任一失败将阻塞发布。',
     parameters: {
       type: 'object',
       properties: {
@@ -30,19 +31,19 @@ export const schema = {
   }
 }
 
-export async function handler(args, _ctx) {
+export async function handler(args, ctx) {
   const version = args.version
   const repos = args.repos || []
   const results = []
+  const { remote, stableBranch, releaseBranch } = await resolveReleaseRefs(version, ctx)
 
   for (const repo of repos) {
     const checks = {}
-    const releaseBranch = `release/v${version}`
 
     try {
       const [releaseExists, latestExists, pkgVersion, workingTree] = await Promise.all([
-        invoke('git_remote_branch_exists', { projectPath: repo.path, branchName: releaseBranch }),
-        invoke('git_remote_branch_exists', { projectPath: repo.path, branchName: 'latest' }),
+        invoke('git_remote_branch_exists', { projectPath: repo.path, branchName: releaseBranch, remote }),
+        invoke('git_remote_branch_exists', { projectPath: repo.path, branchName: stableBranch, remote }),
         invoke('read_package_version', { projectPath: repo.path }),
         invoke('git_check_working_tree', { projectPath: repo.path })
       ])
@@ -52,8 +53,8 @@ export async function handler(args, _ctx) {
         : { ok: false, detail: `分支 ${releaseBranch} 不存在` }
 
       checks.latestBranch = latestExists.exists
-        ? { ok: true, detail: 'latest' }
-        : { ok: false, detail: '分支 latest 不存在' }
+        ? { ok: true, detail: stableBranch }
+        : { ok: false, detail: `分支 ${stableBranch} 不存在` }
 
       const expected = version.replace(/^v/, '')
       const actual = pkgVersion.version || ''
@@ -68,11 +69,12 @@ export async function handler(args, _ctx) {
       if (releaseExists.exists && latestExists.exists) {
         const mergeCheck = await invoke('git_merge_conflict_check', {
           projectPath: repo.path,
-          sourceBranch: 'latest',
-          targetBranch: releaseBranch
+          sourceBranch: stableBranch,
+          targetBranch: releaseBranch,
+          remote
         })
         checks.mergeConflict = mergeCheck.hasConflict
-          ? { ok: false, detail: 'latest → release 存在合并冲突' }
+          ? { ok: false, detail: `${stableBranch} → ${releaseBranch} 存在合并冲突：${(mergeCheck.conflicts || []).map(c => c.path).join('、')}`, conflicts: mergeCheck.conflicts }
           : { ok: true, detail: `Behind: ${mergeCheck.behind}, Ahead: ${mergeCheck.ahead}` }
       }
     } catch (e) {
@@ -7,7 +7,7 @@ export const collectConfigChangesSchema = {
   type: 'function',
   function: {
     name: 'collect_config_changes',
-    description: '扫描本次发布涉及仓库中的配置差异，输出结构化配置变更列表。',
+    description: '扫描本次发布涉及仓库中的配置差异，对 JSON/YAML/TOML/.env 按键输出新增、删除与修改（含新旧值，疑似密钥的值自动脱敏）。',
     parameters: {
       type: 'object',
       properties: {
@@ -63,11 +63,28 @@ export const applyConfigChangesSchema = {
   type: 'function',
   function: {
     name: 'apply_config_changes',
-    description: '在审批通过后，将结构化配置变更正式写入目标仓库文件。',
+    description: '在审批通过后，以三方合并方式将配置变更写入目标仓库文件（保留工作区本地修改）。可按文件或配置键选择性应用；冲突文件不会写入，会返回冲突键和 diff3 文件；写入前自动备份，可用 restore_config_backup 回退。',
     parameters: {
       type: 'object',
       properties: {
-        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        selection: {
+          type: 'array',
+          description: '可选。只应用列出的文件；省略则应用全部已收集的配置文件。',
+          items: {
+            type: 'object',
+            properties: {
+              repo_key: { type: 'string', description: '仓库 key，与 collect_config_changes 返回的 repoKey 一致。' },
+              file: { type: 'string', description: '仓库内相对路径。' },
+              keys: {
+                type: 'array',
+                items: { type: 'string' },
+                description: '可选。只应用这些配置键（如 server.port）；省略则整文件合并。'
+              }
+            },
+            required: ['repo_key', 'file']
+          }
+        }
       },
       required: ['session_id']
     }
@@ -76,7 +93,37 @@ export const applyConfigChangesSchema = {
 
 export async function applyConfigChangesHandler(args) {
   return invoke('release_apply_config_changes', {
-    sessionId: args.session_id
+    sessionId: args.session_id,
+    selection: Array.isArray(args.selection)
+      ? args.selection.map((entry) => ({
+        repoKey: entry.repo_key,
+        file: entry.file,
+        keys: Array.isArray(entry.keys) && entry.keys.length ? entry.keys : null
+      }))
+      : null
+  })
+}
+
+export const restoreConfigBackupSchema = {
+  type: 'function',
+  function: {
+    name: 'restore_config_backup',
+    description: '将 apply_config_changes 写入的配置文件恢复到应用前的备份；省略 backup_id 时使用最近一次备份。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        backup_id: { type: 'string', description: '可选。apply_config_changes 返回的 backupId。' }
+      },
+      required: ['session_id']
+    }
+  }
+}
+
+export async function restoreConfigBackupHandler(args) {
+  return invoke('release_restore_config_backup', {
+    sessionId: args.session_id,
+    backupId: args.backup_id || null
   })
 }
 
@@ -84,7 +131,7 @@ export const collectI18nChangesSchema = {
   type: 'function',
   function: {
     name: 'collect_i18n_changes',
-    description: '扫描 release 分支与 latest 的 i18n 差异，返回变更的 key 列表。',
+    description: '扫描 release 分支与 latest 的 i18n 差异（按目录约定和语言代码识别语言文件），返回新增/修改/删除的 key，以及源语言新增 key 在其他语言中的缺失翻译覆盖率报告。',
     parameters: {
       type: 'object',
       properties: {
@@ -119,7 +166,7 @@ export const generateI18nArtifactsSchema = {
   type: 'function',
   function: {
     name: 'generate_i18n_artifacts',
-    description: '基于已收集的 i18n 变更为本次发布生成 CSV 产物，并挂到 Release Session。',
+    description: '基于已收集的 i18n 变更为本次发布生成产物并挂到 Release Session：变更清单 CSV、供翻译供应商使用的 XLSX（源文本、各语言译文、上下文、变更类型）以及每个目标语言一份 XLIFF 2.0。',
     parameters: {
       type: 'object',
       properties: {
@@ -136,6 +183,29 @@ export async function generateI18nArtifactsHandler(args) {
   })
 }
 
+export const importI18nTranslationsSchema = {
+  type: 'function',
+  function: {
+    name: 'import_i18n_translations',
+    description: '导入翻译供应商返回的 XLSX 或 XLIFF 2.0 文件，将译文写回工作区中对应仓库的语言文件（JSON/YAML 会补充缺失的 key，.po 只填写已有条目）。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        file_path: { type: 'string', description: '翻译文件的绝对路径（.xlsx / .xlf / .xliff）。' }
+      },
+      required: ['session_id', 'file_path']
+    }
+  }
+}
+
+export async function importI18nTranslationsHandler(args) {
+  return invoke('release_import_i18n_translations', {
+    sessionId: args.session_id,
+    filePath: args.file_path
+  })
+}
+
 export const generateReleaseReadinessReportSchema = {
   type: 'function',
   function: {
@@ -165,7 +235,8 @@ export const executeReleaseMergeSchema = {
     parameters: {
       type: 'object',
       properties: {
-        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        parallelism: { type: 'integer', description: '可选，同时处理的仓库数；默认取工作区发布配置（4）。' }
       },
       required: ['session_id']
     }
@@ -174,7 +245,8 @@ export const executeReleaseMergeSchema = {
 
 export async function executeReleaseMergeHandler(args) {
   return invoke('release_execute_merge', {
-    sessionId: args.session_id
+    sessionId: args.session_id,
+    parallelism: args.parallelism || null
   })
 }
 
@@ -186,7 +258,8 @@ export const executePostMergeBuildSchema = {
     parameters: {
       type: 'object',
       properties: {
-        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        parallelism: { type: 'integer', description: '可选，同时处理的仓库数；默认取工作区发布配置（4）。' }
       },
       required: ['session_id']
     }
@@ -195,7 +268,29 @@ export const executePostMergeBuildSchema = {
 
 export async function executePostMergeBuildHandler(args) {
   return invoke('release_execute_post_merge_build', {
-    sessionId: args.session_id
+    sessionId: args.session_id,
+    parallelism: args.parallelism || null
+  })
+}
+
+export const cancelReleaseBuildSchema = {
+  type: 'function',
+  function: {
+    name: 'cancel_release_build',
+    description: '取消一个正在运行的构建（连同其子进程）。build_id 来自构建进度或构建结果。',
+    parameters: {
+      type: 'object',
+      properties: {
+        build_id: { type: 'string', description: '构建唯一标识。' }
+      },
+      required: ['build_id']
+    }
+  }
+}
+
+export async function cancelReleaseBuildHandler(args) {
+  return invoke('release_cancel_build', {
+    buildId: args.build_id
   })
 }
 
@@ -207,7 +302,8 @@ export const createReleaseTagSchema = {
     parameters: {
       type: 'object',
       properties: {
-        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        parallelism: { type: 'integer', description: '可选，同时处理的仓库数；默认取工作区发布配置（4）。' }
       },
       required: ['session_id']
     }
@@ -216,15 +312,175 @@ export const createReleaseTagSchema = {
 
 export async function createReleaseTagHandler(args) {
   return invoke('release_create_tag', {
+    sessionId: args.session_id,
+    parallelism: args.parallelism || null
+  })
+}
+
+export const pushReleaseLatestSchema = {
+  type: 'function',
+  function: {
+    name: 'push_release_latest',
+    description: '在审批通过后，把各仓库合并后的 latest 推送到远端；先做非 fast-forward 预检，并记录推送后的远端 SHA。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+      },
+      required: ['session_id']
+    }
+  }
+}
+
+export async function pushReleaseLatestHandler(args) {
+  return invoke('release_push_latest', {
+    sessionId: args.session_id
+  })
+}
+
+export const pushReleaseTagsSchema = {
+  type: 'function',
+  function: {
+    name: 'push_release_tags',
+    description: '在审批通过后，把本次发布的 tag 推送到远端（同一仓库多个 ref 时使用 --atomic），远端已有不同指向的同名 tag 时拒绝推送。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+      },
+      required: ['session_id']
+    }
+  }
+}
+
+export async function pushReleaseTagsHandler(args) {
+  return invoke('release_push_tags', {
+    sessionId: args.session_id
+  })
+}
+
+export const previewReleaseRollbackSchema = {
+  type: 'function',
+  function: {
+    name: 'preview_release_rollback',
+    description: '预览回滚计划：每个仓库的 latest 将被重置到的合并前 SHA，以及将删除的本次创建的 Tag。不做任何修改。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+      },
+      required: ['session_id']
+    }
+  }
+}
+
+export async function previewReleaseRollbackHandler(args) {
+  return invoke('release_rollback', {
+    sessionId: args.session_id,
+    dryRun: true
+  })
+}
+
+export const rollbackReleaseSchema = {
+  type: 'function',
+  function: {
+    name: 'rollback_release',
+    description: '在审批通过后，把 latest 重置到合并前的 SHA 并删除本次创建的 Tag，Release Session 回到 ready。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+      },
+      required: ['session_id']
+    }
+  }
+}
+
+export async function rollbackReleaseHandler(args) {
+  return invoke('release_rollback', {
+    sessionId: args.session_id,
+    dryRun: false
+  })
+}
+
+export const getPromotionStatusSchema = {
+  type: 'function',
+  function: {
+    name: 'get_promotion_status',
+    description: '查看晋级路径（如 qa → uat → prod）上每个环境部署的仓库 SHA/Tag、检查结果，以及当前能否晋级到该环境。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' }
+      },
+      required: ['session_id']
+    }
+  }
+}
+
+export async function getPromotionStatusHandler(args) {
+  return invoke('release_promotion_status', {
     sessionId: args.session_id
   })
 }
 
+export const promoteReleaseSchema = {
+  type: 'function',
+  function: {
+    name: 'promote_release',
+    description: '把本次发布的 SHA 晋级到指定环境并记录部署。只有上一环境部署的是同一批 SHA 且检查全部通过时才允许；需要审批的环境（如 prod）会消耗一次审批。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        environment: { type: 'string', description: '目标环境名称，例如 qa、uat、prod。' }
+      },
+      required: ['session_id', 'environment']
+    }
+  }
+}
+
+export async function promoteReleaseHandler(args) {
+  return invoke('release_promote', {
+    sessionId: args.session_id,
+    environment: args.environment
+  })
+}
+
+export const recordEnvironmentCheckSchema = {
+  type: 'function',
+  function: {
+    name: 'record_environment_check',
+    description: '为某环境最近一次部署记录检查结果（如 qa 的 smoke、uat 的 acceptance），检查名必须是发布配置中为该环境配置的。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        environment: { type: 'string', description: '环境名称。' },
+        check: { type: 'string', description: '检查名称。' },
+        passed: { type: 'boolean', description: '检查是否通过。' },
+        detail: { type: 'string', description: '可选，检查结果说明或报告链接。' }
+      },
+      required: ['session_id', 'environment', 'check', 'passed']
+    }
+  }
+}
+
+export async function recordEnvironmentCheckHandler(args) {
+  return invoke('release_record_environment_check', {
+    sessionId: args.session_id,
+    environment: args.environment,
+    check: args.check,
+    passed: args.passed,
+    detail: args.detail || null
+  })
+}
+
 export const generateConfluenceDraftSchema = {
   type: 'function',
   function: {
     name: 'generate_confluence_draft',
-    description: '根据 Release Session 当前结果生成发布文档草稿，并附带 i18n/config 产物信息。',
+    description: '生成发布说明草稿：按 Issue 类型与 Epic 汇总本版本 Jira Issue，关联已合并 PR（链接与作者），附配置/i18n 变更表和构建结果，同时输出 Markdown、HTML 与 Confluence storage 格式。',
     parameters: {
       type: 'object',
       properties: {
@@ -235,9 +491,50 @@ export const generateConfluenceDraftSchema = {
   }
 }
 
-export async function generateConfluenceDraftHandler(args) {
+// 发布说明的数据来源：Jira 版本 Issue 与 GitHub 已合并 PR
+export function releaseNotesSources(ctx) {
+  const jira = ctx?.jira?.config || {}
+  const project = (jira.project || 'CRMCN').split('\n').map(p => p.trim()).filter(Boolean)[0] || 'CRMCN'
+  return {
+    domain: jira.domain || null,
+    email: jira.email || null,
+    apiToken: jira.apiToken || null,
+    project,
+    githubToken: ctx?.settings?.githubToken || null
+  }
+}
+
+export async function generateConfluenceDraftHandler(args, ctx) {
   return invoke('release_generate_confluence_draft', {
-    sessionId: args.session_id
+    sessionId: args.session_id,
+    ...releaseNotesSources(ctx)
+  })
+}
+
+export const previewReleaseDocSchema = {
+  type: 'function',
+  function: {
+    name: 'preview_release_doc',
+    description: '按工作区发布文档模板渲染发布说明预览（Markdown 与 HTML），不写入产物、不改变 Session 状态；模板错误会带行号返回。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        template_source: {
+          type: 'string',
+          description: '可选，未保存的模板内容；为空时使用 release profile 中配置的模板。'
+        }
+      },
+      required: ['session_id']
+    }
+  }
+}
+
+export async function previewReleaseDocHandler(args, ctx) {
+  return invoke('release_preview_release_doc', {
+    sessionId: args.session_id,
+    templateSource: args.template_source || null,
+    ...releaseNotesSources(ctx)
   })
 }
 
@@ -245,7 +542,7 @@ export const publishConfluenceReleaseDocSchema = {
   type: 'function',
   function: {
     name: 'publish_confluence_release_doc',
-    description: '在审批通过后，正式发布 Confluence 运维发布文档，并回写页面链接。',
+    description: '在审批通过后，通过 Confluence REST API 创建或更新运维发布文档页面（目标空间取自 release profile），并回写页面链接与版本号。',
     parameters: {
       type: 'object',
       properties: {
@@ -260,9 +557,13 @@ export const publishConfluenceReleaseDocSchema = {
   }
 }
 
-export async function publishConfluenceReleaseDocHandler(args) {
+export async function publishConfluenceReleaseDocHandler(args, ctx) {
+  // Confluence 与 Jira 同站点，复用 Jira 的账号与 API Token
+  const jira = ctx?.jira?.config || {}
   return invoke('release_publish_confluence_doc', {
     sessionId: args.session_id,
-    draftId: args.draft_id || null
+    draftId: args.draft_id || null,
+    email: jira.email || null,
+    apiToken: jira.apiToken || null
   })
 }
@@ -25,13 +25,35 @@ export const createReleaseSessionSchema = {
   }
 }
 
-export async function createReleaseSessionHandler(args) {
+export async function createReleaseSessionHandler(args, ctx) {
   return invoke('release_session_create', {
     version: args.version || null,
-    environment: args.environment
+    environment: args.environment,
+    workspacePath: ctx?.settings?.workspacePath || null
   })
 }
 
+/**
+ * Resolve the workspace release profile into concrete ref names for `version`.
+ * Falls back to the default `latest` / `release/v{version}` layout when the profile can't be read.
+ */
+export async function resolveReleaseRefs(version, ctx) {
+  const plain = String(version || '').replace(/^v/, '')
+  try {
+    const result = await invoke('release_profile_read', {
+      workspacePath: ctx?.settings?.workspacePath || null,
+      version: plain
+    })
+    if (result?.resolved) return result.resolved
+  } catch { /* use defaults */ }
+  return {
+    remote: 'origin',
+    stableBranch: 'latest',
+    releaseBranch: `release/v${plain}`,
+    tagName: `release/v${plain}`
+  }
+}
+
 export const resumeReleaseSessionSchema = {
   type: 'function',
   function: {
@@ -104,6 +126,29 @@ export async function listReleaseSessionsHandler(args) {
   })
 }
 
+export const readReleaseAuditLogSchema = {
+  type: 'function',
+  function: {
+    name: 'read_release_audit_log',
+    description: '读取 Release Session 的只追加审计日志（操作人、时间、输入、SHA、结果），可选校验 hash 链是否被篡改。',
+    parameters: {
+      type: 'object',
+      properties: {
+        session_id: { type: 'string', description: 'Release Session 唯一标识。' },
+        verify: { type: 'boolean', description: '是否同时校验 hash 链完整性。' }
+      },
+      required: ['session_id']
+    }
+  }
+}
+
+export async function readReleaseAuditLogHandler(args) {
+  return invoke('release_audit_log_read', {
+    sessionId: args.session_id,
+    verify: Boolean(args.verify)
+  })
+}
+
 export const requestStepApprovalSchema = {
   type: 'function',
   function: {
@@ -137,7 +182,7 @@ export const recordStepDecisionSchema = {
   type: 'function',
   function: {
     name: 'record_step_decision',
-    description: '记录审批结果。只有 approved 后，危险步骤才允许继续执行。',
+    description: '拒绝（撤回）一条待审批记录。批准只能由用户在界面中完成，Agent 不能批准危险步骤。',
     parameters: {
       type: 'object',
       properties: {
@@ -145,8 +190,8 @@ export const recordStepDecisionSchema = {
         approval_id: { type: 'string', description: '审批记录 ID。' },
         decision: {
           type: 'string',
-          enum: ['approved', 'rejected'],
-          description: '审批结果。'
+          enum: ['rejected'],
+          description: '审批结果，只能是 rejected。'
         },
         actor: {
           type: 'string',
@@ -159,6 +204,9 @@ export const recordStepDecisionSchema = {
 }
 
 export async function recordStepDecisionHandler(args) {
+  if (args.decision !== 'rejected') {
+    return { ok: false, summary: 'Agent 不能批准危险步骤，请用户在发布面板中审批。' }
+  }
   return invoke('release_approval_decide', {
     sessionId: args.session_id,
     approvalId: args.approval_id,
@@ -3,8 +3,10 @@ import {
   createReleaseSessionState,
   createStepApproval,
   decideStepApproval,
+  getReachedMilestone,
   getStepStatus,
   isReleaseSessionReadyForStep,
+  rebaseReleaseSession,
   syncReleaseSessionToolResult
 } from '../release-session.js'
 
@@ -103,4 +105,85 @@ describe('release session workflow state', () => {
     expect(approved.approvals[0].decision).toBe('approved')
     expect(isReleaseSessionReadyForStep(approved, 'mergeLatest')).toBe(true)
   })
+
+  it('advances through execution milestones instead of a generic executing state', () => {
+    const session = createReleaseSessionState({
+      sessionId: 'session-3',
+      version: '3.8.2',
+      environment: 'production'
+    })
+
+    const ready = syncReleaseSessionToolResult(session, {
+      toolName: 'generate_release_readiness_report',
+      result: { ok: true, status: 'ready', pendingApprovals: [] }
+    })
+    const merged = syncReleaseSessionToolResult(ready, {
+      toolName: 'execute_release_merge',
+      result: { ok: true, summary: 'release -> latest 合并已完成。', results: [] }
+    })
+    const built = syncReleaseSessionToolResult(merged, {
+      toolName: 'execute_post_merge_build',
+      result: { ok: true, results: [] }
+    })
+
+    expect(ready.status).toBe('ready')
+    expect(merged.status).toBe('merging')
+    expect(built.status).toBe('built')
+    expect(getReachedMilestone(built)).toBe('built')
+
+    const withApproval = createStepApproval(built, { stepId: 'tagRelease', action: 'create_release_tag' })
+    const approved = decideStepApproval(withApproval, {
+      approvalId: withApproval.currentGate.approvalId,
+      decision: 'approved',
+      actor: 'tester'
+    })
+    expect(approved.status).toBe('built')
+  })
+  it('returns to ready after a rollback and keeps rollback out of the step order', () => {
+    let session = createReleaseSessionState({
+      sessionId: 'session-4',
+      version: '3.8.2',
+      environment: 'production'
+    })
+    for (const toolName of ['generate_release_readiness_report', 'execute_release_merge', 'execute_post_merge_build', 'create_release_tag']) {
+      session = syncReleaseSessionToolResult(session, { toolName, result: { ok: true, results: [] } })
+    }
+    expect(session.status).toBe('tagged')
+
+    const rolledBack = syncReleaseSessionToolResult(session, {
+      toolName: 'rollback_release',
+      result: { ok: true, stepId: 'rollback', results: [] }
+    })
+
+    expect(rolledBack.status).toBe('ready')
+    expect(getStepStatus(rolledBack, 'rollback')).toBe('done')
+    expect(getStepStatus(rolledBack, 'mergeLatest')).toBe('pending')
+    expect(getStepStatus(rolledBack, 'tagRelease')).toBe('pending')
+    expect(rolledBack.currentStepId).not.toBe('rollback')
+  })
+
+  it('replays local edits on top of a newer backend session and reports conflicts', () => {
+    const base = createReleaseSessionState({ sessionId: 'session-5', version: '3.8.2' })
+    base.revision = 3
+    const local = syncReleaseSessionToolResult(base, {
+      toolName: 'generate_release_readiness_report',
+      result: { ok: true, summary: 'ready', artifacts: [{ kind: 'report', path: '/tmp/report.md' }] }
+    })
+    local.steps.tagRelease = { ...local.steps.tagRelease, status: 'running' }
+    const latest = syncReleaseSessionToolResult(base, {
+      toolName: 'execute_release_merge',
+      result: { ok: true, summary: 'merged', artifacts: [{ kind: 'merge-log', path: '/tmp/merge.log' }] }
+    })
+    latest.steps.tagRelease = { ...latest.steps.tagRelease, status: 'blocked' }
+    latest.revision = 4
+
+    const { session, conflicts } = rebaseReleaseSession(base, local, latest)
+
+    expect(session.revision).toBe(4)
+    expect(getStepStatus(session, 'readinessReport')).toBe('done')
+    expect(getStepStatus(session, 'mergeLatest')).toBe('done')
+    expect(getStepStatus(session, 'tagRelease')).toBe('blocked')
+    expect(session.artifacts.map(artifact => artifact.kind).sort()).toEqual(['merge-log', 'report'])
+    expect(conflicts).toContain('tagRelease')
+  })
 })
@@ -67,7 +67,7 @@ export const RELEASE_STEP_ORDER = [
   },
   {
     id: 'mergeLatest',
-    label: '合并到 latest',
+    label: '合并到稳定分支',
     toolName: 'execute_release_merge',
     phase: 'execute',
     requiresApproval: true,
@@ -79,6 +79,14 @@ export const RELEASE_STEP_ORDER = [
     toolName: 'execute_post_merge_build',
     phase: 'execute'
   },
+  {
+    id: 'pushLatest',
+    label: '推送稳定分支',
+    toolName: 'push_release_latest',
+    phase: 'execute',
+    requiresApproval: true,
+    dangerous: true
+  },
   {
     id: 'tagRelease',
     label: '创建 Tag',
@@ -87,6 +95,14 @@ export const RELEASE_STEP_ORDER = [
     requiresApproval: true,
     dangerous: true
   },
+  {
+    id: 'pushTags',
+    label: '推送 Tag',
+    toolName: 'push_release_tags',
+    phase: 'execute',
+    requiresApproval: true,
+    dangerous: true
+  },
   {
     id: 'confluenceDraft',
     label: '文档草稿',
@@ -103,18 +119,50 @@ export const RELEASE_STEP_ORDER = [
   }
 ]
 
+/**
+ * Recovery step outside the forward order: undoes mergeLatest / tagRelease and
+ * returns the session to `ready`. It never becomes the current step.
+ */
+export const RELEASE_ROLLBACK_STEP = {
+  id: 'rollback',
+  label: '回滚发布',
+  toolName: 'rollback_release',
+  phase: 'recovery',
+  requiresApproval: true,
+  dangerous: true
+}
+
+export const RELEASE_ROLLED_BACK_STEP_IDS = ['mergeLatest', 'buildVerification', 'pushLatest', 'tagRelease', 'pushTags']
+
+const RELEASE_ALL_STEPS = [...RELEASE_STEP_ORDER, RELEASE_ROLLBACK_STEP]
+
 export const RELEASE_STEP_BY_ID = Object.fromEntries(
-  RELEASE_STEP_ORDER.map(step => [step.id, step])
+  RELEASE_ALL_STEPS.map(step => [step.id, step])
 )
 
 export const RELEASE_STEP_BY_TOOL = Object.fromEntries(
-  RELEASE_STEP_ORDER
+  RELEASE_ALL_STEPS
     .filter(step => step.toolName)
     .map(step => [step.toolName, step])
 )
 
+/**
+ * Forward milestones mirrored from the backend state machine
+ * (draft → checking → ready → merging → built → tagged → published).
+ * Each milestone after `checking` is reached once its driving step is done.
+ */
+export const RELEASE_MILESTONES = [
+  { status: 'draft', stepId: '' },
+  { status: 'checking', stepId: '' },
+  { status: 'ready', stepId: 'readinessReport' },
+  { status: 'merging', stepId: 'mergeLatest' },
+  { status: 'built', stepId: 'buildVerification' },
+  { status: 'tagged', stepId: 'tagRelease' },
+  { status: 'published', stepId: 'confluencePublish' }
+]
+
 export const RELEASE_GUARDED_TOOL_NAMES = new Set(
-  RELEASE_STEP_ORDER
+  RELEASE_ALL_STEPS
     .filter(step => step.requiresApproval || step.dangerous)
     .map(step => step.toolName)
 )
@@ -190,6 +238,15 @@ function setSessionStatus(session, status) {
   session.updatedAt = timestamp()
 }
 
+export function getReachedMilestone(session) {
+  const reached = [...RELEASE_MILESTONES]
+    .reverse()
+    .find(milestone => milestone.stepId && session?.steps?.[milestone.stepId]?.status === 'done')
+  if (reached) return reached.status
+  const anyDone = Object.values(session?.steps || {}).some(step => step.status === 'done')
+  return anyDone ? 'checking' : 'draft'
+}
+
 function clearBlockedStep(session, stepId) {
   session.blockedSteps = (session.blockedSteps || []).filter(item => item !== stepId)
 }
@@ -202,7 +259,7 @@ function addBlockedStep(session, stepId) {
 
 export function createReleaseSessionState(options = {}) {
   const steps = Object.fromEntries(
-    RELEASE_STEP_ORDER.map(step => [step.id, createReleaseStepState(step)])
+    RELEASE_ALL_STEPS.map(step => [step.id, createReleaseStepState(step)])
   )
 
   return {
@@ -299,7 +356,7 @@ export function decideStepApproval(session, options = {}) {
       summary: '审批已通过',
       detail: approval.target || ''
     })
-    setSessionStatus(nextSession, 'ready')
+    setSessionStatus(nextSession, getReachedMilestone(nextSession))
   } else {
     addBlockedStep(nextSession, approval.stepId)
     markStep(nextSession, approval.stepId, {
@@ -360,7 +417,9 @@ export function syncReleaseSessionToolResult(session, payload = {}) {
       summary: `已选择版本 ${nextSession.version || '未命名版本'}`,
       detail: nextSession.version
     })
-    setSessionStatus(nextSession, 'checking')
+    if (['draft', 'checking', 'ready'].includes(getReachedMilestone(nextSession))) {
+      setSessionStatus(nextSession, 'checking')
+    }
   }
 
   if (toolName === 'scan_pr_status') {
@@ -369,15 +428,18 @@ export function syncReleaseSessionToolResult(session, payload = {}) {
 
   if (toolName === 'generate_release_readiness_report') {
     nextSession.pendingApprovals = Array.isArray(result?.pendingApprovals) ? result.pendingApprovals : []
-    setSessionStatus(nextSession, result?.ok ? 'ready' : 'blocked')
   }
 
-  if (toolName === 'execute_release_merge' && status === 'done') {
-    setSessionStatus(nextSession, 'executing')
+  if (toolName === 'rollback_release' && status === 'done') {
+    for (const stepId of RELEASE_ROLLED_BACK_STEP_IDS) {
+      markStep(nextSession, stepId, { status: 'pending', summary: '已回滚', detail: '', result: null })
+      clearBlockedStep(nextSession, stepId)
+    }
+    setSessionStatus(nextSession, getReachedMilestone(nextSession))
   }
 
-  if (toolName === 'publish_confluence_release_doc' && status === 'done') {
-    setSessionStatus(nextSession, 'completed')
+  if (status === 'done' && RELEASE_MILESTONES.some(milestone => milestone.stepId === step.id)) {
+    setSessionStatus(nextSession, getReachedMilestone(nextSession))
   }
 
   for (const artifact of result?.artifacts || []) {
@@ -391,6 +453,56 @@ export function syncReleaseSessionToolResult(session, payload = {}) {
   return nextSession
 }
 
+const BACKEND_OWNED_SESSION_FIELDS = new Set(['approvals', 'promotions', 'revision', 'workspacePath', 'updatedAt', 'currentStepId'])
+
+function sameValue(left, right) {
+  return JSON.stringify(left ?? null) === JSON.stringify(right ?? null)
+}
+
+function artifactKey(artifact) {
+  return `${artifact.stepId}:${artifact.kind}:${artifact.path || artifact.title || ''}`
+}
+
+/**
+ * Re-apply the front-end edits made since `base` (the last session synced with the backend)
+ * on top of `latest`. A step or field the backend also changed keeps the backend value and is
+ * reported in `conflicts` instead of being overwritten.
+ */
+export function rebaseReleaseSession(base, local, latest) {
+  const nextSession = shallowClone(latest)
+  const conflicts = []
+
+  for (const [stepId, step] of Object.entries(local.steps || {})) {
+    const baseStep = base.steps?.[stepId]
+    if (sameValue(step, baseStep)) continue
+    if (sameValue(latest.steps?.[stepId], baseStep)) {
+      nextSession.steps[stepId] = { ...step }
+    } else if (!sameValue(latest.steps?.[stepId], step)) {
+      conflicts.push(stepId)
+    }
+  }
+
+  const baseArtifacts = new Set((base.artifacts || []).map(artifactKey))
+  for (const artifact of local.artifacts || []) {
+    if (!baseArtifacts.has(artifactKey(artifact))) {
+      upsertArtifact(nextSession, artifact)
+    }
+  }
+
+  for (const [field, value] of Object.entries(local)) {
+    if (field === 'steps' || field === 'artifacts' || BACKEND_OWNED_SESSION_FIELDS.has(field)) continue
+    if (sameValue(value, base[field])) continue
+    if (sameValue(latest[field], base[field])) {
+      nextSession[field] = value
+    } else if (!sameValue(latest[field], value)) {
+      conflicts.push(field)
+    }
+  }
+
+  recomputeCurrentStepId(nextSession)
+  return { session: nextSession, conflicts }
+}
+
 export function summarizeReleaseSession(session) {
   const completed = getCompletedReleaseSteps(session)
   const blocked = session?.blockedSteps || []
@@ -170,13 +170,30 @@ export function handleReleaseToolEnd({ toolName, result }) {
   }
 
   if (toolName === 'execute_post_merge_build' && result?.ok) {
+    return {
+      suppressNextAssistantText: true,
+      interaction: {
+        id: `interaction-approval-pushLatest-${Date.now()}`,
+        type: 'approval-card',
+        title: '确认推送 latest',
+        description: result.summary || '构建验证通过，请确认是否把合并后的 latest 推送到远端。',
+        actions: buildApprovalActions('pushLatest', '确认推送'),
+        meta: {
+          severity: 'high',
+          approvalLabel: '需要人工授权'
+        }
+      }
+    }
+  }
+
+  if (toolName === 'push_release_latest' && result?.ok) {
     return {
       suppressNextAssistantText: true,
       interaction: {
         id: `interaction-approval-tagRelease-${Date.now()}`,
         type: 'approval-card',
         title: '确认创建 Tag',
-        description: result.summary || '构建验证通过，请确认是否继续创建 release tag。',
+        description: result.summary || 'latest 已推送，请确认是否继续创建 release tag。',
         actions: buildApprovalActions('tagRelease', '确认创建 Tag'),
         meta: {
           severity: 'high',
@@ -187,10 +204,27 @@ export function handleReleaseToolEnd({ toolName, result }) {
   }
 
   if (toolName === 'create_release_tag' && result?.ok) {
+    return {
+      suppressNextAssistantText: true,
+      interaction: {
+        id: `interaction-approval-pushTags-${Date.now()}`,
+        type: 'approval-card',
+        title: '确认推送 Tag',
+        description: result.summary || 'Tag 已在本地创建，请确认是否推送到远端。',
+        actions: buildApprovalActions('pushTags', '确认推送'),
+        meta: {
+          severity: 'high',
+          approvalLabel: '需要人工授权'
+        }
+      }
+    }
+  }
+
+  if (toolName === 'push_release_tags' && result?.ok) {
     return {
       messages: [
         {
-          text: result.summary || 'Tag 创建完成，建议继续生成发布文档草稿。',
+          text: result.summary || 'Tag 已推送到远端，建议继续生成发布文档草稿。',
           kind: 'notice',
           status: 'success'
         }
@@ -198,6 +232,23 @@ export function handleReleaseToolEnd({ toolName, result }) {
     }
   }
 
+  if (toolName === 'preview_release_rollback' && result?.ok) {
+    return {
+      suppressNextAssistantText: true,
+      interaction: {
+        id: `interaction-approval-rollback-${Date.now()}`,
+        type: 'approval-card',
+        title: '确认回滚发布',
+        description: result.summary || '请确认是否把 latest 重置到合并前的 SHA，并删除本次创建的 Tag。',
+        actions: buildApprovalActions('rollback', '确认回滚'),
+        meta: {
+          severity: 'high',
+          approvalLabel: '需要人工授权'
+        }
+      }
+    }
+  }
+
   const stepId = result?.stepId
   if (result?.ok === false && stepId) {
     return {
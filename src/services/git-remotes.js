@@ -0,0 +1,19 @@
+import { invoke } from '@tauri-apps/api/core'
+
+/**
+ * GitHub repository a local checkout opens PRs against: the `prRemote` reported by
+ * `git_list_remotes` (`upstream` in a fork, otherwise `origin`), or `remoteName` when given.
+ * Returns null when that remote is missing or not hosted on GitHub / GitHub Enterprise.
+ */
+export async function resolveGithubRemote(projectPath, remoteName) {
+  const { remotes = [], prRemote } = await invoke('git_list_remotes', { projectPath })
+  const remote = remotes.find(r => r.name === (remoteName || prRemote))
+  if (!remote?.parsed || remote.parsed.forge !== 'github') return null
+  return {
+    remote: remote.name,
+    owner: remote.parsed.owner,
+    repo: remote.parsed.repo,
+    fullName: remote.fullName,
+    apiBaseUrl: remote.apiBaseUrl
+  }
+}
@@ -2,6 +2,7 @@ import { defineStore } from 'pinia'
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
 import { useSettingsStore } from './settings'
+import { resolveGithubRemote } from '../services/git-remotes.js'
 
 export const useGithubStore = defineStore('github', () => {
   const currentUser = ref('')
@@ -25,13 +26,6 @@ export const useGithubStore = defineStore('github', () => {
     return ''
   }
 
-  function parseOwnerRepo(remoteInfo) {
-    const normalized = (remoteInfo || '').trim().replace(/\.git$/i, '')
-    const parts = normalized.split('/').filter(Boolean)
-    if (parts.length < 2) return null
-    return { owner: parts[0], repo: parts[1] }
-  }
-
   function formatTime(dateStr) {
     if (!dateStr) return ''
     const date = new Date(dateStr)
@@ -87,12 +81,11 @@ export const useGithubStore = defineStore('github', () => {
           if (!cur) return
           const [, path] = cur
           try {
-            const remoteInfo = await invoke('git_get_remote_info', { projectPath: path }).catch(() => '')
-            const parsed = parseOwnerRepo(remoteInfo)
-            if (!parsed) continue
-            const { owner, repo } = parsed
+            const remote = await resolveGithubRemote(path).catch(() => null)
+            if (!remote) continue
+            const { owner, repo, apiBaseUrl } = remote
 
-            const res = await invoke('github_list_open_prs', { owner, repo, token: settings.githubToken })
+            const res = await invoke('github_list_open_prs', { owner, repo, token: settings.githubToken, apiBaseUrl })
             if (res.status !== 200) continue
             const prs = JSON.parse(res.body || '[]') || []
 
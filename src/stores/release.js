@@ -3,6 +3,7 @@
  */
 
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { defineStore } from 'pinia'
 import { computed, reactive, ref } from 'vue'
 import { useSettingsStore } from './settings'
@@ -12,6 +13,8 @@ import { routeAgentIntent } from '../agent/router.js'
 import { createAgentRuntime } from '../agent/runtime.js'
 import { pushTraceEntry } from '../agent/tracing.js'
 import { TOOL_HANDLERS } from '../agent/tools/index.js'
+import { importI18nTranslationsHandler, previewReleaseDocHandler } from '../agent/tools/release-ops.js'
+import { resolveReleaseRefs } from '../agent/tools/release-session.js'
 import { getWorkflowTools, resolveAgentWorkflow } from '../agent/workflows/index.js'
 import {
   RELEASE_GUARDED_TOOL_NAMES,
@@ -23,6 +26,7 @@ import {
   decideStepApproval,
   getCompletedReleaseSteps,
   isReleaseSessionReadyForStep,
+  rebaseReleaseSession,
   syncReleaseSessionToolResult
 } from '../agent/workflows/release-session.js'
 
@@ -68,7 +72,12 @@ export const useReleaseStore = defineStore('release', () => {
     'apply_config_changes',
     'execute_release_merge',
     'execute_post_merge_build',
+    'push_release_latest',
     'create_release_tag',
+    'push_release_tags',
+    'rollback_release',
+    'promote_release',
+    'record_environment_check',
     'generate_confluence_draft',
     'publish_confluence_release_doc'
   ]
@@ -86,6 +95,16 @@ export const useReleaseStore = defineStore('release', () => {
   const releaseSession = ref(createReleaseSessionState({
     environment: environment.value
   }))
+  // Per-repo progress of the running merge/build/tag step, fed by `release-progress-{sessionId}` events.
+  const repoProgress = ref({ stepId: '', completed: 0, total: 0, repos: {} })
+  // Recent output lines of running builds by build id, fed by `release-build-log-{buildId}` events.
+  const buildLogs = ref({})
+  const BUILD_LOG_LINES = 200
+  let progressSessionId = ''
+  // Last copy known to match the backend, the base for replaying local edits after a stale write.
+  let syncedReleaseSession = null
+  const sessionConflicts = ref([])
+  let unlistenProgress = null
 
   function getRuntimeState() {
     return {
@@ -129,24 +148,137 @@ export const useReleaseStore = defineStore('release', () => {
   async function persistReleaseSession() {
     if (!releaseSession.value?.sessionId) return
     try {
-      await invoke('release_session_update', {
-        session: releaseSession.value
-      })
-    } catch {
-      // Keep the front-end session authoritative when the persistence layer is unavailable.
+      await writeReleaseSession()
+    } catch (error) {
+      if (!String(error).includes('revision is stale')) return
+      // The backend recorded a step outcome in between: replay the local edits on top of its copy.
+      try {
+        const latest = await invoke('release_session_read', {
+          sessionId: releaseSession.value.sessionId
+        })
+        const latestSession = normalizeReleaseSession(latest?.session || latest)
+        const { session, conflicts } = rebaseReleaseSession(
+          syncedReleaseSession || latestSession,
+          releaseSession.value,
+          latestSession
+        )
+        releaseSession.value = session
+        syncedReleaseSession = latestSession
+        sessionConflicts.value = conflicts
+        await writeReleaseSession()
+      } catch {
+        // Keep the front-end session authoritative when the persistence layer is unavailable.
+      }
+    }
+  }
+
+  async function writeReleaseSession() {
+    const result = await invoke('release_session_update', {
+      session: releaseSession.value
+    })
+    const revision = result?.session?.revision
+    if (typeof revision === 'number' && releaseSession.value) {
+      releaseSession.value.revision = revision
+      syncedReleaseSession = normalizeReleaseSession(result.session)
     }
   }
 
   function applyReleaseSession(nextSession, options = {}) {
     const { persist = true } = options
     releaseSession.value = normalizeReleaseSession(nextSession)
+    if (!persist) {
+      syncedReleaseSession = JSON.parse(JSON.stringify(releaseSession.value))
+      sessionConflicts.value = []
+    }
     version.value = releaseSession.value.version || version.value
     sessionActive.value = mode.value === 'release' || Boolean(releaseSession.value.sessionId)
+    void watchRepoProgress(releaseSession.value.sessionId)
     if (persist) {
       void persistReleaseSession()
     }
   }
 
+  async function watchBuildLog(buildId, repoKey) {
+    if (buildLogs.value[buildId]) return
+    buildLogs.value = { ...buildLogs.value, [buildId]: { repoKey, lines: [], status: 'running' } }
+    let unlisten = null
+    try {
+      unlisten = await listen(`release-build-log-${buildId}`, ({ payload }) => {
+        const current = buildLogs.value[buildId]
+        if (!current) return
+        const next = payload.done
+          ? { ...current, status: payload.status }
+          : { ...current, lines: [...current.lines, payload.line].slice(-BUILD_LOG_LINES) }
+        buildLogs.value = { ...buildLogs.value, [buildId]: next }
+        if (payload.done) unlisten?.()
+      })
+    } catch {
+      // Without the event bridge the build result still carries the log tail and log path.
+    }
+  }
+
+  async function cancelBuild(buildId) {
+    return invoke('release_cancel_build', { buildId })
+  }
+
+  async function readReleaseTemplate(name = null) {
+    return invoke('release_template_read', { workspacePath: settings.workspacePath || null, name })
+  }
+
+  async function validateReleaseTemplate(source) {
+    return invoke('release_template_validate', { source })
+  }
+
+  async function saveReleaseTemplate(name, source) {
+    return invoke('release_template_save', { workspacePath: settings.workspacePath || '', name, source })
+  }
+
+  async function previewReleaseDoc(templateSource = null) {
+    const sessionId = releaseSession.value?.sessionId
+    if (!sessionId) return { ok: false, summary: '当前没有 Release Session。' }
+    return previewReleaseDocHandler({ session_id: sessionId, template_source: templateSource }, { settings, jira })
+  }
+
+  async function importI18nTranslations(filePath) {
+    const sessionId = releaseSession.value?.sessionId
+    if (!sessionId) return { ok: false, summary: '当前没有 Release Session。' }
+    return importI18nTranslationsHandler({ session_id: sessionId, file_path: filePath })
+  }
+
+  async function watchRepoProgress(sessionId) {
+    if (sessionId === progressSessionId) return
+    unlistenProgress?.()
+    unlistenProgress = null
+    progressSessionId = sessionId || ''
+    repoProgress.value = { stepId: '', completed: 0, total: 0, repos: {} }
+    buildLogs.value = {}
+    if (!sessionId) return
+    try {
+      const unlisten = await listen(`release-progress-${sessionId}`, ({ payload }) => {
+        const sameStep = repoProgress.value.stepId === payload.stepId
+        repoProgress.value = {
+          stepId: payload.stepId,
+          completed: payload.completed,
+          total: payload.total,
+          repos: {
+            ...(sameStep ? repoProgress.value.repos : {}),
+            [payload.repoKey]: { status: payload.status, result: payload.result || null }
+          }
+        }
+        if (payload.status === 'running' && payload.result?.buildId) {
+          void watchBuildLog(payload.result.buildId, payload.repoKey)
+        }
+      })
+      if (progressSessionId === sessionId) {
+        unlistenProgress = unlisten
+      } else {
+        unlisten()
+      }
+    } catch {
+      // Progress is cosmetic; the step result still arrives through the command response.
+    }
+  }
+
   async function ensureReleaseSession(options = {}) {
     if (releaseSession.value?.sessionId) {
       return releaseSession.value
@@ -155,7 +287,8 @@ export const useReleaseStore = defineStore('release', () => {
     try {
       const result = await invoke('release_session_create', {
         version: options.version || null,
-        environment: options.environment || environment.value
+        environment: options.environment || environment.value,
+        workspacePath: settings.workspacePath || null
       })
       applyReleaseSession(result?.session || result, { persist: false })
       return releaseSession.value
@@ -186,6 +319,9 @@ export const useReleaseStore = defineStore('release', () => {
     releaseSession.value = createReleaseSessionState({
       environment: environment.value
     })
+    syncedReleaseSession = null
+    sessionConflicts.value = []
+    void watchRepoProgress('')
   }
 
   function setMode(nextMode, options = {}) {
@@ -258,7 +394,7 @@ export const useReleaseStore = defineStore('release', () => {
   const pipelineStatus = computed(() => {
     if (!sessionActive.value) return 'idle'
     if (['blocked', 'failed'].includes(releaseSession.value.status)) return 'blocked'
-    if (['ready', 'completed'].includes(releaseSession.value.status)) return 'pass'
+    if (['ready', 'published'].includes(releaseSession.value.status)) return 'pass'
     return 'running'
   })
 
@@ -302,7 +438,7 @@ export const useReleaseStore = defineStore('release', () => {
   })
   const configChanges = computed(() => toolResults.collect_config_changes?.changes || [])
   const i18nArtifacts = computed(() =>
-    toolResults.generate_i18n_artifacts?.artifacts || releaseSession.value.artifacts.filter(artifact => artifact.kind === 'i18n-csv')
+    toolResults.generate_i18n_artifacts?.artifacts || releaseSession.value.artifacts.filter(artifact => artifact.kind?.startsWith('i18n-'))
   )
   const releaseArtifacts = computed(() => releaseSession.value.artifacts || [])
   const approvals = computed(() => releaseSession.value.approvals || [])
@@ -331,7 +467,7 @@ export const useReleaseStore = defineStore('release', () => {
     }
 
     const status = String(releaseSession.value?.status || '')
-    return Boolean(releaseSession.value?.sessionId) && !['completed', 'cancelled'].includes(status)
+    return Boolean(releaseSession.value?.sessionId) && !['published', 'aborted'].includes(status)
   }
 
   function isReleaseContinuationMessage(userText) {
@@ -461,7 +597,7 @@ export const useReleaseStore = defineStore('release', () => {
     if (stepId === 'i18nArtifacts') {
       return { session_id: sessionId }
     }
-    if (stepId === 'mergeLatest' || stepId === 'buildVerification' || stepId === 'tagRelease' || stepId === 'confluenceDraft' || stepId === 'confluencePublish') {
+    if (['mergeLatest', 'buildVerification', 'pushLatest', 'tagRelease', 'pushTags', 'rollback', 'confluenceDraft', 'confluencePublish'].includes(stepId)) {
       return { session_id: sessionId }
     }
     if (stepId === 'readinessReport') {
@@ -495,11 +631,40 @@ export const useReleaseStore = defineStore('release', () => {
         actor: 'chat-user'
       })
     } else if (isApprove) {
-      nextSession = createStepApproval(releaseSession.value, {
+      const releaseVersion = releaseSession.value.version
+      const approvalRequest = {
         stepId,
         action: RELEASE_STEP_BY_ID[stepId]?.toolName || stepId,
-        target: releaseSession.value.version ? `release/v${releaseSession.value.version}` : '',
+        target: releaseVersion ? (await resolveReleaseRefs(releaseVersion, { settings })).releaseBranch : '',
         summary: `等待人工确认 ${RELEASE_STEP_BY_ID[stepId]?.label || stepId}`
+      }
+      // Dangerous steps are gated on the backend, so the click must become a recorded approval there.
+      let approvalId = ''
+      try {
+        const created = await invoke('release_approval_create', {
+          sessionId: releaseSession.value.sessionId,
+          ...approvalRequest
+        })
+        approvalId = created?.approval?.approvalId || ''
+        if (approvalId) {
+          await invoke('release_approval_decide', {
+            sessionId: releaseSession.value.sessionId,
+            approvalId,
+            decision: 'approved',
+            actor: 'chat-user'
+          })
+        }
+      } catch {
+        // The backend gate will refuse the step and surface an approval-required result.
+      }
+      const withApproval = createStepApproval(releaseSession.value, {
+        ...approvalRequest,
+        approvalId: approvalId || undefined
+      })
+      nextSession = decideStepApproval(withApproval, {
+        approvalId: withApproval.currentGate.approvalId,
+        decision: 'approved',
+        actor: 'chat-user'
       })
     }
 
@@ -519,7 +684,7 @@ export const useReleaseStore = defineStore('release', () => {
       await executeReleaseStep('buildVerification')
     }
 
-    if (result?.ok && stepId === 'tagRelease') {
+    if (result?.ok && stepId === 'pushTags') {
       await executeReleaseStep('confluenceDraft')
     }
   }
@@ -662,6 +827,15 @@ export const useReleaseStore = defineStore('release', () => {
     version,
     sessionActive,
     releaseSession,
+    sessionConflicts,
+    repoProgress,
+    buildLogs,
+    cancelBuild,
+    readReleaseTemplate,
+    validateReleaseTemplate,
+    saveReleaseTemplate,
+    previewReleaseDoc,
+    importI18nTranslations,
     approvals,
     currentGate,
     releaseArtifacts,